@@ -9,5 +9,5 @@ fn main() {
 // This example uses anyhow
 fn do_check() -> Result<Resource, anyhow::Error> {
     // Do something which returns an error.
-    return Err(anyhow!("something really bad happened"));
+    Err(anyhow!("something really bad happened"))
 }
@@ -0,0 +1,25 @@
+use std::error::Error;
+use std::time::Duration;
+
+use nagiosplugin::config_generator::{Argument, CommandDescription};
+use nagiosplugin::{plugin_main, Metric, Resource, ServiceState, TriggerIfValue};
+
+plugin_main!(
+    do_check,
+    error_state = ServiceState::Critical,
+    timeout = Duration::from_secs(10),
+    timeout_flag,
+    icinga_config = CommandDescription::new("plugin_main").with_argument(
+        Argument::new("--timeout")
+            .with_value("$plugin_main_timeout$")
+            .with_description("Seconds before the check is aborted"),
+    ),
+);
+
+fn do_check() -> Result<Resource, Box<dyn Error>> {
+    let resource = Resource::new("foo")
+        .with_description("This is a simple test plugin")
+        .with_result(Metric::new("test", 15).with_thresholds(20, 50, TriggerIfValue::Greater));
+
+    Ok(resource)
+}
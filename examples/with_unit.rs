@@ -1,6 +1,8 @@
 use std::error::Error;
 
-use nagiosplugin::{safe_run, Metric, Resource, ServiceState, TriggerIfValue, Unit, UnitString};
+use nagiosplugin::{
+    safe_run, unit, Metric, Resource, ServiceState, TriggerIfValue, Unit, UnitString,
+};
 
 fn main() {
     safe_run(do_check, ServiceState::Critical).print_and_exit()
@@ -13,6 +15,9 @@ fn do_check() -> Result<Resource, Box<dyn Error>> {
     // Use new_unchecked only if you know what you are doing
     let _custom_unit = Unit::Other(UnitString::new_unchecked("km"));
 
+    // unit! validates the string at compile time, so there is nothing to handle at runtime
+    let _custom_unit = unit!("km");
+
     let resource = Resource::new("foo")
         .with_description("This is a simple test plugin")
         .with_result(
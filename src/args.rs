@@ -0,0 +1,117 @@
+//! Reads single options from the raw arguments for the `from_args` functions, leaving all other
+//! arguments to the plugin.
+
+/// Options of the monitoring plugin guidelines which take a value.
+const STANDARD_VALUE_OPTIONS: &[&str] = &[
+    "-H",
+    "--hostname",
+    "-p",
+    "--port",
+    "-w",
+    "--warning",
+    "-c",
+    "--critical",
+    "-t",
+    "--timeout",
+];
+
+/// The options to look for in [scan].
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Options<'a> {
+    /// Options which take a value, either as the next argument or separated by `=`.
+    pub(crate) values: &'a [&'a str],
+    /// Options without a value. A value separated by `=` is returned, but the next argument is
+    /// never taken.
+    pub(crate) flags: &'a [&'a str],
+    /// Other options of the plugin which take a value, in addition to the standard ones. Their
+    /// values are skipped, so e.g. the `-6` in `--label -6` isn't taken for a flag.
+    pub(crate) skip: &'a [&'a str],
+}
+
+/// Returns the given options with their values in the order they appear. Arguments after `--` are
+/// ignored, as are the values of the standard plugin options `-H`, `-p`, `-w`, `-c` and `-t` and
+/// their long forms. Returns the option as error if its value is missing.
+pub(crate) fn scan<I, S>(
+    args: I,
+    options: Options<'_>,
+) -> Result<Vec<(String, Option<String>)>, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut found = Vec::new();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let arg = arg.as_ref();
+        if arg == "--" {
+            break;
+        }
+        let (key, inline) = match arg.split_once('=') {
+            Some((key, value)) => (key, Some(value.to_owned())),
+            None => (arg, None),
+        };
+
+        if options.flags.contains(&key) {
+            found.push((key.to_owned(), inline));
+        } else if options.values.contains(&key) {
+            let value = match inline {
+                Some(value) => value,
+                None => match args.next() {
+                    Some(value) => value.as_ref().to_owned(),
+                    None => return Err(key.to_owned()),
+                },
+            };
+            found.push((key.to_owned(), Some(value)));
+        } else if inline.is_none()
+            && (STANDARD_VALUE_OPTIONS.contains(&key) || options.skip.contains(&key))
+        {
+            args.next();
+        }
+    }
+
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        let options = Options {
+            values: &["--include", "-t"],
+            flags: &["-6", "--list"],
+            skip: &["--label"],
+        };
+        let found = scan(
+            [
+                "check",
+                "-w",
+                "-6",
+                "--label",
+                "--list",
+                "--include",
+                "a",
+                "-t=5",
+                "--list=json",
+                "-6",
+                "--",
+                "-6",
+            ],
+            options,
+        )
+        .unwrap();
+
+        assert_eq!(
+            found,
+            vec![
+                ("--include".to_owned(), Some("a".to_owned())),
+                ("-t".to_owned(), Some("5".to_owned())),
+                ("--list".to_owned(), Some("json".to_owned())),
+                ("-6".to_owned(), None),
+            ]
+        );
+        assert_eq!(scan(["--include"], options), Err("--include".to_owned()));
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{CheckResult, Metric, ServiceState, StateFile};
+
+/// Evaluates metrics against their own history instead of static thresholds, for values like
+/// request rates which are only suspicious if they differ from what is usual. The last values are
+/// kept in a [StateFile] under `<name>.baseline`; a value is a warning or critical if it deviates
+/// from their mean by at least the given number of standard deviations (z-score).
+///
+/// Static thresholds of the metric are still evaluated, the worse state wins. Until enough values
+/// are collected, only the static thresholds apply.
+///
+/// ## Example
+///
+/// ```no_run
+/// use nagiosplugin::{state_dir, Baseline, Metric, Resource, StateFile};
+///
+/// let mut state = StateFile::open(state_dir("check_web").unwrap().join("state")).unwrap();
+/// let result = Baseline::new(3.0, 5.0).evaluate(&mut state, Metric::new("requests", 1520.0));
+/// state.save().unwrap();
+///
+/// let resource = Resource::new("web").with_result(result);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Baseline {
+    warning: Option<f64>,
+    critical: Option<f64>,
+    window: usize,
+    min_samples: usize,
+}
+
+impl Baseline {
+    /// Creates an evaluator with the given z-scores, a window of 100 values and at least 10
+    /// values before alerting.
+    pub fn new(warning: impl Into<Option<f64>>, critical: impl Into<Option<f64>>) -> Self {
+        Self {
+            warning: warning.into(),
+            critical: critical.into(),
+            window: 100,
+            min_samples: 10,
+        }
+    }
+
+    /// Sets how many of the last values form the baseline.
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// Sets how many values have to be collected before deviations are reported.
+    pub fn with_min_samples(mut self, min_samples: usize) -> Self {
+        self.min_samples = min_samples.max(2);
+        self
+    }
+
+    /// Evaluates the metric against the baseline and adds its value to the baseline afterwards.
+    /// Values which are not finite are neither evaluated nor recorded.
+    pub fn evaluate(&self, state: &mut StateFile, metric: Metric<f64>) -> CheckResult {
+        let key = format!("{}.baseline", metric.name);
+        let name = metric.name.clone();
+        let value = metric.value;
+        let mut result = CheckResult::from(metric);
+        if !value.is_finite() {
+            return result;
+        }
+
+        let mut history: Vec<f64> = state
+            .get(&key)
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|v| v.parse().ok())
+            .collect();
+
+        if let Some((deviation_state, message)) = self.deviation(&name, value, &history) {
+            if deviation_state > result.state.unwrap_or(ServiceState::Ok) {
+                result.state = Some(deviation_state);
+                result.message = Some(message);
+            } else {
+                result.details.push(message);
+            }
+        }
+
+        history.push(value);
+        let start = history.len().saturating_sub(self.window);
+        let history: Vec<_> = history[start..].iter().map(f64::to_string).collect();
+        state.set(key, history.join(","));
+
+        result
+    }
+
+    fn deviation(&self, name: &str, value: f64, history: &[f64]) -> Option<(ServiceState, String)> {
+        if history.len() < self.min_samples {
+            return None;
+        }
+
+        let n = history.len() as f64;
+        let mean = history.iter().sum::<f64>() / n;
+        let stddev = (history.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+        if stddev == 0.0 {
+            return None;
+        }
+
+        let z = (value - mean) / stddev;
+        let exceeds = |limit: Option<f64>| limit.is_some_and(|limit| z.abs() >= limit);
+        let state = if exceeds(self.critical) {
+            ServiceState::Critical
+        } else if exceeds(self.warning) {
+            ServiceState::Warning
+        } else {
+            return None;
+        };
+
+        Some((
+            state,
+            format!(
+                "metric '{}' is {}: value '{}' deviates {:.1} standard deviations from the baseline of '{:.2}'",
+                name, state, value, z, mean
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TriggerIfValue;
+
+    #[test]
+    fn test_evaluate() {
+        let mut state = StateFile::open("/nonexistent/state").unwrap();
+        let baseline = Baseline::new(2.0, 4.0).with_window(4).with_min_samples(4);
+
+        for value in [10.0, 12.0, 10.0, 12.0] {
+            let result = baseline.evaluate(&mut state, Metric::new("rps", value));
+            assert_eq!(result.state, None);
+        }
+        assert_eq!(state.get("rps.baseline"), Some("10,12,10,12"));
+
+        let result = baseline.evaluate(&mut state, Metric::new("rps", 14.5));
+        assert_eq!(result.state, Some(ServiceState::Warning));
+        assert_eq!(
+            result.message.as_deref(),
+            Some("metric 'rps' is WARNING: value '14.5' deviates 3.5 standard deviations from the baseline of '11.00'")
+        );
+        assert_eq!(state.get("rps.baseline"), Some("12,10,12,14.5"));
+
+        let metric = Metric::new("rps", 100.0).with_thresholds(50.0, 80.0, TriggerIfValue::Greater);
+        let result = baseline.evaluate(&mut state, metric);
+        assert_eq!(result.state, Some(ServiceState::Critical));
+        assert_eq!(result.details.len(), 1);
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::sub_check::{outcome_results, run_detached, Outcome};
+use crate::{Resource, ServiceState};
+
+/// Runs the same check against many targets, e.g. all endpoints of a service, and aggregates them
+/// into a single [Resource]. At most [BulkCheck::with_concurrency] targets are checked at the same
+/// time. Results are reported in the order of the targets, with messages prefixed with
+/// `<target>: ` and performance data labels with `<target>_`, like [crate::SubChecks] does.
+///
+/// ## Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use nagiosplugin::{BulkCheck, Metric, Resource, ServiceState, TriggerIfValue};
+///
+/// let endpoints = vec!["api1", "api2", "api3"];
+/// let resource = BulkCheck::new("endpoints")
+///     .with_concurrency(2)
+///     .with_timeout(Duration::from_secs(5))
+///     .run(endpoints, |endpoint: &&str| {
+///         let errors = if *endpoint == "api2" { 12 } else { 0 };
+///         Ok::<_, String>(Resource::new(*endpoint).with_result(
+///             Metric::new("errors", errors).with_thresholds(5, 10, TriggerIfValue::Greater),
+///         ))
+///     });
+///
+/// let (state, output) = resource.nagios_result();
+/// assert_eq!(state, ServiceState::Critical);
+/// assert!(output.contains("api2: metric 'errors' is CRITICAL"));
+/// ```
+///
+/// ## Timeouts
+///
+/// Each target is checked on a thread of its own. A check exceeding the timeout is reported as
+/// [ServiceState::Unknown] and its thread is abandoned, so it no longer counts against the
+/// concurrency limit.
+#[derive(Debug, Clone)]
+pub struct BulkCheck {
+    name: String,
+    concurrency: usize,
+    timeout: Option<Duration>,
+    error_state: ServiceState,
+}
+
+impl BulkCheck {
+    /// Creates an instance which checks up to 8 targets at the same time without timeout. The
+    /// given name is used for the aggregated [Resource].
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            concurrency: 8,
+            timeout: Default::default(),
+            error_state: ServiceState::Unknown,
+        }
+    }
+
+    /// Sets the number of targets which are checked at the same time, at least 1.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets the time the check of a single target may take.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the state which is reported for a target whose check returned an error or panicked.
+    /// Defaults to [ServiceState::Unknown].
+    pub fn with_error_state(mut self, state: ServiceState) -> Self {
+        self.error_state = state;
+        self
+    }
+
+    /// Checks all targets and returns the aggregated [Resource]. Targets are named by their
+    /// [fmt::Display] implementation.
+    pub fn run<T, E, F>(self, targets: impl IntoIterator<Item = T>, check: F) -> Resource
+    where
+        T: fmt::Display + Send + 'static,
+        E: fmt::Display + Send + 'static,
+        F: Fn(&T) -> Result<Resource, E> + Send + Sync + 'static,
+    {
+        let queue: VecDeque<_> = targets.into_iter().enumerate().collect();
+        let count = queue.len();
+        let queue = Mutex::new(queue);
+        let outcomes = Mutex::new((0..count).map(|_| None).collect::<Vec<_>>());
+        let check = Arc::new(check);
+
+        thread::scope(|scope| {
+            for _ in 0..self.concurrency.min(count) {
+                scope.spawn(|| loop {
+                    let Some((i, target)) = lock(&queue).pop_front() else {
+                        break;
+                    };
+                    let name = target.to_string();
+                    let check = Arc::clone(&check);
+                    let outcome: Outcome<E> = run_detached(move || check(&target), self.timeout);
+                    lock(&outcomes)[i] = Some((name, outcome));
+                });
+            }
+        });
+
+        let outcomes = outcomes.into_inner().unwrap_or_else(|e| e.into_inner());
+        outcomes.into_iter().flatten().fold(
+            Resource::new(self.name),
+            |resource, (name, outcome)| {
+                resource.with_results(outcome_results(&name, outcome, self.error_state))
+            },
+        )
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    // Workers only push and pop whole entries, so a poisoned lock is still consistent.
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Metric;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_bulk_check_run() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let (r, m) = (Arc::clone(&running), Arc::clone(&max_running));
+
+        let (state, output) = BulkCheck::new("hosts")
+            .with_concurrency(2)
+            .with_timeout(Duration::from_millis(200))
+            .run(1..=5, move |&n: &i32| {
+                if n == 4 {
+                    thread::sleep(Duration::from_millis(500));
+                } else {
+                    let now = r.fetch_add(1, Ordering::SeqCst) + 1;
+                    m.fetch_max(now, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(10));
+                    r.fetch_sub(1, Ordering::SeqCst);
+                }
+                match n {
+                    3 => Err("refused"),
+                    n => Ok(Resource::new("host").with_result(Metric::new("n", n))),
+                }
+            })
+            .nagios_result();
+
+        assert_eq!(state, ServiceState::Unknown);
+        assert_eq!(
+            output,
+            "hosts is UNKNOWN\n\n\
+             3: refused\n\
+             4: timed out after 0.20 seconds\n\
+             |'1_n'=1;;;; '2_n'=2;;;; '5_n'=5;;;;"
+        );
+        assert!(max_running.load(Ordering::SeqCst) <= 2);
+    }
+}
@@ -0,0 +1,143 @@
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// A cache on disk for the results of expensive collection steps, like full SNMP walks or vendor
+/// API inventories, which can be reused by several runs and by different plugins on the same host
+/// as long as they use the same directory.
+///
+/// Entries are files which expire after the TTL given when reading them. They are replaced
+/// atomically, so readers never see partial data, and refreshed by one process at a time, so
+/// concurrent runs don't repeat the same expensive collection.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use nagiosplugin::{state_dir, Cache};
+///
+/// # fn snmp_walk(host: &str) -> std::io::Result<Vec<u8>> { Ok(Vec::new()) }
+/// let cache = Cache::new(state_dir("snmp-cache").unwrap());
+/// let interfaces = cache
+///     .get_or_refresh("switch1.ifTable", Duration::from_secs(300), || snmp_walk("switch1"))
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Returns the entry, if it exists and is younger than the TTL.
+    pub fn get(&self, key: &str, ttl: Duration) -> io::Result<Option<Vec<u8>>> {
+        let path = self.path(key, "");
+        let modified = match fs::metadata(&path).and_then(|m| m.modified()) {
+            Ok(modified) => modified,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        // Entries from the future, e.g. after the clock was set back, are expired as well.
+        match SystemTime::now().duration_since(modified) {
+            Ok(age) if age <= ttl => match fs::read(&path) {
+                Ok(data) => Ok(Some(data)),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(err) => Err(err),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Stores the entry, replacing an existing one.
+    pub fn put(&self, key: &str, data: &[u8]) -> io::Result<()> {
+        let temp = self.path(key, &format!(".{}.tmp", std::process::id()));
+        fs::write(&temp, data)?;
+        fs::rename(&temp, self.path(key, ""))
+    }
+
+    /// Returns the entry if it is younger than the TTL. Otherwise the entry is refreshed with the
+    /// given function while holding a lock, so other processes wait for the new entry instead of
+    /// refreshing it as well. Errors of the cache itself are returned as well, so `E` has to be
+    /// convertible from [io::Error].
+    pub fn get_or_refresh<E: From<io::Error>>(
+        &self,
+        key: &str,
+        ttl: Duration,
+        refresh: impl FnOnce() -> Result<Vec<u8>, E>,
+    ) -> Result<Vec<u8>, E> {
+        if let Some(data) = self.get(key, ttl)? {
+            return Ok(data);
+        }
+
+        let lock = File::create(self.path(key, ".lock"))?;
+        lock.lock()?;
+        // Another process may have refreshed the entry while we were waiting for the lock.
+        if let Some(data) = self.get(key, ttl)? {
+            return Ok(data);
+        }
+
+        let data = refresh()?;
+        self.put(key, &data)?;
+        Ok(data)
+    }
+
+    fn path(&self, key: &str, suffix: &str) -> PathBuf {
+        self.dir.join(format!("{}.cache{}", file_name(key), suffix))
+    }
+}
+
+/// Turns the key into a file name. Keys with other characters than ASCII alphanumerics, `-`, `_`
+/// and `.` get a hash of the original key appended, so different keys never share a file.
+fn file_name(key: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.');
+    if !key.is_empty() && !key.starts_with('.') && key.chars().all(is_safe) {
+        return key.to_owned();
+    }
+
+    // FNV-1a, which is stable across Rust versions unlike the std hasher.
+    let hash = key.bytes().fold(0xcbf29ce484222325u64, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x100000001b3)
+    });
+    let name: String = key
+        .chars()
+        .map(|c| if is_safe(c) { c } else { '_' })
+        .collect();
+    format!("{}-{:016x}", name.trim_start_matches('.'), hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_or_refresh() {
+        let dir = std::env::temp_dir().join(format!("nagiosplugin-cache-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let cache = Cache::new(&dir);
+        let ttl = Duration::from_secs(60);
+
+        let data = cache.get_or_refresh("a/b", ttl, || Ok::<_, io::Error>(b"1".to_vec()));
+        assert_eq!(data.unwrap(), b"1");
+        let data = cache.get_or_refresh("a/b", ttl, || Ok::<_, io::Error>(b"2".to_vec()));
+        assert_eq!(data.unwrap(), b"1");
+
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(cache.get("a/b", Duration::from_millis(1)).unwrap(), None);
+        assert_eq!(cache.get("missing", ttl).unwrap(), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name("switch1.ifTable"), "switch1.ifTable");
+        assert_ne!(file_name("a/b"), file_name("a_b"));
+        assert!(file_name("../etc").starts_with("_etc-"));
+    }
+}
@@ -0,0 +1,240 @@
+use std::str::FromStr;
+
+use crate::{args, json, Unit};
+
+/// The argument which requests the listing of a [MetricCatalog].
+pub const LIST_METRICS_ARG: &str = "--list-metrics";
+
+/// The format of the listing of a [MetricCatalog].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum ListFormat {
+    /// One line per metric, e.g. `used (B): used space of the disk`.
+    #[default]
+    Text,
+    /// An array of objects with `name`, `unit` and `description`.
+    Json,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid format '{0}' for --list-metrics, expected 'text' or 'json'")]
+/// This error is returned by the [FromStr] implementation of [ListFormat].
+pub struct ListFormatError(String);
+
+impl FromStr for ListFormat {
+    type Err = ListFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(ListFormat::Text),
+            "json" => Ok(ListFormat::Json),
+            _ => Err(ListFormatError(s.to_owned())),
+        }
+    }
+}
+
+/// The definition of a metric in a [MetricCatalog]. The range and thresholds are optional and
+/// only used to generate graph templates, see [MetricCatalog::to_pnp_template] and
+/// [MetricCatalog::to_grafana_panels].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricDefinition {
+    pub(crate) name: String,
+    pub(crate) unit: Unit,
+    pub(crate) description: String,
+    pub(crate) minimum: Option<f64>,
+    pub(crate) maximum: Option<f64>,
+    pub(crate) warning: Option<f64>,
+    pub(crate) critical: Option<f64>,
+}
+
+impl MetricDefinition {
+    pub fn new(name: impl Into<String>, unit: Unit, description: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            unit,
+            description: description.into(),
+            minimum: Default::default(),
+            maximum: Default::default(),
+            warning: Default::default(),
+            critical: Default::default(),
+        }
+    }
+
+    pub fn with_range(
+        mut self,
+        minimum: impl Into<Option<f64>>,
+        maximum: impl Into<Option<f64>>,
+    ) -> Self {
+        self.minimum = minimum.into();
+        self.maximum = maximum.into();
+        self
+    }
+
+    /// Sets the default thresholds of the plugin, which are drawn as lines in the graphs.
+    pub fn with_thresholds(
+        mut self,
+        warning: impl Into<Option<f64>>,
+        critical: impl Into<Option<f64>>,
+    ) -> Self {
+        self.warning = warning.into();
+        self.critical = critical.into();
+        self
+    }
+}
+
+/// The metrics a plugin can emit, so dashboards and threshold audits can be generated from the
+/// plugin itself with `--list-metrics`.
+///
+/// ## Example
+///
+/// ```no_run
+/// use nagiosplugin::{MetricCatalog, Unit};
+///
+/// let catalog = MetricCatalog::new()
+///     .with_metric("used", Unit::Bytes, "Used space of the disk")
+///     .with_metric("used_pct", Unit::Percentage, "Used space in percent");
+///
+/// // Prints the metrics and exits if `--list-metrics` was given.
+/// catalog.handle_args(std::env::args());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MetricCatalog {
+    pub(crate) metrics: Vec<MetricDefinition>,
+}
+
+impl MetricCatalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_metric(
+        mut self,
+        name: impl Into<String>,
+        unit: Unit,
+        description: impl Into<String>,
+    ) -> Self {
+        self.push_metric(name, unit, description);
+        self
+    }
+
+    pub fn push_metric(
+        &mut self,
+        name: impl Into<String>,
+        unit: Unit,
+        description: impl Into<String>,
+    ) {
+        self.push_definition(MetricDefinition::new(name, unit, description));
+    }
+
+    pub fn with_definition(mut self, definition: MetricDefinition) -> Self {
+        self.push_definition(definition);
+        self
+    }
+
+    pub fn push_definition(&mut self, definition: MetricDefinition) {
+        self.metrics.push(definition);
+    }
+
+    pub fn render(&self, format: ListFormat) -> String {
+        match format {
+            ListFormat::Text => self
+                .metrics
+                .iter()
+                .map(|m| match m.unit {
+                    Unit::None => format!("{}: {}", m.name, m.description),
+                    _ => format!("{} ({}): {}", m.name, m.unit.as_str(), m.description),
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            ListFormat::Json => {
+                let metrics = self
+                    .metrics
+                    .iter()
+                    .map(|m| {
+                        format!(
+                            "{{\"name\":{},\"unit\":{},\"description\":{}}}",
+                            json::quote(&m.name),
+                            json::quote(m.unit.as_str()),
+                            json::quote(&m.description)
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",");
+                format!("[{}]", metrics)
+            }
+        }
+    }
+
+    /// Returns the listing if `--list-metrics` is part of the given arguments. The format can be
+    /// chosen with `--list-metrics=json`, the default is [ListFormat::Text]. Arguments after `--`
+    /// and the values of the standard plugin options like `-w` are skipped, as in
+    /// [crate::net::NetOptions::from_args].
+    pub fn listing_from_args<I, S>(&self, args: I) -> Result<Option<String>, ListFormatError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let options = args::Options {
+            flags: &[LIST_METRICS_ARG],
+            ..Default::default()
+        };
+        // Flags never miss a value.
+        let found = args::scan(args, options).unwrap_or_default();
+
+        match found.into_iter().next() {
+            Some((_, Some(format))) => Ok(Some(self.render(format.parse()?))),
+            Some((_, None)) => Ok(Some(self.render(ListFormat::default()))),
+            None => Ok(None),
+        }
+    }
+
+    /// Prints the listing and exits if `--list-metrics` is part of the given arguments, see
+    /// [MetricCatalog::listing_from_args]. An invalid format exits with
+    /// [ServiceState::Unknown]. Otherwise this function returns and the plugin runs normally.
+    ///
+    /// [ServiceState::Unknown]: crate::ServiceState::Unknown
+    pub fn handle_args<I, S>(&self, args: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        match self.listing_from_args(args) {
+            Ok(Some(listing)) => {
+                println!("{}", listing);
+                std::process::exit(0);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                println!("UNKNOWN: {}", err);
+                std::process::exit(crate::ServiceState::Unknown.exit_code());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listing() {
+        let catalog = MetricCatalog::new()
+            .with_metric("used", Unit::Bytes, "Used space")
+            .with_metric("inodes", Unit::None, "Used \"inodes\"");
+
+        assert_eq!(
+            catalog
+                .listing_from_args(["check_disk", "--list-metrics"])
+                .unwrap(),
+            Some("used (B): Used space\ninodes: Used \"inodes\"".to_owned())
+        );
+        assert_eq!(
+            catalog.listing_from_args(["--list-metrics=json"]).unwrap(),
+            Some(
+                r#"[{"name":"used","unit":"B","description":"Used space"},{"name":"inodes","unit":"","description":"Used \"inodes\""}]"#
+                    .to_owned()
+            )
+        );
+        assert_eq!(catalog.listing_from_args(["-w", "80"]).unwrap(), None);
+        assert!(catalog.listing_from_args(["--list-metrics=xml"]).is_err());
+    }
+}
@@ -0,0 +1,532 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+
+use crate::{args, CheckResult, Resource, RunResult, Runner, ServiceState, SubChecks};
+
+/// The error type of [Check] and [AsyncCheck]. Any error type can be converted into it with `?`.
+pub type CheckError = Box<dyn Error + Send + Sync>;
+
+/// The future returned by [AsyncCheck::run].
+pub type CheckFuture<'a> = Pin<Box<dyn Future<Output = Result<Resource, CheckError>> + Send + 'a>>;
+
+/// A named check, so multiple checks can be registered in a [CheckRegistry] and shipped as a
+/// single binary.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{Check, CheckError, Metric, Resource};
+///
+/// struct Load;
+///
+/// impl Check for Load {
+///     fn name(&self) -> &str {
+///         "load"
+///     }
+///
+///     fn run(&self) -> Result<Resource, CheckError> {
+///         let load: f64 = "0.42".parse()?;
+///         Ok(Resource::new("load").with_result(Metric::new("load1", load)))
+///     }
+/// }
+/// ```
+pub trait Check {
+    fn name(&self) -> &str;
+    fn run(&self) -> Result<Resource, CheckError>;
+}
+
+/// The async variant of [Check]. The future is boxed, so it's independent of the used runtime
+/// and the trait can be used as a trait object.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{AsyncCheck, CheckFuture, Resource};
+///
+/// struct Api;
+///
+/// impl AsyncCheck for Api {
+///     fn name(&self) -> &str {
+///         "api"
+///     }
+///
+///     fn run(&self) -> CheckFuture<'_> {
+///         Box::pin(async { Ok(Resource::new("api")) })
+///     }
+/// }
+/// ```
+pub trait AsyncCheck {
+    fn name(&self) -> &str;
+    fn run(&self) -> CheckFuture<'_>;
+}
+
+impl fmt::Debug for dyn Check + Send + Sync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Check").field("name", &self.name()).finish()
+    }
+}
+
+impl fmt::Debug for dyn AsyncCheck + Send + Sync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncCheck")
+            .field("name", &self.name())
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+enum Registered {
+    Sync(Box<dyn Check + Send + Sync>),
+    Async(Box<dyn AsyncCheck + Send + Sync>),
+}
+
+/// A set of [Check]s which are selected by name at runtime, e.g. by a subcommand or by the name
+/// of the binary, so one binary can be symlinked as `check_load`, `check_disk` etc.
+///
+/// [AsyncCheck]s can be registered as well, but only run with [CheckRegistry::dispatch_async]
+/// and [CheckRegistry::dispatch_args_async], which run synchronous checks too.
+///
+/// ## Example
+///
+/// ```no_run
+/// # use nagiosplugin::{Check, CheckError, Resource};
+/// # struct Load;
+/// # impl Check for Load {
+/// #     fn name(&self) -> &str { "load" }
+/// #     fn run(&self) -> Result<Resource, CheckError> { Ok(Resource::new("load")) }
+/// # }
+/// use nagiosplugin::{CheckRegistry, Runner};
+///
+/// fn main() {
+///     CheckRegistry::new()
+///         .with_check(Load)
+///         .dispatch_args(&Runner::new(), std::env::args())
+///         .print_and_exit()
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct CheckRegistry {
+    checks: BTreeMap<String, Registered>,
+}
+
+impl CheckRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the check under its name. A check registered earlier with the same name is
+    /// replaced.
+    pub fn with_check(mut self, check: impl Check + Send + Sync + 'static) -> Self {
+        self.register(check);
+        self
+    }
+
+    /// Registers the check under its name. A check registered earlier with the same name is
+    /// replaced.
+    pub fn register(&mut self, check: impl Check + Send + Sync + 'static) {
+        self.checks
+            .insert(check.name().to_owned(), Registered::Sync(Box::new(check)));
+    }
+
+    /// Registers the async check under its name. A check registered earlier with the same name is
+    /// replaced.
+    pub fn with_async_check(mut self, check: impl AsyncCheck + Send + Sync + 'static) -> Self {
+        self.register_async(check);
+        self
+    }
+
+    /// Registers the async check under its name. A check registered earlier with the same name is
+    /// replaced.
+    pub fn register_async(&mut self, check: impl AsyncCheck + Send + Sync + 'static) {
+        self.checks
+            .insert(check.name().to_owned(), Registered::Async(Box::new(check)));
+    }
+
+    /// Returns the synchronous check with the given name.
+    pub fn get(&self, name: &str) -> Option<&(dyn Check + Send + Sync)> {
+        match self.checks.get(name)? {
+            Registered::Sync(check) => Some(check.as_ref()),
+            Registered::Async(_) => None,
+        }
+    }
+
+    /// Returns the async check with the given name.
+    pub fn get_async(&self, name: &str) -> Option<&(dyn AsyncCheck + Send + Sync)> {
+        match self.checks.get(name)? {
+            Registered::Async(check) => Some(check.as_ref()),
+            Registered::Sync(_) => None,
+        }
+    }
+
+    /// Returns the names of all registered checks in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.checks.keys().map(String::as_str)
+    }
+
+    /// Runs the check with the given name using the given [Runner]. An unknown name is reported
+    /// as [ServiceState::Unknown], together with the registered names, as are async checks.
+    pub fn dispatch(&self, runner: &Runner, name: &str) -> RunResult<CheckError> {
+        match self.checks.get(name) {
+            Some(Registered::Sync(check)) => runner.safe_run(|| check.run()),
+            Some(Registered::Async(_)) => RunResult::Err(ServiceState::Unknown, async_only(name)),
+            None => RunResult::Err(ServiceState::Unknown, self.unknown_check(name)),
+        }
+    }
+
+    /// Like [CheckRegistry::dispatch], but runs async checks as well, using
+    /// [Runner::safe_run_async].
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// # use nagiosplugin::{AsyncCheck, CheckFuture, Resource};
+    /// # struct Api;
+    /// # impl AsyncCheck for Api {
+    /// #     fn name(&self) -> &str { "api" }
+    /// #     fn run(&self) -> CheckFuture<'_> { Box::pin(async { Ok(Resource::new("api")) }) }
+    /// # }
+    /// use nagiosplugin::{CheckRegistry, Runner};
+    ///
+    /// # async fn example() {
+    /// CheckRegistry::new()
+    ///     .with_async_check(Api)
+    ///     .dispatch_args_async(&Runner::new(), std::env::args())
+    ///     .await
+    ///     .print_and_exit()
+    /// # }
+    /// ```
+    pub async fn dispatch_async(&self, runner: &Runner, name: &str) -> RunResult<CheckError> {
+        match self.checks.get(name) {
+            Some(Registered::Sync(check)) => {
+                runner
+                    .safe_run_async(|| std::future::ready(check.run()))
+                    .await
+            }
+            Some(Registered::Async(check)) => runner.safe_run_async(|| check.run()).await,
+            None => RunResult::Err(ServiceState::Unknown, self.unknown_check(name)),
+        }
+    }
+
+    /// Selects the check by the file name of the binary (the first argument) if a check with
+    /// that name is registered, otherwise by the second argument, and runs it. This makes both
+    /// `plugin load` and a symlink named `load` pointing to `plugin` work.
+    pub fn dispatch_args<I, S>(&self, runner: &Runner, args: I) -> RunResult<CheckError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        match self.select(args) {
+            Ok(name) => self.dispatch(runner, &name),
+            Err(err) => RunResult::Err(ServiceState::Unknown, err),
+        }
+    }
+
+    /// Like [CheckRegistry::dispatch_args], but runs async checks as well, see
+    /// [CheckRegistry::dispatch_async].
+    pub async fn dispatch_args_async<I, S>(&self, runner: &Runner, args: I) -> RunResult<CheckError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        match self.select(args) {
+            Ok(name) => self.dispatch_async(runner, &name).await,
+            Err(err) => RunResult::Err(ServiceState::Unknown, err),
+        }
+    }
+
+    /// Returns the name of the check selected by [CheckRegistry::dispatch_args].
+    fn select<I, S>(&self, args: I) -> Result<String, CheckError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut args = args.into_iter();
+        let binary = args.next();
+        let binary = binary
+            .as_ref()
+            .and_then(|b| Path::new(b.as_ref()).file_name())
+            .and_then(|b| b.to_str());
+
+        match binary {
+            Some(name) if self.checks.contains_key(name) => Ok(name.to_owned()),
+            _ => match args.next() {
+                Some(name) => Ok(name.as_ref().to_owned()),
+                None => Err(format!(
+                    "no check given, available checks: {}",
+                    self.names().collect::<Vec<_>>().join(", ")
+                )
+                .into()),
+            },
+        }
+    }
+
+    /// Runs all checks selected by the filter in alphabetical order and aggregates them into a
+    /// single [Resource] with the given name, like [SubChecks] does. Names in the filter which
+    /// aren't registered and async checks are reported as [ServiceState::Unknown].
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// # use nagiosplugin::{Check, CheckError, Resource};
+    /// # struct Load;
+    /// # impl Check for Load {
+    /// #     fn name(&self) -> &str { "load" }
+    /// #     fn run(&self) -> Result<Resource, CheckError> { Ok(Resource::new("load")) }
+    /// # }
+    /// use nagiosplugin::{CheckFilter, CheckRegistry, Runner};
+    ///
+    /// fn main() {
+    ///     let registry = CheckRegistry::new().with_check(Load);
+    ///
+    ///     Runner::new()
+    ///         .safe_run(|| {
+    ///             let filter = CheckFilter::from_args(std::env::args().skip(1))?;
+    ///             Ok::<_, CheckError>(registry.run_combined("server", &filter))
+    ///         })
+    ///         .print_and_exit()
+    /// }
+    /// ```
+    pub fn run_combined(&self, name: impl Into<String>, filter: &CheckFilter) -> Resource {
+        let mut sub_checks = SubChecks::new(name);
+        for (name, check) in self.checks.iter().filter(|(name, _)| filter.matches(name)) {
+            sub_checks.push_check(name, || match check {
+                Registered::Sync(check) => check.run(),
+                Registered::Async(_) => Err(async_only(name)),
+            });
+        }
+
+        let mut resource = sub_checks.run();
+        for name in filter.names().filter(|n| !self.checks.contains_key(*n)) {
+            resource.push_result(
+                CheckResult::new()
+                    .with_state(ServiceState::Unknown)
+                    .with_message(self.unknown_check(name).to_string()),
+            );
+        }
+
+        resource
+    }
+
+    fn unknown_check(&self, name: &str) -> CheckError {
+        format!(
+            "unknown check '{}', available checks: {}",
+            name,
+            self.names().collect::<Vec<_>>().join(", ")
+        )
+        .into()
+    }
+}
+
+fn async_only(name: &str) -> CheckError {
+    format!("check '{}' can only be run asynchronously", name).into()
+}
+
+/// Selects checks for [CheckRegistry::run_combined]. By default all checks are selected.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckFilter {
+    only: Option<Vec<String>>,
+    skip: Vec<String>,
+}
+
+/// This error is returned by [CheckFilter::from_args].
+#[derive(Debug, thiserror::Error)]
+#[error("missing value for argument '{0}'")]
+pub struct CheckFilterError(String);
+
+impl CheckFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only runs the given checks. Can be called multiple times.
+    pub fn with_only(mut self, names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.only
+            .get_or_insert_with(Vec::new)
+            .extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Skips the given checks. Takes precedence over [CheckFilter::with_only].
+    pub fn with_skip(mut self, names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.skip.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Reads `--only` and `--skip` from the given arguments. Both take a comma separated list of
+    /// check names, either as the next argument or separated by `=`, and can be repeated. Other
+    /// arguments are ignored, so they can be handled by the plugin itself. Arguments after `--`
+    /// and the values of the standard plugin options like `-w` are skipped, as in
+    /// [crate::net::NetOptions::from_args].
+    pub fn from_args<I, S>(args: I) -> Result<Self, CheckFilterError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let options = args::Options {
+            values: &["--only", "--skip"],
+            ..Default::default()
+        };
+        let found = args::scan(args, options).map_err(CheckFilterError)?;
+
+        let mut filter = Self::new();
+        for (key, value) in found {
+            let value = value.unwrap_or_default();
+            let names = value.split(',').map(str::trim).filter(|n| !n.is_empty());
+            filter = match key.as_str() {
+                "--only" => filter.with_only(names),
+                _ => filter.with_skip(names),
+            };
+        }
+
+        Ok(filter)
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        let selected = self
+            .only
+            .as_ref()
+            .is_none_or(|only| only.iter().any(|n| n == name));
+        selected && !self.skip.iter().any(|n| n == name)
+    }
+
+    /// Returns all names mentioned by the filter.
+    fn names(&self) -> impl Iterator<Item = &str> {
+        self.only
+            .iter()
+            .flatten()
+            .chain(&self.skip)
+            .map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Metric;
+
+    struct Static(&'static str, i64);
+
+    impl Check for Static {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn run(&self) -> Result<Resource, CheckError> {
+            Ok(Resource::new(self.0).with_result(Metric::new("value", self.1)))
+        }
+    }
+
+    #[test]
+    fn test_registry_dispatch() {
+        let registry = CheckRegistry::new()
+            .with_check(Static("load", 1))
+            .with_check(Static("disk", 2));
+        let runner = Runner::new();
+
+        assert_eq!(registry.names().collect::<Vec<_>>(), vec!["disk", "load"]);
+
+        match registry.dispatch_args(&runner, ["/usr/lib/nagios/plugins/disk"]) {
+            RunResult::Ok(resource) => assert_eq!(resource.name(), "disk"),
+            RunResult::Err(_, err) => panic!("unexpected error: {}", err),
+        }
+
+        match registry.dispatch_args(&runner, ["plugin", "load"]) {
+            RunResult::Ok(resource) => assert_eq!(resource.name(), "load"),
+            RunResult::Err(_, err) => panic!("unexpected error: {}", err),
+        }
+
+        match registry.dispatch_args(&runner, ["plugin", "cpu"]) {
+            RunResult::Ok(_) => panic!("expected an error"),
+            RunResult::Err(state, err) => {
+                assert_eq!(state, ServiceState::Unknown);
+                assert_eq!(
+                    err.to_string(),
+                    "unknown check 'cpu', available checks: disk, load"
+                );
+            }
+        }
+    }
+
+    struct StaticAsync(&'static str);
+
+    impl AsyncCheck for StaticAsync {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn run(&self) -> CheckFuture<'_> {
+            Box::pin(async { Ok(Resource::new(self.0)) })
+        }
+    }
+
+    #[test]
+    fn test_registry_dispatch_async() {
+        use std::task::{Context, Poll, Waker};
+
+        let registry = CheckRegistry::new()
+            .with_check(Static("load", 1))
+            .with_async_check(StaticAsync("api"));
+        let runner = Runner::new();
+        let run = |args: &[&str]| {
+            let future = std::pin::pin!(registry.dispatch_args_async(&runner, args));
+            match future.poll(&mut Context::from_waker(Waker::noop())) {
+                Poll::Ready(RunResult::Ok(resource)) => resource.name().to_owned(),
+                Poll::Ready(RunResult::Err(_, err)) => panic!("unexpected error: {}", err),
+                Poll::Pending => panic!("the check should be ready"),
+            }
+        };
+
+        assert_eq!(run(&["plugin", "api"]), "api");
+        assert_eq!(run(&["plugin", "load"]), "load");
+
+        match registry.dispatch(&runner, "api") {
+            RunResult::Ok(_) => panic!("expected an error"),
+            RunResult::Err(state, err) => {
+                assert_eq!(state, ServiceState::Unknown);
+                assert_eq!(
+                    err.to_string(),
+                    "check 'api' can only be run asynchronously"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_check_filter_from_args() {
+        let filter =
+            CheckFilter::from_args(["-v", "--only", "load,disk", "--skip=disk", "--only=cpu"])
+                .unwrap();
+
+        assert_eq!(
+            filter,
+            CheckFilter::new()
+                .with_only(["load", "disk", "cpu"])
+                .with_skip(["disk"])
+        );
+        assert!(filter.matches("load"));
+        assert!(!filter.matches("disk"));
+        assert!(!filter.matches("memory"));
+        assert!(CheckFilter::from_args(["--skip"]).is_err());
+    }
+
+    #[test]
+    fn test_registry_run_combined() {
+        let registry = CheckRegistry::new()
+            .with_check(Static("load", 1))
+            .with_check(Static("disk", 2))
+            .with_check(Static("cpu", 3));
+
+        let filter = CheckFilter::new().with_skip(["cpu", "memory"]);
+        let (state, output) = registry.run_combined("server", &filter).nagios_result();
+
+        assert_eq!(state, ServiceState::Unknown);
+        assert!(output.contains("'disk_value'=2"));
+        assert!(output.contains("'load_value'=1"));
+        assert!(!output.contains("cpu_value"));
+        assert!(output.contains("unknown check 'memory'"));
+    }
+}
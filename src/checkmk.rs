@@ -0,0 +1,135 @@
+use crate::{PerfString, Resource, UNDETERMINED_VALUE};
+
+impl Resource {
+    /// Renders this resource as a
+    /// [Checkmk local check](https://docs.checkmk.com/latest/en/localchecks.html) line: the exit
+    /// code, the name as service name, the performance data separated by `|` (or `-` without any)
+    /// and the summary. The long output is appended with escaped line breaks, which Checkmk shows
+    /// as details. Units and undetermined values are left out, as Checkmk doesn't accept them, and
+    /// threshold ranges are reduced to their upper bound.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use nagiosplugin::{Metric, Resource, TriggerIfValue, Unit};
+    ///
+    /// let output = Resource::new("Disk usage")
+    ///     .with_result(
+    ///         Metric::new("root", 93)
+    ///             .with_unit(Unit::Percentage)
+    ///             .with_thresholds(80, 90, TriggerIfValue::Greater),
+    ///     )
+    ///     .to_checkmk_local();
+    ///
+    /// assert_eq!(
+    ///     output,
+    ///     "2 \"Disk usage\" root=93;80;90;; Disk usage is CRITICAL\\n\
+    ///      metric 'root' is CRITICAL: value '93%' has exceeded threshold of '90%'"
+    /// );
+    /// ```
+    pub fn to_checkmk_local(self) -> String {
+        let name = match self.name.contains(char::is_whitespace) {
+            true => format!("\"{}\"", self.name.replace('"', "'")),
+            false => self.name.clone(),
+        };
+        let summary = self.exit_summary();
+
+        let metrics: Vec<_> = summary
+            .perf_strings
+            .iter()
+            .filter(|p| p.value != UNDETERMINED_VALUE)
+            .map(checkmk_metric)
+            .collect();
+        let metrics = match metrics.is_empty() {
+            true => "-".to_owned(),
+            false => metrics.join("|"),
+        };
+
+        let mut text = summary.summary;
+        for line in &summary.long_output {
+            text.push_str("\\n");
+            text.push_str(line);
+        }
+
+        format!(
+            "{} {} {} {}",
+            summary.state.exit_code(),
+            name,
+            metrics,
+            text
+        )
+    }
+}
+
+/// Formats the entry as `name=value;warn;crit;min;max`, replacing characters in the name which
+/// would end it early. Checkmk only knows upper levels, so see [checkmk_level] for thresholds.
+fn checkmk_metric(perf_string: &PerfString) -> String {
+    let name: String = perf_string
+        .label
+        .chars()
+        .map(|c| match c {
+            c if c.is_whitespace() => '_',
+            '=' | '|' | '\'' => '_',
+            c => c,
+        })
+        .collect();
+
+    format!(
+        "{}={};{};{};{};{}",
+        name,
+        perf_string.value,
+        checkmk_level(&perf_string.warning),
+        checkmk_level(&perf_string.critical),
+        perf_string.minimum,
+        perf_string.maximum
+    )
+}
+
+/// Returns the upper bound of a Nagios range like `10:20` or `~:20`, as Checkmk accepts plain
+/// numbers only. Ranges alerting inside (`@10:20`) or without an upper bound (`10:`) are left out.
+fn checkmk_level(threshold: &str) -> &str {
+    let end = threshold.rsplit(':').next().unwrap_or_default();
+    match end.parse::<f64>() {
+        Ok(value) if value.is_finite() && !threshold.starts_with('@') => end,
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CheckResult, Metric, Resource, ServiceState, ThresholdRange};
+
+    #[test]
+    fn test_to_checkmk_local() {
+        let output = Resource::new("ntp")
+            .with_result(CheckResult::new().with_state(ServiceState::Warning))
+            .to_checkmk_local();
+        assert_eq!(output, "1 ntp - ntp is WARNING");
+
+        let output = Resource::new("eth0")
+            .with_result(Metric::new("in bytes", 1))
+            .with_result(Metric::new("out", f64::NAN))
+            .with_result(Metric::new("errors", 0).with_maximum(10))
+            .to_checkmk_local();
+        assert!(
+            output.starts_with("3 eth0 in_bytes=1;;;;|errors=0;;;;10 eth0 is UNKNOWN\\n"),
+            "{}",
+            output
+        );
+
+        let range = |s: &str| s.parse::<ThresholdRange<f64>>().unwrap();
+        let output = Resource::new("temp")
+            .with_result(
+                Metric::new("level", 15.0).with_threshold_ranges(range("@10:20"), range("~:30")),
+            )
+            .with_result(
+                Metric::new("free", 5.0).with_threshold_ranges(range("10:"), range("5:50")),
+            )
+            .to_checkmk_local();
+        assert!(
+            output.starts_with("1 temp level=15;;30;;|free=5;;50;;"),
+            "{}",
+            output
+        );
+    }
+}
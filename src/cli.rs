@@ -0,0 +1,383 @@
+//! Helpers for plugins which parse their arguments with [clap].
+//!
+//! [ServiceState] implements [clap::ValueEnum] and [Unit] can be parsed with
+//! `value_parser!(Unit)`, so the possible values show up in the help and in shell completions.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use clap::{value_parser, Arg, Command};
+//! use nagiosplugin::cli;
+//! use nagiosplugin::{ServiceState, Unit};
+//!
+//! let mut cmd = cli::with_completions(
+//!     Command::new("check_foo")
+//!         .arg(Arg::new("unit").long("unit").value_parser(value_parser!(Unit)))
+//!         .arg(Arg::new("on-error").long("on-error").value_parser(value_parser!(ServiceState))),
+//! );
+//! let matches = cmd.clone().get_matches();
+//! if cli::write_completions(&mut cmd, &matches, &mut std::io::stdout()) {
+//!     return;
+//! }
+//! ```
+
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use clap::builder::{PossibleValue, TypedValueParser, ValueParserFactory};
+use clap::{value_parser, Arg, ArgMatches, Command};
+use clap_complete::Shell;
+
+use crate::{args, ServiceState, ThresholdRange, ThresholdRangeParseError, Unit};
+
+/// The name of the hidden argument added by [with_completions].
+pub const COMPLETIONS_ARG: &str = "generate-completions";
+
+static KNOWN_UNITS: [Unit; 21] = [
+    Unit::Seconds,
+    Unit::Milliseconds,
+    Unit::Microseconds,
+    Unit::Percentage,
+    Unit::Bytes,
+    Unit::Kilobytes,
+    Unit::Megabytes,
+    Unit::Gigabytes,
+    Unit::Terabytes,
+    Unit::Petabytes,
+    Unit::Kibibytes,
+    Unit::Mebibytes,
+    Unit::Gibibytes,
+    Unit::Tebibytes,
+    Unit::Pebibytes,
+    Unit::Counter,
+    Unit::Bits,
+    Unit::BitsPerSecond,
+    Unit::PacketsPerSecond,
+    Unit::Hertz,
+    Unit::Iops,
+];
+
+impl clap::ValueEnum for ServiceState {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            ServiceState::Ok,
+            ServiceState::Warning,
+            ServiceState::Critical,
+            ServiceState::Unknown,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let name = match self {
+            ServiceState::Ok => "ok",
+            ServiceState::Warning => "warning",
+            ServiceState::Critical => "critical",
+            ServiceState::Unknown => "unknown",
+        };
+        Some(PossibleValue::new(name))
+    }
+}
+
+/// Parses a [Unit] as it is written in the performance data. The predefined units are offered
+/// as possible values, other units are accepted as well.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct UnitValueParser;
+
+impl TypedValueParser for UnitValueParser {
+    type Value = Unit;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let value = clap::builder::StringValueParser::new().parse_ref(cmd, arg, value)?;
+        value.parse().map_err(|err| {
+            let arg = arg.map_or_else(|| "...".to_owned(), Arg::to_string);
+            clap::Error::raw(
+                clap::error::ErrorKind::InvalidValue,
+                format!("invalid value '{}' for '{}': {}\n", value, arg, err),
+            )
+            .with_cmd(cmd)
+        })
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(
+            KNOWN_UNITS
+                .iter()
+                .map(|unit| PossibleValue::new(unit.as_str())),
+        ))
+    }
+}
+
+impl ValueParserFactory for Unit {
+    type Parser = UnitValueParser;
+
+    fn value_parser() -> Self::Parser {
+        UnitValueParser
+    }
+}
+
+/// Parses a `-w` or `-c` argument in the range format of the plugin guidelines, for use as
+/// `value_parser = threshold_parser::<f64>` in derived arguments. The result can be passed to
+/// [crate::Metric::with_threshold_ranges].
+///
+/// ## Example
+///
+/// ```
+/// use clap::{Arg, Command};
+/// use nagiosplugin::cli::threshold_parser;
+/// use nagiosplugin::ThresholdRange;
+///
+/// let matches = Command::new("check_foo")
+///     .arg(Arg::new("warning").short('w').value_parser(threshold_parser::<f64>))
+///     .get_matches_from(["check_foo", "-w", "@10:20"]);
+/// let warning = matches.get_one::<ThresholdRange<f64>>("warning").unwrap();
+/// assert!(warning.is_alerting(&15.0));
+/// ```
+pub fn threshold_parser<T>(value: &str) -> Result<ThresholdRange<T>, ThresholdRangeParseError>
+where
+    T: FromStr + Default + PartialOrd,
+    T::Err: fmt::Display,
+{
+    value.parse()
+}
+
+/// Adds the hidden `--generate-completions <SHELL>` argument to the command, see
+/// [write_completions].
+pub fn with_completions(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new(COMPLETIONS_ARG)
+            .long(COMPLETIONS_ARG)
+            .value_name("SHELL")
+            .value_parser(value_parser!(Shell))
+            .hide(true),
+    )
+}
+
+/// Writes the completions for the shell requested with `--generate-completions` to `out`.
+/// Returns `false` if the argument wasn't given, so the plugin should run normally.
+pub fn write_completions(cmd: &mut Command, matches: &ArgMatches, out: &mut dyn Write) -> bool {
+    let Some(shell) = matches.get_one::<Shell>(COMPLETIONS_ARG).copied() else {
+        return false;
+    };
+
+    let name = cmd.get_name().to_owned();
+    clap_complete::generate(shell, cmd, name, out);
+    true
+}
+
+/// Writes the completions for the shell requested with `--generate-completions` in `args` to
+/// `out`, without parsing the other arguments. Unlike [write_completions] this also works if the
+/// command has required arguments which aren't given. Arguments after `--` are ignored.
+///
+/// Returns `false` if the argument wasn't given or names an unknown shell.
+pub fn write_requested_completions<I, S>(cmd: &mut Command, args: I, out: &mut dyn Write) -> bool
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let Some(shell) = requested_shell(args) else {
+        return false;
+    };
+
+    let name = cmd.get_name().to_owned();
+    clap_complete::generate(shell, cmd, name, out);
+    true
+}
+
+/// Returns the shell requested with `--generate-completions` before the first `--`, if any.
+pub fn requested_shell<I, S>(args: I) -> Option<Shell>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let long = format!("--{}", COMPLETIONS_ARG);
+    let options = args::Options {
+        values: &[long.as_str()],
+        ..Default::default()
+    };
+    let (_, value) = args::scan(args, options).ok()?.into_iter().next()?;
+    value?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command() -> Command {
+        with_completions(
+            Command::new("check_foo")
+                .arg(
+                    Arg::new("unit")
+                        .long("unit")
+                        .value_parser(value_parser!(Unit)),
+                )
+                .arg(
+                    Arg::new("state")
+                        .long("state")
+                        .value_parser(value_parser!(ServiceState)),
+                ),
+        )
+    }
+
+    #[test]
+    fn test_value_parsers() {
+        let matches = command()
+            .try_get_matches_from(["check_foo", "--unit", "MiB", "--state", "critical"])
+            .unwrap();
+        assert_eq!(matches.get_one::<Unit>("unit"), Some(&Unit::Mebibytes));
+        assert_eq!(
+            matches.get_one::<ServiceState>("state"),
+            Some(&ServiceState::Critical)
+        );
+        assert!(command()
+            .try_get_matches_from(["check_foo", "--state", "bad"])
+            .is_err());
+
+        let err = command()
+            .try_get_matches_from(["check_foo", "--unit", "k;B"])
+            .unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::InvalidValue);
+        assert!(
+            err.to_string()
+                .contains("invalid value 'k;B' for '--unit <unit>': expected string to not"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_net_value_parsers() {
+        use crate::net::{Host, Port};
+
+        let cmd = Command::new("check_foo")
+            .arg(
+                Arg::new("host")
+                    .short('H')
+                    .value_parser(value_parser!(Host)),
+            )
+            .arg(
+                Arg::new("port")
+                    .short('p')
+                    .value_parser(value_parser!(Port)),
+            );
+        let matches = cmd
+            .clone()
+            .try_get_matches_from(["check_foo", "-H", "[::1]", "-p", "22"])
+            .unwrap();
+        assert_eq!(
+            matches.get_one::<Host>("host").unwrap().to_string(),
+            "[::1]"
+        );
+        assert_eq!(matches.get_one::<Port>("port").unwrap().get(), 22);
+
+        let err = cmd
+            .try_get_matches_from(["check_foo", "-p", "0"])
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("expected a number between 1 and 65535"));
+    }
+
+    #[test]
+    fn test_named_thresholds() {
+        use crate::{NamedThreshold, NamedThresholds};
+        use clap::ArgAction;
+
+        let matches = Command::new("check_foo")
+            .arg(
+                Arg::new("threshold")
+                    .long("threshold")
+                    .action(ArgAction::Append)
+                    .value_parser(value_parser!(NamedThreshold<f64>)),
+            )
+            .try_get_matches_from(["check_foo", "--threshold", "a=1,2", "--threshold=b=,3"])
+            .unwrap();
+        let thresholds: NamedThresholds<f64> = matches
+            .get_many::<NamedThreshold<f64>>("threshold")
+            .unwrap()
+            .cloned()
+            .collect();
+        assert_eq!(
+            thresholds.get("b").unwrap().critical,
+            Some(ThresholdRange::outside(0.0, 3.0))
+        );
+    }
+
+    #[test]
+    fn test_threshold_parser() {
+        let cmd = Command::new("check_foo")
+            .arg(
+                Arg::new("warning")
+                    .short('w')
+                    .value_parser(threshold_parser::<i64>),
+            )
+            .arg(
+                Arg::new("critical")
+                    .short('c')
+                    .value_parser(threshold_parser::<i64>),
+            );
+        let matches = cmd
+            .clone()
+            .try_get_matches_from(["check_foo", "-w", "10:", "-c", "~:5"])
+            .unwrap();
+        assert_eq!(
+            matches.get_one::<ThresholdRange<i64>>("warning"),
+            Some(&ThresholdRange::outside(10, None))
+        );
+        assert_eq!(
+            matches.get_one::<ThresholdRange<i64>>("critical"),
+            Some(&ThresholdRange::outside(None, 5))
+        );
+
+        let err = cmd
+            .try_get_matches_from(["check_foo", "-w", "20:10"])
+            .unwrap_err();
+        assert!(err.to_string().contains("start is greater than end"));
+    }
+
+    #[test]
+    fn test_write_completions() {
+        let mut cmd = command();
+        let mut out = Vec::new();
+        let matches = cmd.clone().try_get_matches_from(["check_foo"]).unwrap();
+        assert!(!write_completions(&mut cmd, &matches, &mut out));
+
+        let matches = cmd
+            .clone()
+            .try_get_matches_from(["check_foo", "--generate-completions", "bash"])
+            .unwrap();
+        assert!(write_completions(&mut cmd, &matches, &mut out));
+        let script = String::from_utf8(out).unwrap();
+        assert!(script.contains("ok warning critical unknown"));
+        assert!(script.contains("MiB"));
+    }
+
+    #[test]
+    fn test_write_requested_completions() {
+        let mut cmd = command().arg(Arg::new("host").short('H').required(true));
+        let mut out = Vec::new();
+
+        assert!(!write_requested_completions(
+            &mut cmd,
+            ["check_foo"],
+            &mut out
+        ));
+        assert!(!write_requested_completions(
+            &mut cmd,
+            ["check_foo", "--", "--generate-completions", "bash"],
+            &mut out
+        ));
+        assert!(write_requested_completions(
+            &mut cmd,
+            ["check_foo", "--generate-completions=bash"],
+            &mut out
+        ));
+        assert!(String::from_utf8(out).unwrap().contains("MiB"));
+    }
+}
@@ -0,0 +1,176 @@
+//! The names of the 0.x API mapped onto the current types, so plugins can be upgraded
+//! incrementally. Everything in here is deprecated and will be removed in a future release.
+//!
+//! ## Example
+//!
+//! ```
+//! #![allow(deprecated)]
+//! use nagiosplugin::compat::{Resource, SimpleMetric, State};
+//!
+//! let mut resource = Resource::new(None, Some("all good".to_owned()));
+//! resource.push(SimpleMetric::new("test", None, 15, Some(20), Some(50), None, None));
+//!
+//! let (state, output) = resource.nagios_result();
+//! assert_eq!(state, State::Ok);
+//! assert_eq!(output, "OK: all good|'test'=15;20;50;;");
+//! ```
+#![allow(deprecated)]
+
+use std::ops::{Deref, DerefMut};
+
+use crate::{CheckResult, Metric, ServiceState, SummaryHeader, ToPerfString, TriggerIfValue};
+
+/// The state of a check.
+#[deprecated(note = "use `ServiceState` instead")]
+pub type State = ServiceState;
+
+/// A metric with an optional fixed state, which is alerted if its value exceeds the thresholds.
+#[deprecated(note = "use `Metric` with `TriggerIfValue::Greater` instead")]
+#[derive(Debug, Clone)]
+pub struct SimpleMetric<T> {
+    name: String,
+    state: Option<State>,
+    value: T,
+    warning: Option<T>,
+    critical: Option<T>,
+    min: Option<T>,
+    max: Option<T>,
+}
+
+impl<T> SimpleMetric<T> {
+    pub fn new(
+        name: impl Into<String>,
+        state: Option<State>,
+        value: T,
+        warning: Option<T>,
+        critical: Option<T>,
+        min: Option<T>,
+        max: Option<T>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            state,
+            value,
+            warning,
+            critical,
+            min,
+            max,
+        }
+    }
+}
+
+impl<T> From<SimpleMetric<T>> for Metric<T> {
+    fn from(m: SimpleMetric<T>) -> Self {
+        let mut metric = Metric::new(m.name, m.value).with_thresholds(
+            m.warning,
+            m.critical,
+            TriggerIfValue::Greater,
+        );
+        if let Some(state) = m.state {
+            metric = metric.with_fixed_state(state);
+        }
+        if let Some(min) = m.min {
+            metric = metric.with_minimum(min);
+        }
+        if let Some(max) = m.max {
+            metric = metric.with_maximum(max);
+        }
+        metric
+    }
+}
+
+impl<T: PartialOrd + ToPerfString> From<SimpleMetric<T>> for CheckResult {
+    fn from(m: SimpleMetric<T>) -> Self {
+        Metric::from(m).into()
+    }
+}
+
+/// A [crate::Resource] created the 0.x way. The summary starts with the state instead of the
+/// resource name, like it used to. All methods of [crate::Resource] taking a reference are
+/// available, use [Resource::into_inner] for the remaining ones.
+#[deprecated(note = "use `nagiosplugin::Resource` instead")]
+pub struct Resource(crate::Resource);
+
+impl Resource {
+    pub fn new(state: Option<State>, description: Option<String>) -> Self {
+        let mut resource = crate::Resource::new("").with_summary_header(SummaryHeader::State);
+        if let Some(state) = state {
+            resource = resource.with_fixed_state(state);
+        }
+        if let Some(description) = description {
+            resource.set_description(description);
+        }
+        Self(resource)
+    }
+
+    pub fn push(&mut self, result: impl Into<CheckResult>) {
+        self.0.push_result(result);
+    }
+
+    pub fn into_inner(self) -> crate::Resource {
+        self.0
+    }
+
+    /// See [crate::Resource::nagios_result].
+    pub fn nagios_result(self) -> (State, String) {
+        self.0.nagios_result()
+    }
+
+    /// Prints the result to stdout and exits with the corresponding exit code.
+    pub fn print_and_exit(self) -> ! {
+        self.0.print_and_exit()
+    }
+}
+
+impl Deref for Resource {
+    type Target = crate::Resource;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Resource {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<Resource> for crate::Resource {
+    fn from(resource: Resource) -> Self {
+        resource.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compat() {
+        let mut resource = Resource::new(None, None);
+        resource.push(SimpleMetric::new(
+            "a",
+            None,
+            42,
+            Some(40),
+            Some(50),
+            Some(0),
+            None,
+        ));
+        resource.push(SimpleMetric::new(
+            "b",
+            Some(State::Unknown),
+            1,
+            None,
+            None,
+            None,
+            None,
+        ));
+        assert_eq!(resource.state(), State::Warning);
+
+        let (state, output) = Resource::new(Some(State::Critical), None).nagios_result();
+        assert_eq!(state, State::Critical);
+        assert_eq!(output, "CRITICAL");
+    }
+}
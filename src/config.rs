@@ -0,0 +1,244 @@
+//! Resolves plugin settings from several layers. A value given on the command line takes
+//! precedence over an environment variable, which takes precedence over the config file, which
+//! takes precedence over the default.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use nagiosplugin::config::Config;
+//!
+//! let config = Config::new()
+//!     .with_default("warning", "80")
+//!     .with_key("hostname")
+//!     .with_file("/etc/nagios/check_foo.conf")
+//!     .unwrap()
+//!     .with_env_prefix("CHECK_FOO_")
+//!     .with_args(std::env::args());
+//!
+//! let warning: f64 = config.value("warning").unwrap().parse().unwrap();
+//! for line in config.explanation() {
+//!     eprintln!("{}", line);
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Where the effective value of a key came from, in order of precedence.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConfigSource {
+    CommandLine,
+    Environment,
+    File,
+    Default,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConfigSource::CommandLine => "command line",
+            ConfigSource::Environment => "environment",
+            ConfigSource::File => "config file",
+            ConfigSource::Default => "default",
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+/// This error is returned if the config file can't be read.
+pub enum ConfigError {
+    #[error("failed to read config file '{0}': {1}")]
+    Io(PathBuf, #[source] std::io::Error),
+    #[error("invalid line {line} in config file, expected 'key = value': {content}")]
+    Syntax { line: usize, content: String },
+}
+
+/// The layered settings of a plugin. Only declared keys, see [Config::with_key] and
+/// [Config::with_default], are looked up, so unrelated arguments and environment variables are
+/// ignored.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    keys: Vec<String>,
+    values: BTreeMap<String, BTreeMap<ConfigSource, String>>,
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares a key without default value.
+    pub fn with_key(mut self, key: impl Into<String>) -> Self {
+        let key = key.into();
+        if !self.keys.contains(&key) {
+            self.keys.push(key);
+        }
+        self
+    }
+
+    /// Declares a key with the given default value.
+    pub fn with_default(self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        self.with_key(key.clone())
+            .with_value(key, ConfigSource::Default, value.into())
+    }
+
+    /// Reads the config file at the given path, see [Config::with_file_contents].
+    pub fn with_file(self, path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let contents =
+            std::fs::read_to_string(path).map_err(|err| ConfigError::Io(path.to_owned(), err))?;
+        self.with_file_contents(&contents)
+    }
+
+    /// Reads the values from a config file with one `key = value` per line. Empty lines and lines
+    /// starting with `#` are ignored, as are undeclared keys.
+    pub fn with_file_contents(mut self, contents: &str) -> Result<Self, ConfigError> {
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or_else(|| ConfigError::Syntax {
+                line: index + 1,
+                content: line.to_owned(),
+            })?;
+            self = self.with_declared_value(key.trim(), ConfigSource::File, value.trim());
+        }
+
+        Ok(self)
+    }
+
+    /// Reads the environment variables `<prefix><KEY>`, with the key in upper case and `-`
+    /// replaced by `_`, e.g. `CHECK_FOO_WARNING` for the key `warning`.
+    pub fn with_env_prefix(self, prefix: &str) -> Self {
+        self.with_env(prefix, std::env::vars())
+    }
+
+    fn with_env(mut self, prefix: &str, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        let vars: BTreeMap<_, _> = vars.into_iter().collect();
+        for key in self.keys.clone() {
+            let name = format!("{}{}", prefix, key.to_uppercase().replace('-', "_"));
+            if let Some(value) = vars.get(&name) {
+                self = self.with_value(key, ConfigSource::Environment, value.clone());
+            }
+        }
+        self
+    }
+
+    /// Reads `--key value` and `--key=value` from the given arguments.
+    pub fn with_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(arg) = arg.as_ref().strip_prefix("--") else {
+                continue;
+            };
+            let (key, value) = match arg.split_once('=') {
+                Some((key, value)) => (key.to_owned(), Some(value.to_owned())),
+                None => (arg.to_owned(), None),
+            };
+            if !self.keys.contains(&key) {
+                continue;
+            }
+
+            let value = value.or_else(|| args.next().map(|v| v.as_ref().to_owned()));
+            if let Some(value) = value {
+                self = self.with_value(key, ConfigSource::CommandLine, value);
+            }
+        }
+        self
+    }
+
+    /// Returns the effective value of the key.
+    pub fn value(&self, key: &str) -> Option<&str> {
+        self.get(key).map(|(value, _)| value)
+    }
+
+    /// Returns the effective value of the key and where it came from.
+    pub fn get(&self, key: &str) -> Option<(&str, ConfigSource)> {
+        let (source, value) = self.values.get(key)?.iter().next()?;
+        Some((value, *source))
+    }
+
+    /// Returns a line per declared key with the effective value and its source, e.g.
+    /// `config: warning = '80' (from default)`.
+    pub fn explanation(&self) -> Vec<String> {
+        self.keys
+            .iter()
+            .map(|key| match self.get(key) {
+                Some((value, source)) => {
+                    format!("config: {} = '{}' (from {})", key, value, source)
+                }
+                None => format!("config: {} is not set", key),
+            })
+            .collect()
+    }
+
+    fn with_declared_value(self, key: &str, source: ConfigSource, value: &str) -> Self {
+        if self.keys.iter().any(|k| k == key) {
+            self.with_value(key.to_owned(), source, value.to_owned())
+        } else {
+            self
+        }
+    }
+
+    fn with_value(mut self, key: String, source: ConfigSource, value: String) -> Self {
+        self.values.entry(key).or_default().insert(source, value);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precedence() {
+        let config = Config::new()
+            .with_default("warning", "80")
+            .with_default("critical", "90")
+            .with_default("timeout", "10")
+            .with_key("host-name")
+            .with_file_contents("# comment\nwarning = 70\ncritical=85\nunknown = 1\n")
+            .unwrap()
+            .with_env(
+                "CHECK_",
+                [
+                    ("CHECK_CRITICAL".to_owned(), "95".to_owned()),
+                    ("CHECK_HOST_NAME".to_owned(), "db1".to_owned()),
+                ],
+            )
+            .with_args(["check", "--host-name", "db2", "--other", "x", "--timeout=5"]);
+
+        assert_eq!(config.get("warning"), Some(("70", ConfigSource::File)));
+        assert_eq!(
+            config.get("critical"),
+            Some(("95", ConfigSource::Environment))
+        );
+        assert_eq!(
+            config.get("host-name"),
+            Some(("db2", ConfigSource::CommandLine))
+        );
+        assert_eq!(config.value("timeout"), Some("5"));
+        assert_eq!(config.value("unknown"), None);
+        assert_eq!(
+            config.explanation()[0],
+            "config: warning = '70' (from config file)"
+        );
+    }
+
+    #[test]
+    fn test_file_syntax_error() {
+        assert!(matches!(
+            Config::new().with_file_contents("\nfoo\n"),
+            Err(ConfigError::Syntax { line: 2, .. })
+        ));
+    }
+}
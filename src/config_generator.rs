@@ -0,0 +1,320 @@
+//! Generation of Icinga 2 configuration for plugins.
+//!
+//! ## Example
+//!
+//! ```
+//! use nagiosplugin::config_generator::{Argument, CommandDescription};
+//!
+//! let command = CommandDescription::new("check_foo")
+//!     .with_argument(
+//!         Argument::new("-w")
+//!             .with_value("$foo_warning$")
+//!             .with_description("Warning threshold")
+//!             .required(),
+//!     )
+//!     .with_argument(Argument::new("--verbose").with_set_if("$foo_verbose$"))
+//!     .with_default_var("foo_warning", "80");
+//!
+//! let config = command.to_string();
+//! assert!(config.starts_with("object CheckCommand \"check_foo\" {"));
+//! ```
+use std::fmt;
+use std::fmt::Formatter;
+
+/// Describes an Icinga 2 `CheckCommand` object. The [fmt::Display] implementation renders the
+/// object in the Icinga 2 DSL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandDescription {
+    name: String,
+    command: Command,
+    arguments: Vec<Argument>,
+    vars: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+    PluginDir(String),
+    Path(String),
+}
+
+impl CommandDescription {
+    /// Creates a new description. The command defaults to the executable with the same name
+    /// inside of `PluginDir`.
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+        Self {
+            command: Command::PluginDir(name.clone()),
+            name,
+            arguments: Default::default(),
+            vars: Default::default(),
+        }
+    }
+
+    /// Sets the name of the executable inside of `PluginDir`.
+    pub fn with_plugin_dir_command(mut self, executable: impl Into<String>) -> Self {
+        self.command = Command::PluginDir(executable.into());
+        self
+    }
+
+    /// Sets an absolute path to the executable.
+    pub fn with_command_path(mut self, path: impl Into<String>) -> Self {
+        self.command = Command::Path(path.into());
+        self
+    }
+
+    pub fn with_argument(mut self, argument: Argument) -> Self {
+        self.push_argument(argument);
+        self
+    }
+
+    pub fn push_argument(&mut self, argument: Argument) {
+        self.arguments.push(argument);
+    }
+
+    /// Adds a default value for a custom variable (`vars.<name> = "<value>"`).
+    pub fn with_default_var(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.vars.push((name.into(), value.into()));
+        self
+    }
+}
+
+impl fmt::Display for CommandDescription {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "object CheckCommand {} {{", quote(&self.name))?;
+
+        match &self.command {
+            Command::PluginDir(executable) => writeln!(
+                f,
+                "  command = [ PluginDir + {} ]",
+                quote(&format!("/{}", executable))
+            )?,
+            Command::Path(path) => writeln!(f, "  command = [ {} ]", quote(path))?,
+        }
+
+        if !self.arguments.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "  arguments = {{")?;
+            for argument in &self.arguments {
+                argument.fmt_attributes(f)?;
+            }
+            writeln!(f, "  }}")?;
+        }
+
+        if !self.vars.is_empty() {
+            writeln!(f)?;
+            for (name, value) in &self.vars {
+                writeln!(f, "  {} = {}", var_reference(name), quote(value))?;
+            }
+        }
+
+        writeln!(f, "}}")
+    }
+}
+
+/// A single entry of the `arguments` dictionary of a [CommandDescription].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Argument {
+    key: String,
+    value: Option<String>,
+    description: Option<String>,
+    set_if: Option<String>,
+    required: bool,
+    skip_key: bool,
+    repeat_key: Option<bool>,
+    order: Option<i32>,
+}
+
+impl Argument {
+    /// Creates a new argument with the given key, e.g. `-w` or `--warning`.
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            value: Default::default(),
+            description: Default::default(),
+            set_if: Default::default(),
+            required: Default::default(),
+            skip_key: Default::default(),
+            repeat_key: Default::default(),
+            order: Default::default(),
+        }
+    }
+
+    /// Sets the value, usually a runtime macro like `$foo_warning$`.
+    pub fn with_value(mut self, value: impl Into<String>) -> Self {
+        self.value = Some(value.into());
+        self
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// The argument is only passed if the given runtime macro evaluates to true. Used for flags.
+    pub fn with_set_if(mut self, set_if: impl Into<String>) -> Self {
+        self.set_if = Some(set_if.into());
+        self
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Only the value is passed, without the key. Used for positional arguments.
+    pub fn skip_key(mut self) -> Self {
+        self.skip_key = true;
+        self
+    }
+
+    /// Sets if the key is repeated for every element if the value is an array.
+    pub fn with_repeat_key(mut self, repeat_key: bool) -> Self {
+        self.repeat_key = Some(repeat_key);
+        self
+    }
+
+    pub fn with_order(mut self, order: i32) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    fn fmt_attributes(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "    {} = {{", quote(&self.key))?;
+        if let Some(value) = &self.value {
+            // The value is meant to contain runtime macros, so `$` must not be escaped.
+            writeln!(f, "      value = \"{}\"", escape_macro_string(value))?;
+        }
+        if let Some(description) = &self.description {
+            writeln!(f, "      description = {}", quote(description))?;
+        }
+        if let Some(set_if) = &self.set_if {
+            writeln!(f, "      set_if = \"{}\"", escape_macro_string(set_if))?;
+        }
+        if self.required {
+            writeln!(f, "      required = true")?;
+        }
+        if self.skip_key {
+            writeln!(f, "      skip_key = true")?;
+        }
+        if let Some(repeat_key) = self.repeat_key {
+            writeln!(f, "      repeat_key = {}", repeat_key)?;
+        }
+        if let Some(order) = self.order {
+            writeln!(f, "      order = {}", order)?;
+        }
+        writeln!(f, "    }}")
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", escape_string(s))
+}
+
+/// Returns `vars.<name>`, or `vars["<name>"]` if the name is not a valid identifier.
+fn var_reference(name: &str) -> String {
+    let is_identifier = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if is_identifier {
+        format!("vars.{}", name)
+    } else {
+        format!("vars[{}]", quote(name))
+    }
+}
+
+/// Escapes a string for use inside of a double quoted Icinga 2 string, including `$`, so it is
+/// not treated as a runtime macro.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::config_generator::escape_string;
+///
+/// assert_eq!(escape_string("C:\\temp \"$5\"\n"), r#"C:\\temp \"$$5\"\n"#);
+/// ```
+pub fn escape_string(s: &str) -> String {
+    escape(s, true)
+}
+
+/// Like [escape_string], but `$` is kept, for strings which contain runtime macros like
+/// `$foo_warning$`.
+pub fn escape_macro_string(s: &str) -> String {
+    escape(s, false)
+}
+
+/// The hidden argument which makes plugins generated with [crate::plugin_main] print their
+/// `CheckCommand` object instead of running the check.
+pub const GENERATE_ARG: &str = "--generate-icinga-config";
+
+/// Returns whether [GENERATE_ARG] is given before the first `--`.
+pub fn is_requested<I, S>(args: I) -> bool
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    args.into_iter()
+        .map_while(|arg| (arg.as_ref() != "--").then_some(arg))
+        .any(|arg| arg.as_ref() == GENERATE_ARG)
+}
+
+fn escape(s: &str, dollar: bool) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{c}' => escaped.push_str("\\f"),
+            '$' if dollar => escaped.push_str("$$"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_requested() {
+        assert!(is_requested(["check_foo", "-v", GENERATE_ARG]));
+        assert!(!is_requested(["check_foo", "--", GENERATE_ARG]));
+        assert!(!is_requested(["check_foo"]));
+    }
+
+    #[test]
+    fn test_command_description_display() {
+        let config = CommandDescription::new("check_foo")
+            .with_argument(
+                Argument::new("-w")
+                    .with_value("$foo_warning$")
+                    .with_description("Warning \"threshold\"")
+                    .required(),
+            )
+            .with_default_var("foo_warning", "80$")
+            .with_default_var("foo-path", "C:\\temp\n")
+            .to_string();
+
+        assert_eq!(
+            config,
+            r#"object CheckCommand "check_foo" {
+  command = [ PluginDir + "/check_foo" ]
+
+  arguments = {
+    "-w" = {
+      value = "$foo_warning$"
+      description = "Warning \"threshold\""
+      required = true
+    }
+  }
+
+  vars.foo_warning = "80$$"
+  vars["foo-path"] = "C:\\temp\n"
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::{json, CheckResult, MetricEvaluation, Resource, TriggerIfValue};
+
+/// Defines where the debug dump of a [Resource] is written to. See [Resource::with_debug_json].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebugJson {
+    Stderr,
+    /// The dump is appended to the given file.
+    File(PathBuf),
+}
+
+impl DebugJson {
+    pub(crate) fn write(&self, dump: &str) -> std::io::Result<()> {
+        match self {
+            DebugJson::Stderr => writeln!(std::io::stderr(), "{}", dump),
+            DebugJson::File(path) => {
+                let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                writeln!(file, "{}", dump)
+            }
+        }
+    }
+}
+
+pub(crate) fn to_json(resource: &Resource) -> String {
+    let results = resource
+        .results
+        .iter()
+        .map(result_to_json)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    format!(
+        "{{\"name\":{},\"state\":{},\"fixed_state\":{},\"fixed_state_reason\":{},\"results\":[{}]}}",
+        json::quote(&resource.name),
+        json::quote(&resource.state().to_string()),
+        json::opt(resource.fixed_state.map(|s| s.to_string()).as_deref()),
+        json::opt(resource.fixed_state_reason.as_deref()),
+        results
+    )
+}
+
+fn result_to_json(result: &CheckResult) -> String {
+    format!(
+        "{{\"state\":{},\"message\":{},\"details\":[{}],\"perf_data\":[{}],\"evaluation\":{}}}",
+        json::opt(result.state.map(|s| s.to_string()).as_deref()),
+        json::opt(result.message.as_deref()),
+        result
+            .details
+            .iter()
+            .map(|d| json::quote(d))
+            .collect::<Vec<_>>()
+            .join(","),
+        result
+            .perf_strings
+            .iter()
+            .map(json::perf_string)
+            .collect::<Vec<_>>()
+            .join(","),
+        result
+            .evaluation
+            .as_ref()
+            .map_or_else(|| "null".to_owned(), evaluation_to_json),
+    )
+}
+
+fn evaluation_to_json(e: &MetricEvaluation) -> String {
+    let trigger = e.trigger.map(|t| match t {
+        TriggerIfValue::Greater => "greater",
+        TriggerIfValue::Less => "less",
+    });
+
+    format!(
+        "{{\"metric\":{},\"value\":{},\"warning\":{},\"critical\":{},\"trigger\":{},\"fixed_state\":{},\"state\":{}}}",
+        json::quote(&e.name),
+        json::quote(&e.value),
+        json::opt(e.warning.as_deref()),
+        json::opt(e.critical.as_deref()),
+        json::opt(trigger),
+        json::opt(e.fixed_state.map(|s| s.to_string()).as_deref()),
+        json::opt(e.state.map(|s| s.to_string()).as_deref()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Metric;
+
+    #[test]
+    fn test_to_json() {
+        let resource = Resource::new("foo").with_result(Metric::new("a", 42).with_thresholds(
+            40,
+            50,
+            TriggerIfValue::Greater,
+        ));
+
+        assert_eq!(
+            to_json(&resource),
+            "{\"name\":\"foo\",\"state\":\"WARNING\",\"fixed_state\":null,\"fixed_state_reason\":null,\
+             \"results\":[{\
+             \"state\":\"WARNING\",\
+             \"message\":\"metric 'a' is WARNING: value '42' has exceeded threshold of '40'\",\
+             \"details\":[],\
+             \"perf_data\":[{\"label\":\"a\",\"value\":\"42\",\"unit\":\"\",\"warning\":\"40\",\
+             \"critical\":\"50\",\"min\":\"\",\"max\":\"\"}],\
+             \"evaluation\":{\"metric\":\"a\",\"value\":\"42\",\"warning\":\"40\",\"critical\":\"50\",\
+             \"trigger\":\"greater\",\"fixed_state\":null,\"state\":\"WARNING\"}}]}"
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ServiceState;
+
+/// A class of errors an [ErrorPolicy] can assign a state to. As checks can return any error
+/// type, errors are classified by their message. The predefined classes match the messages the
+/// operating system uses for the corresponding I/O errors, which are contained in the messages of
+/// most error types wrapping them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorClass {
+    ConnectionRefused,
+    ConnectionReset,
+    HostUnreachable,
+    TimedOut,
+    NotFound,
+    PermissionDenied,
+    /// Matches errors whose message contains the given text, ignoring case.
+    MessageContains(String),
+}
+
+impl ErrorClass {
+    pub fn matches(&self, message: &str) -> bool {
+        let message = message.to_lowercase();
+        let patterns: &[&str] = match self {
+            ErrorClass::ConnectionRefused => &["connection refused"],
+            ErrorClass::ConnectionReset => &["connection reset"],
+            ErrorClass::HostUnreachable => &["host unreachable", "no route to host"],
+            ErrorClass::TimedOut => &["timed out", "timeout"],
+            ErrorClass::NotFound => &["not found", "no such file or directory"],
+            ErrorClass::PermissionDenied => &["permission denied"],
+            ErrorClass::MessageContains(text) => {
+                return message.contains(&text.to_lowercase());
+            }
+        };
+        patterns.iter().any(|p| message.contains(p))
+    }
+}
+
+impl fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorClass::ConnectionRefused => f.write_str("connection-refused"),
+            ErrorClass::ConnectionReset => f.write_str("connection-reset"),
+            ErrorClass::HostUnreachable => f.write_str("host-unreachable"),
+            ErrorClass::TimedOut => f.write_str("timed-out"),
+            ErrorClass::NotFound => f.write_str("not-found"),
+            ErrorClass::PermissionDenied => f.write_str("permission-denied"),
+            ErrorClass::MessageContains(text) => write!(f, "message:{}", text),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+/// This error is returned by the [FromStr] implementations of [ErrorClass] and [ErrorPolicy].
+pub enum ErrorPolicyParseError {
+    #[error("unknown error class '{0}'")]
+    UnknownClass(String),
+    #[error("expected '<class>=<state>', got '{0}'")]
+    InvalidRule(String),
+    #[error("invalid state in rule '{0}': expected one of: ok, warning, critical, unknown")]
+    InvalidState(String),
+}
+
+impl FromStr for ErrorClass {
+    type Err = ErrorPolicyParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(text) = s.strip_prefix("message:") {
+            return Ok(ErrorClass::MessageContains(text.to_owned()));
+        }
+
+        match s.to_lowercase().as_str() {
+            "connection-refused" => Ok(ErrorClass::ConnectionRefused),
+            "connection-reset" => Ok(ErrorClass::ConnectionReset),
+            "host-unreachable" => Ok(ErrorClass::HostUnreachable),
+            "timed-out" => Ok(ErrorClass::TimedOut),
+            "not-found" => Ok(ErrorClass::NotFound),
+            "permission-denied" => Ok(ErrorClass::PermissionDenied),
+            _ => Err(ErrorPolicyParseError::UnknownClass(s.to_owned())),
+        }
+    }
+}
+
+/// Assigns states to classes of errors, so e.g. refused connections can be reported as
+/// [ServiceState::Warning] during a deploy window instead of the error state of the
+/// [Runner](crate::Runner). The first matching rule wins. See [Runner::with_error_policy].
+///
+/// The policy can be parsed from a comma separated list of `<class>=<state>` rules, which makes it
+/// easy to pass it as a command line argument.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{ErrorClass, ErrorPolicy, ServiceState};
+///
+/// let policy: ErrorPolicy = "connection-refused=warning,message:maintenance=ok".parse().unwrap();
+///
+/// assert_eq!(
+///     policy,
+///     ErrorPolicy::new()
+///         .with_rule(ErrorClass::ConnectionRefused, ServiceState::Warning)
+///         .with_rule(ErrorClass::MessageContains("maintenance".to_owned()), ServiceState::Ok)
+/// );
+/// assert_eq!(
+///     policy.state_for("Connection refused (os error 111)"),
+///     Some(ServiceState::Warning)
+/// );
+/// ```
+///
+/// [Runner::with_error_policy]: crate::Runner::with_error_policy
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorPolicy {
+    rules: Vec<(ErrorClass, ServiceState)>,
+}
+
+impl ErrorPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rule(mut self, class: ErrorClass, state: ServiceState) -> Self {
+        self.push_rule(class, state);
+        self
+    }
+
+    pub fn push_rule(&mut self, class: ErrorClass, state: ServiceState) {
+        self.rules.push((class, state));
+    }
+
+    /// Returns the state of the first rule matching the given error message.
+    pub fn state_for(&self, message: &str) -> Option<ServiceState> {
+        self.rules
+            .iter()
+            .find(|(class, _)| class.matches(message))
+            .map(|(_, state)| *state)
+    }
+}
+
+impl FromStr for ErrorPolicy {
+    type Err = ErrorPolicyParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut policy = ErrorPolicy::new();
+        for rule in s.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+            let (class, state) = rule
+                .rsplit_once('=')
+                .ok_or_else(|| ErrorPolicyParseError::InvalidRule(rule.to_owned()))?;
+            let state = state
+                .trim()
+                .parse()
+                .map_err(|_| ErrorPolicyParseError::InvalidState(rule.to_owned()))?;
+            policy.push_rule(class.trim().parse()?, state);
+        }
+        Ok(policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_policy() {
+        let policy: ErrorPolicy = "timed-out=ok, connection-refused=warning".parse().unwrap();
+
+        assert_eq!(
+            policy.state_for("operation timed out"),
+            Some(ServiceState::Ok)
+        );
+        assert_eq!(
+            policy.state_for("failed to connect: Connection refused (os error 111)"),
+            Some(ServiceState::Warning)
+        );
+        assert_eq!(policy.state_for("invalid response"), None);
+
+        assert!("foo=ok".parse::<ErrorPolicy>().is_err());
+        assert!("timed-out".parse::<ErrorPolicy>().is_err());
+        assert!("timed-out=bad".parse::<ErrorPolicy>().is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{CheckResult, Resource, ServiceState, StateFile};
+
+impl Resource {
+    /// Escalates [ServiceState::Warning] to [ServiceState::Critical] once the resource has been in
+    /// a problem state for at least the given duration, e.g. a warning which persists for more
+    /// than 30 minutes becomes critical. The start of the problem is kept in the state file under
+    /// `<name>.problem_since` and the elapsed time is added to the messages. The caller has to
+    /// [save](StateFile::save) the state file afterwards.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use nagiosplugin::{state_dir, Metric, Resource, StateFile, TriggerIfValue};
+    ///
+    /// let mut state = StateFile::open(state_dir("check_queue").unwrap().join("state")).unwrap();
+    /// let resource = Resource::new("queue")
+    ///     .with_result(Metric::new("length", 120).with_thresholds(100, 1000, TriggerIfValue::Greater))
+    ///     .with_escalation(&mut state, Duration::from_secs(30 * 60));
+    /// state.save().unwrap();
+    /// ```
+    pub fn with_escalation(self, state: &mut StateFile, after: Duration) -> Self {
+        self.escalate(state, after, SystemTime::now())
+    }
+
+    fn escalate(self, state: &mut StateFile, after: Duration, now: SystemTime) -> Self {
+        let key = format!("{}.problem_since", self.name());
+        let current = self.state();
+        if !matches!(current, ServiceState::Warning | ServiceState::Critical) {
+            state.remove(&key);
+            return self;
+        }
+
+        let now = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let since = match state.get(&key).and_then(|v| v.parse::<u64>().ok()) {
+            Some(since) if since <= now => since,
+            _ => {
+                state.set(key, now.to_string());
+                now
+            }
+        };
+        let elapsed = Duration::from_secs(now - since);
+        let message = format!("{} for {}", current, format_duration(elapsed));
+
+        if current == ServiceState::Warning && elapsed >= after {
+            let reason = format!("{}, escalated after {}", message, format_duration(after));
+            self.with_fixed_state_reason(ServiceState::Critical, reason)
+        } else {
+            self.with_result(CheckResult::new().with_message(message))
+        }
+    }
+}
+
+/// Formats the duration with its two largest units, e.g. `1h 5m` or `42s`.
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let parts = [
+        (seconds / 86400, "d"),
+        (seconds / 3600 % 24, "h"),
+        (seconds / 60 % 60, "m"),
+        (seconds % 60, "s"),
+    ];
+
+    let first = parts.iter().position(|(v, _)| *v > 0).unwrap_or(3);
+    parts[first..]
+        .iter()
+        .take(2)
+        .filter(|(v, _)| *v > 0 || first == 3)
+        .map(|(v, unit)| format!("{}{}", v, unit))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Metric, TriggerIfValue};
+
+    fn resource(value: i64) -> Resource {
+        Resource::new("queue").with_result(Metric::new("length", value).with_thresholds(
+            100,
+            1000,
+            TriggerIfValue::Greater,
+        ))
+    }
+
+    #[test]
+    fn test_escalate() {
+        let mut state = StateFile::open("/nonexistent/state").unwrap();
+        let after = Duration::from_secs(1800);
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        let r = resource(120).escalate(&mut state, after, start);
+        assert_eq!(r.state(), ServiceState::Warning);
+        assert_eq!(state.get("queue.problem_since"), Some("1700000000"));
+
+        let r = resource(120).escalate(&mut state, after, start + Duration::from_secs(600));
+        assert_eq!(
+            r.nagios_result().1,
+            "queue is WARNING\n\n\
+             metric 'length' is WARNING: value '120' has exceeded threshold of '100'\n\
+             WARNING for 10m\n|'length'=120;100;1000;;"
+        );
+
+        let r = resource(120).escalate(&mut state, after, start + Duration::from_secs(1830));
+        assert_eq!(r.state(), ServiceState::Critical);
+        assert!(r
+            .nagios_result()
+            .1
+            .contains("(state forced to CRITICAL: WARNING for 30m 30s, escalated after 30m)"));
+
+        resource(1).escalate(&mut state, after, start + Duration::from_secs(2000));
+        assert_eq!(state.get("queue.problem_since"), None);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(0)), "0s");
+        assert_eq!(format_duration(Duration::from_secs(3900)), "1h 5m");
+        assert_eq!(format_duration(Duration::from_secs(90061)), "1d 1h");
+    }
+}
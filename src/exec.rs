@@ -0,0 +1,403 @@
+//! Helpers to run external programs, e.g. `smartctl` or vendor tools, from within a check.
+//!
+//! The environment of a plugin is controlled by whoever runs it, which matters as soon as a plugin
+//! runs with elevated privileges. [secure_command] therefore starts programs with a minimal
+//! environment, as recommended by the security section of the plugin development guidelines.
+//! [run] builds on it and covers the common case of running a program to completion.
+//! [ExitCodePolicy] turns the exit status of a wrapped plugin into a state.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use nagiosplugin::exec::{secure_command, wait_with_timeout};
+//!
+//! let mut child = secure_command("/usr/sbin/smartctl")
+//!     .args(["-H", "/dev/sda"])
+//!     .spawn()
+//!     .unwrap();
+//! let status = wait_with_timeout(&mut child, Duration::from_secs(10)).unwrap();
+//! ```
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::io::Read;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{CheckResult, ServiceState};
+
+/// The `PATH` programs started by [secure_command] see.
+pub const SECURE_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+/// Variables which are passed on from the environment of the plugin.
+const PASSED_VARIABLES: &[&str] = &["TZ"];
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// How long output is still read after the program exited or was killed.
+const READ_GRACE: Duration = Duration::from_millis(100);
+
+/// Creates a [Command] with a sanitized environment. All variables are removed, so e.g.
+/// `LD_PRELOAD`, `LD_LIBRARY_PATH` or `IFS` of the caller can't influence the program. Only `TZ`
+/// is passed on, `PATH` is set to [SECURE_PATH] and `LC_ALL` to `C`, so the output can be parsed
+/// reliably. Further variables can be added to the returned command as usual.
+///
+/// Prefer absolute paths for the program, relative names are looked up in [SECURE_PATH].
+pub fn secure_command(program: impl AsRef<OsStr>) -> Command {
+    secure_command_with_env(program, |name| std::env::var_os(name))
+}
+
+/// Like [secure_command], with the environment of the plugin given by `var`.
+fn secure_command_with_env(
+    program: impl AsRef<OsStr>,
+    var: impl Fn(&str) -> Option<OsString>,
+) -> Command {
+    let mut command = Command::new(program);
+    command
+        .env_clear()
+        .env("PATH", SECURE_PATH)
+        .env("LC_ALL", "C");
+
+    for name in PASSED_VARIABLES {
+        if let Some(value) = var(name) {
+            command.env(name, value);
+        }
+    }
+
+    command
+}
+
+/// The result of [run].
+#[derive(Debug, Clone)]
+pub struct ExecOutput {
+    pub status: ExitStatus,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration: Duration,
+    /// Whether the program was killed because it exceeded the timeout. The output contains
+    /// whatever was written until then.
+    pub timed_out: bool,
+}
+
+/// Runs the program with the given arguments using [secure_command] and captures its output.
+/// If the program is still running after the given timeout it is killed. Invalid UTF-8 in the
+/// output is replaced.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// let output = nagiosplugin::exec::run("smartctl", ["-H", "/dev/sda"], Duration::from_secs(10))
+///     .unwrap();
+/// if output.timed_out {
+///     // ...
+/// }
+/// ```
+pub fn run<I, S>(program: impl AsRef<OsStr>, args: I, timeout: Duration) -> io::Result<ExecOutput>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let start = Instant::now();
+    let mut child = secure_command(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Both pipes are drained concurrently, otherwise a program filling one of them blocks.
+    let stdout = Capture::start(child.stdout.take());
+    let stderr = Capture::start(child.stderr.take());
+    let (status, timed_out) = wait_or_kill(&mut child, timeout)?;
+    let duration = start.elapsed();
+
+    let deadline = start + timeout;
+    Ok(ExecOutput {
+        status,
+        stdout: stdout.finish(deadline)?,
+        stderr: stderr.finish(deadline)?,
+        duration,
+        timed_out,
+    })
+}
+
+/// Waits for the child to exit. If it is still running after the given timeout it is killed and an
+/// error of kind [io::ErrorKind::TimedOut] is returned.
+pub fn wait_with_timeout(child: &mut Child, timeout: Duration) -> io::Result<ExitStatus> {
+    match wait_or_kill(child, timeout)? {
+        (status, false) => Ok(status),
+        (_, true) => Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("process timed out after {} seconds", timeout.as_secs_f64()),
+        )),
+    }
+}
+
+/// Maps the exit status of a wrapped plugin or tool to a state. The codes 0 to 3 map to the states
+/// as defined by the plugin API. Other codes and deaths by signal, which would otherwise be
+/// misread by the monitoring system, are [ServiceState::Unknown] by default with a reason which
+/// names the original code or signal, e.g. `command not found (exit code 127)` or `timed out
+/// (killed by signal 9)`.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use nagiosplugin::exec::{run, ExitCodePolicy};
+/// use nagiosplugin::{Resource, ServiceState};
+///
+/// let output = run("/usr/lib/nagios/plugins/check_disk", ["-w", "10%"], Duration::from_secs(10))
+///     .unwrap();
+/// let policy = ExitCodePolicy::new().with_code(4, ServiceState::Critical, "disk unavailable");
+/// let resource = Resource::new("disk").with_result(policy.evaluate(&output.status));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExitCodePolicy {
+    codes: BTreeMap<i32, (ServiceState, String)>,
+    signals: BTreeMap<i32, (ServiceState, String)>,
+    default_state: ServiceState,
+}
+
+impl Default for ExitCodePolicy {
+    fn default() -> Self {
+        let entry = |reason: &str| (ServiceState::Unknown, reason.to_owned());
+        Self {
+            codes: BTreeMap::from([
+                (126, entry("command not executable")),
+                (127, entry("command not found")),
+            ]),
+            signals: BTreeMap::from([
+                (2, entry("interrupted")),
+                (6, entry("aborted")),
+                (9, entry("timed out")),
+                (11, entry("crashed")),
+                (15, entry("terminated")),
+            ]),
+            default_state: ServiceState::Unknown,
+        }
+    }
+}
+
+impl ExitCodePolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps the exit code to the state with the given reason. Also overrides the codes 0 to 3.
+    pub fn with_code(mut self, code: i32, state: ServiceState, reason: impl Into<String>) -> Self {
+        self.codes.insert(code, (state, reason.into()));
+        self
+    }
+
+    /// Maps a death by the given signal to the state with the given reason.
+    pub fn with_signal(
+        mut self,
+        signal: i32,
+        state: ServiceState,
+        reason: impl Into<String>,
+    ) -> Self {
+        self.signals.insert(signal, (state, reason.into()));
+        self
+    }
+
+    /// Sets the state for codes and signals without mapping. Defaults to [ServiceState::Unknown].
+    pub fn with_default_state(mut self, state: ServiceState) -> Self {
+        self.default_state = state;
+        self
+    }
+
+    /// Returns a result with the state for the exit status. It has a message unless the code is
+    /// one of 0 to 3 without mapping.
+    pub fn evaluate(&self, status: &ExitStatus) -> CheckResult {
+        let result =
+            |state, message: String| CheckResult::new().with_state(state).with_message(message);
+
+        match (status.code(), signal(status)) {
+            (Some(code), _) => match (self.codes.get(&code), ServiceState::from_exit_code(code)) {
+                (Some((state, reason)), _) => {
+                    result(*state, format!("{} (exit code {})", reason, code))
+                }
+                (None, Some(state)) => CheckResult::new().with_state(state),
+                (None, None) => {
+                    result(self.default_state, format!("unexpected exit code {}", code))
+                }
+            },
+            (None, Some(signal)) => match self.signals.get(&signal) {
+                Some((state, reason)) => {
+                    result(*state, format!("{} (killed by signal {})", reason, signal))
+                }
+                None => result(self.default_state, format!("killed by signal {}", signal)),
+            },
+            (None, None) => result(self.default_state, "unknown exit status".to_owned()),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn signal(status: &ExitStatus) -> Option<i32> {
+    std::os::unix::process::ExitStatusExt::signal(status)
+}
+
+#[cfg(not(unix))]
+fn signal(_: &ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Returns the exit status and whether the child had to be killed.
+fn wait_or_kill(child: &mut Child, timeout: Duration) -> io::Result<(ExitStatus, bool)> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok((status, false));
+        }
+
+        if Instant::now() >= deadline {
+            child.kill()?;
+            return Ok((child.wait()?, true));
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Reads a pipe of a child on a thread of its own. The output is shared, so it's available even if
+/// the pipe is never closed, e.g. because the program left a process behind which inherited it.
+struct Capture {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    done: mpsc::Receiver<io::Result<()>>,
+}
+
+impl Capture {
+    fn start(reader: Option<impl Read + Send + 'static>) -> Self {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let (tx, done) = mpsc::channel();
+
+        let shared = Arc::clone(&buffer);
+        thread::spawn(move || {
+            let result = reader.map_or(Ok(()), |mut reader| {
+                let mut chunk = [0; 4096];
+                loop {
+                    match reader.read(&mut chunk) {
+                        Ok(0) => return Ok(()),
+                        Ok(n) => shared.lock().unwrap().extend_from_slice(&chunk[..n]),
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                        Err(err) => return Err(err),
+                    }
+                }
+            });
+            let _ = tx.send(result);
+        });
+
+        Self { buffer, done }
+    }
+
+    /// Waits until the pipe is closed, but not past the given deadline, and returns what was read.
+    fn finish(self, deadline: Instant) -> io::Result<String> {
+        let wait = deadline.max(Instant::now() + READ_GRACE) - Instant::now();
+        if let Ok(Err(err)) = self.done.recv_timeout(wait) {
+            return Err(err);
+        }
+
+        let buffer = self.buffer.lock().unwrap();
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secure_command() {
+        let var = |name: &str| match name {
+            "IFS" => Some("/".into()),
+            "TZ" => Some("UTC".into()),
+            _ => None,
+        };
+        let output = secure_command_with_env("env", var).output().unwrap();
+        let env = String::from_utf8(output.stdout).unwrap();
+
+        assert!(!env.contains("IFS="));
+        assert!(env.contains("TZ=UTC"));
+        assert!(env.contains(&format!("PATH={}", SECURE_PATH)));
+        assert!(env.contains("LC_ALL=C"));
+    }
+
+    #[test]
+    fn test_wait_with_timeout() {
+        let mut child = secure_command("sleep").arg("5").spawn().unwrap();
+        let err = wait_with_timeout(&mut child, Duration::from_millis(50)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+        let mut child = secure_command("true").spawn().unwrap();
+        let status = wait_with_timeout(&mut child, Duration::from_secs(5)).unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn test_exit_code_policy() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let policy = ExitCodePolicy::new().with_code(4, ServiceState::Critical, "disk unavailable");
+        let evaluate = |raw| policy.evaluate(&ExitStatus::from_raw(raw));
+
+        assert_eq!(
+            evaluate(1 << 8),
+            CheckResult::new().with_state(ServiceState::Warning)
+        );
+        assert_eq!(
+            evaluate(4 << 8),
+            CheckResult::new()
+                .with_state(ServiceState::Critical)
+                .with_message("disk unavailable (exit code 4)")
+        );
+        assert_eq!(
+            evaluate(127 << 8),
+            CheckResult::new()
+                .with_state(ServiceState::Unknown)
+                .with_message("command not found (exit code 127)")
+        );
+        assert_eq!(
+            evaluate(9),
+            CheckResult::new()
+                .with_state(ServiceState::Unknown)
+                .with_message("timed out (killed by signal 9)")
+        );
+        assert_eq!(
+            evaluate(42 << 8),
+            CheckResult::new()
+                .with_state(ServiceState::Unknown)
+                .with_message("unexpected exit code 42")
+        );
+    }
+
+    #[test]
+    fn test_run() {
+        let output = run(
+            "sh",
+            ["-c", "echo out; echo err >&2; exit 2"],
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        assert_eq!(output.stdout, "out\n");
+        assert_eq!(output.stderr, "err\n");
+        assert!(!output.timed_out);
+
+        let output = run(
+            "sh",
+            ["-c", "echo started; sleep 5"],
+            Duration::from_millis(100),
+        )
+        .unwrap();
+        assert_eq!(output.stdout, "started\n");
+        assert!(output.timed_out);
+        assert!(output.duration < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,68 @@
+use std::fmt;
+
+use crate::{PerfString, ServiceState};
+
+/// The evaluated parts of a [crate::Resource], as returned by [crate::Resource::exit_summary], for
+/// callers which need them separately, e.g. to submit them to an API or to check them in tests,
+/// instead of splitting the formatted output again.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{Metric, Resource, ServiceState, TriggerIfValue};
+///
+/// let summary = Resource::new("disk")
+///     .with_result(Metric::new("usage", 93).with_thresholds(80, 90, TriggerIfValue::Greater))
+///     .exit_summary();
+///
+/// assert_eq!(summary.state(), ServiceState::Critical);
+/// assert_eq!(summary.summary(), "disk is CRITICAL");
+/// assert_eq!(
+///     summary.long_output(),
+///     ["metric 'usage' is CRITICAL: value '93' has exceeded threshold of '90'"]
+/// );
+/// assert_eq!(summary.perf_strings()[0].to_string(), "'usage'=93;80;90;;");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExitSummary {
+    pub(crate) state: ServiceState,
+    pub(crate) summary: String,
+    pub(crate) long_output: Vec<String>,
+    pub(crate) perf_strings: Vec<PerfString>,
+    pub(crate) output: String,
+}
+
+impl ExitSummary {
+    pub fn state(&self) -> ServiceState {
+        self.state
+    }
+
+    /// Returns the first line of the output without performance data.
+    pub fn summary(&self) -> &str {
+        &self.summary
+    }
+
+    /// Returns the lines of the long output, i.e. the messages of the results.
+    pub fn long_output(&self) -> &[String] {
+        &self.long_output
+    }
+
+    pub fn perf_strings(&self) -> &[PerfString] {
+        &self.perf_strings
+    }
+
+    /// Returns the complete output, as printed by the plugin.
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    pub fn into_output(self) -> String {
+        self.output
+    }
+}
+
+impl fmt::Display for ExitSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.output)
+    }
+}
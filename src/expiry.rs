@@ -0,0 +1,83 @@
+use std::time::SystemTime;
+
+use crate::{Metric, TriggerIfValue, Unit};
+
+const SECONDS_PER_DAY: f64 = 86400.0;
+
+impl Metric<f64> {
+    /// Creates a metric with the whole days remaining until `expires_at`, e.g. of a certificate,
+    /// license or domain. The value is negative once expired. The thresholds are the minimum of
+    /// remaining days and trigger if the value is less.
+    ///
+    /// Anything convertible into a [SystemTime] is accepted, including `chrono::DateTime`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::time::{Duration, SystemTime};
+    /// use nagiosplugin::{Metric, Resource, ServiceState};
+    ///
+    /// let expires_at = SystemTime::now() + Duration::from_secs(10 * 86400 + 60);
+    /// let resource = Resource::new("certificate")
+    ///     .with_result(Metric::expiry_days("days_left", expires_at, 30.0, 7.0));
+    /// assert_eq!(resource.state(), ServiceState::Warning);
+    /// ```
+    pub fn expiry_days(
+        name: impl Into<String>,
+        expires_at: impl Into<SystemTime>,
+        warning: impl Into<Option<f64>>,
+        critical: impl Into<Option<f64>>,
+    ) -> Self {
+        let days =
+            (remaining_seconds(expires_at.into(), SystemTime::now()) / SECONDS_PER_DAY).floor();
+        Metric::new(name, days).with_thresholds(warning, critical, TriggerIfValue::Less)
+    }
+
+    /// Like [Metric::expiry_days], but the value and thresholds are whole seconds.
+    pub fn expiry_seconds(
+        name: impl Into<String>,
+        expires_at: impl Into<SystemTime>,
+        warning: impl Into<Option<f64>>,
+        critical: impl Into<Option<f64>>,
+    ) -> Self {
+        let seconds = remaining_seconds(expires_at.into(), SystemTime::now()).floor();
+        Metric::new(name, seconds)
+            .with_unit(Unit::Seconds)
+            .with_thresholds(warning, critical, TriggerIfValue::Less)
+    }
+}
+
+/// Returns the seconds from `now` until `expires_at`, negative if it already passed.
+fn remaining_seconds(expires_at: SystemTime, now: SystemTime) -> f64 {
+    match expires_at.duration_since(now) {
+        Ok(d) => d.as_secs_f64(),
+        Err(err) => -err.duration().as_secs_f64(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckResult, ServiceState};
+    use std::time::Duration;
+
+    #[test]
+    fn test_remaining_seconds() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        assert_eq!(remaining_seconds(now + Duration::from_secs(90), now), 90.0);
+        assert_eq!(remaining_seconds(now - Duration::from_secs(90), now), -90.0);
+    }
+
+    #[test]
+    fn test_expiry_days() {
+        let expired = SystemTime::now() - Duration::from_secs(3600);
+        let result: CheckResult = Metric::expiry_days("license", expired, 30.0, 7.0).into();
+        assert_eq!(result.state, Some(ServiceState::Critical));
+        assert_eq!(result.perf_strings[0].to_string(), "'license'=-1;30;7;;");
+
+        let expires_at = SystemTime::now() + Duration::from_secs(90 * 86400);
+        let result: CheckResult =
+            Metric::expiry_seconds("domain", expires_at, 86400.0, None).into();
+        assert_eq!(result.state, None);
+    }
+}
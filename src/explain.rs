@@ -0,0 +1,186 @@
+use std::io::Write;
+
+use crate::{MetricEvaluation, Resource, ServiceState, TriggerIfValue};
+
+/// Defines where the explanation of the state of a [Resource] is written to. See
+/// [Resource::with_explain].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Explain {
+    /// The explanation is appended to the long output.
+    LongOutput,
+    /// The explanation is written to stderr, the plugin output is unchanged.
+    Stderr,
+}
+
+impl Explain {
+    pub(crate) fn write_stderr(lines: &[String]) {
+        let mut stderr = std::io::stderr();
+        for line in lines {
+            let _ = writeln!(stderr, "{}", line);
+        }
+    }
+}
+
+/// Returns one line per metric describing the thresholds, the comparisons and the resulting state,
+/// and a line for the fixed state of the resource, if set.
+pub(crate) fn explain(resource: &Resource) -> Vec<String> {
+    let mut lines: Vec<String> = resource
+        .results
+        .iter()
+        .filter_map(|r| r.evaluation.as_ref())
+        .map(explain_metric)
+        .collect();
+
+    if let Some(state) = resource.fixed_state {
+        lines.push(format!(
+            "explain: resource '{}' => {} (state fixed)",
+            resource.name, state
+        ));
+    }
+
+    lines
+}
+
+fn explain_metric(e: &MetricEvaluation) -> String {
+    let state = e.state.unwrap_or(ServiceState::Ok);
+    let mut steps = vec![format!("value '{}'", e.value)];
+
+    if e.fixed_state.is_some() {
+        steps.push("state fixed, thresholds ignored".to_owned());
+    } else if let Some(reason) = &e.reason {
+        steps.push(reason.clone());
+    } else if let Some(trigger) = e.trigger {
+        let operator = match trigger {
+            TriggerIfValue::Greater => ">=",
+            TriggerIfValue::Less => "<=",
+        };
+        let thresholds = [
+            ("critical", &e.critical, e.critical_breached),
+            ("warning", &e.warning, e.warning_breached),
+        ];
+        for (name, threshold, breached) in thresholds {
+            if let Some(threshold) = threshold {
+                let matched = if breached == Some(true) { "yes" } else { "no" };
+                steps.push(format!(
+                    "{} if {} {} '{}': {}",
+                    name, e.value, operator, threshold, matched
+                ));
+            }
+        }
+    } else if e.warning.is_some() || e.critical.is_some() {
+        let ranges = [
+            ("critical", &e.critical, e.critical_breached),
+            ("warning", &e.warning, e.warning_breached),
+        ];
+        for (name, range, breached) in ranges {
+            if let Some(range) = range {
+                let matched = if breached == Some(true) { "yes" } else { "no" };
+                steps.push(format!(
+                    "{} if {} alerts in range '{}': {}",
+                    name, e.value, range, matched
+                ));
+            }
+        }
+    } else {
+        steps.push("no thresholds".to_owned());
+    }
+
+    format!(
+        "explain: metric '{}': {} => {}",
+        e.name,
+        steps.join("; "),
+        state
+    )
+}
+
+/// Returns one line per metric with the comparisons of the value with the thresholds, as recorded
+/// when the state was determined, e.g. `trace: metric 'load': value 83 vs warn 80: breach; vs
+/// crit 90: ok => WARNING`.
+pub(crate) fn trace(resource: &Resource) -> Vec<String> {
+    resource
+        .results
+        .iter()
+        .filter_map(|r| r.evaluation.as_ref())
+        .map(trace_metric)
+        .collect()
+}
+
+fn trace_metric(e: &MetricEvaluation) -> String {
+    let comparisons: Vec<_> = [
+        ("warn", &e.warning, e.warning_breached),
+        ("crit", &e.critical, e.critical_breached),
+    ]
+    .into_iter()
+    .filter_map(|(name, threshold, breached)| {
+        let outcome = if breached? { "breach" } else { "ok" };
+        Some(format!(
+            "vs {} {}: {}",
+            name,
+            threshold.as_deref()?,
+            outcome
+        ))
+    })
+    .collect();
+
+    let steps = if !comparisons.is_empty() {
+        format!(" {}", comparisons.join("; "))
+    } else if e.fixed_state.is_some() {
+        ": state fixed".to_owned()
+    } else if let Some(reason) = &e.reason {
+        format!(": {}", reason)
+    } else {
+        ": no thresholds".to_owned()
+    };
+
+    format!(
+        "trace: metric '{}': value {}{} => {}",
+        e.name,
+        e.value,
+        steps,
+        e.state.unwrap_or(ServiceState::Ok)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Metric;
+
+    #[test]
+    fn test_explain() {
+        let resource = Resource::new("foo")
+            .with_result(Metric::new("a", 42).with_thresholds(40, 50, TriggerIfValue::Greater))
+            .with_result(Metric::new("b", 5).with_thresholds(None, 10, TriggerIfValue::Less))
+            .with_result(Metric::new("c", 1))
+            .with_result(Metric::new("d", f64::NAN))
+            .with_result(Metric::new("e", 1).with_fixed_state(ServiceState::Warning));
+
+        assert_eq!(
+            explain(&resource),
+            [
+                "explain: metric 'a': value '42'; critical if 42 >= '50': no; warning if 42 >= '40': yes => WARNING",
+                "explain: metric 'b': value '5'; critical if 5 <= '10': yes => CRITICAL",
+                "explain: metric 'c': value '1'; no thresholds => OK",
+                "explain: metric 'd': value 'NaN'; value is not a number => UNKNOWN",
+                "explain: metric 'e': value '1'; state fixed, thresholds ignored => WARNING",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trace() {
+        let resource = Resource::new("foo")
+            .with_result(Metric::new("a", 95).with_thresholds(80, 90, TriggerIfValue::Greater))
+            .with_result(Metric::new("b", 5).with_thresholds(None, 10, TriggerIfValue::Greater))
+            .with_result(Metric::new("c", f64::NAN));
+
+        assert_eq!(
+            trace(&resource),
+            [
+                "trace: metric 'a': value 95 vs warn 80: breach; vs crit 90: breach => CRITICAL",
+                "trace: metric 'b': value 5 vs crit 10: ok => OK",
+                "trace: metric 'c': value NaN: value is not a number => UNKNOWN",
+            ]
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{Metric, StateFile, TriggerIfValue};
+
+const SECONDS_PER_DAY: f64 = 86400.0;
+
+/// Predicts when a growing value, like the used space of a disk, reaches a limit by fitting a
+/// linear trend over the values of the previous runs. The values are kept in a [StateFile] under
+/// `<name>.samples`. The result is a metric with the remaining days, which triggers if it is less
+/// than the thresholds.
+///
+/// If the value doesn't grow or there are not enough values yet, the remaining days are infinite
+/// and reported as undetermined in the performance data.
+///
+/// ## Example
+///
+/// ```no_run
+/// use nagiosplugin::{state_dir, Forecast, Resource, StateFile};
+///
+/// let mut state = StateFile::open(state_dir("check_disk").unwrap().join("state")).unwrap();
+/// let used_bytes = 412_000_000_000.0;
+/// let metric = Forecast::new(500_000_000_000.0)
+///     .with_thresholds(14.0, 3.0)
+///     .days_until_limit(&mut state, "var_days_left", used_bytes);
+/// state.save().unwrap();
+///
+/// let resource = Resource::new("disk").with_result(metric);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Forecast {
+    limit: f64,
+    window: Duration,
+    min_samples: usize,
+    warning: Option<f64>,
+    critical: Option<f64>,
+}
+
+impl Forecast {
+    /// Creates a forecast for the given limit, e.g. the size of the disk, over the values of the
+    /// last 7 days and at least 3 values.
+    pub fn new(limit: f64) -> Self {
+        Self {
+            limit,
+            window: Duration::from_secs(7 * 86400),
+            min_samples: 3,
+            warning: Default::default(),
+            critical: Default::default(),
+        }
+    }
+
+    /// Sets the time span of the values the trend is fitted to.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn with_min_samples(mut self, min_samples: usize) -> Self {
+        self.min_samples = min_samples.max(2);
+        self
+    }
+
+    /// Sets the minimum of remaining days.
+    pub fn with_thresholds(
+        mut self,
+        warning: impl Into<Option<f64>>,
+        critical: impl Into<Option<f64>>,
+    ) -> Self {
+        self.warning = warning.into();
+        self.critical = critical.into();
+        self
+    }
+
+    /// Records the current value and returns the metric with the days until the limit is
+    /// reached, rounded down to tenths.
+    pub fn days_until_limit(&self, state: &mut StateFile, name: &str, value: f64) -> Metric<f64> {
+        self.forecast(state, name, value, SystemTime::now())
+    }
+
+    fn forecast(
+        &self,
+        state: &mut StateFile,
+        name: &str,
+        value: f64,
+        now: SystemTime,
+    ) -> Metric<f64> {
+        let key = format!("{}.samples", name);
+        let now = now
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64());
+        let oldest = now - self.window.as_secs_f64();
+
+        let mut samples: Vec<(f64, f64)> = state
+            .get(&key)
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|s| s.split_once(':'))
+            .filter_map(|(t, v)| Some((t.parse().ok()?, v.parse().ok()?)))
+            .filter(|&(t, _)| t >= oldest && t < now)
+            .collect();
+        if value.is_finite() {
+            samples.push((now, value));
+        }
+        let stored: Vec<_> = samples
+            .iter()
+            .map(|(t, v)| format!("{}:{}", t, v))
+            .collect();
+        state.set(key, stored.join(","));
+
+        let days = match trend(&samples) {
+            _ if samples.len() < self.min_samples => f64::INFINITY,
+            Some((slope, intercept)) if slope > 0.0 => {
+                let seconds = (self.limit - (slope * now + intercept)) / slope;
+                (seconds.max(0.0) / SECONDS_PER_DAY * 10.0).floor() / 10.0
+            }
+            _ => f64::INFINITY,
+        };
+
+        Metric::new(name, days).with_minimum(0.0).with_thresholds(
+            self.warning,
+            self.critical,
+            TriggerIfValue::Less,
+        )
+    }
+}
+
+/// Returns the slope and intercept of the least squares line through the samples.
+fn trend(samples: &[(f64, f64)]) -> Option<(f64, f64)> {
+    let n = samples.len() as f64;
+    // Shifting the time avoids losing precision with large timestamps.
+    let t0 = samples.first()?.0;
+    let mean_t = samples.iter().map(|(t, _)| t - t0).sum::<f64>() / n;
+    let mean_v = samples.iter().map(|(_, v)| v).sum::<f64>() / n;
+
+    let covariance: f64 = samples
+        .iter()
+        .map(|(t, v)| (t - t0 - mean_t) * (v - mean_v))
+        .sum();
+    let variance: f64 = samples.iter().map(|(t, _)| (t - t0 - mean_t).powi(2)).sum();
+    if variance == 0.0 {
+        return None;
+    }
+
+    let slope = covariance / variance;
+    Some((slope, mean_v - slope * (mean_t + t0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckResult, ServiceState};
+
+    #[test]
+    fn test_forecast() {
+        let mut state = StateFile::open("/nonexistent/state").unwrap();
+        let forecast = Forecast::new(100.0).with_thresholds(14.0, 3.0);
+        let day = |d: u64| UNIX_EPOCH + Duration::from_secs(1_700_000_000 + d * 86400);
+
+        assert!(forecast
+            .forecast(&mut state, "used", 50.0, day(0))
+            .value
+            .is_infinite());
+        assert!(forecast
+            .forecast(&mut state, "used", 52.0, day(1))
+            .value
+            .is_infinite());
+
+        // Grows by 2 per day, 46 left.
+        let metric = forecast.forecast(&mut state, "used", 54.0, day(2));
+        assert_eq!(metric.value, 23.0);
+
+        // Grows by 12.2 per day, 20.2 left.
+        let metric = forecast.forecast(&mut state, "used", 90.0, day(3));
+        assert_eq!(metric.value, 1.6);
+        assert_eq!(
+            CheckResult::from(metric).state,
+            Some(ServiceState::Critical)
+        );
+
+        // Values older than the window are dropped.
+        forecast.forecast(&mut state, "used", 90.0, day(20));
+        assert_eq!(state.get("used.samples"), Some("1701728000:90"));
+    }
+}
@@ -0,0 +1,212 @@
+use crate::catalog::MetricDefinition;
+use crate::{json, MetricCatalog, Unit};
+
+const WARNING_COLOR: &str = "#FFC125";
+const CRITICAL_COLOR: &str = "#FF0000";
+
+impl MetricCatalog {
+    /// Generates a PNP4Nagios template with one graph per metric, in the order of the catalog,
+    /// which has to match the order of the performance data. The range and the thresholds of the
+    /// [MetricDefinition]s are used for the limits and horizontal lines. The stub is meant to be
+    /// saved as `templates/<check_command>.php` and adjusted from there.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use nagiosplugin::{MetricCatalog, MetricDefinition, Unit};
+    ///
+    /// let catalog = MetricCatalog::new().with_definition(
+    ///     MetricDefinition::new("used_pct", Unit::Percentage, "Used space")
+    ///         .with_range(0.0, 100.0)
+    ///         .with_thresholds(80.0, 90.0),
+    /// );
+    /// let template = catalog.to_pnp_template();
+    /// assert!(template.contains(r##"rrd::hrule(80, "#FFC125", "Warning 80\n")"##));
+    /// ```
+    pub fn to_pnp_template(&self) -> String {
+        let mut template = String::from("<?php\n");
+
+        for (i, m) in self.metrics.iter().enumerate() {
+            let i = i + 1;
+            let mut opt = format!(
+                "--vertical-label \\\"{}\\\" --title \\\"$hostname / $servicedesc: {}\\\"",
+                php_escape(m.unit.as_str()),
+                php_escape(&m.name)
+            );
+            if let Some(minimum) = finite(m.minimum) {
+                opt.push_str(&format!(" --lower-limit {}", minimum));
+            }
+            if let Some(maximum) = finite(m.maximum) {
+                opt.push_str(&format!(" --upper-limit {} --rigid", maximum));
+            }
+
+            template.push_str(&format!("\n# {}\n", m.description.replace('\n', " ")));
+            template.push_str(&format!("$opt[{}] = \"{}\";\n", i, opt));
+            template.push_str(&format!(
+                "$def[{0}] = rrd::def(\"var{0}\", $RRDFILE[{0}], $DS[{0}], \"AVERAGE\");\n",
+                i
+            ));
+            template.push_str(&format!(
+                "$def[{0}] .= rrd::line1(\"var{0}\", \"#1F78B4\", \"{1}\");\n",
+                i,
+                php_escape(&m.name)
+            ));
+            template.push_str(&format!(
+                "$def[{0}] .= rrd::gprint(\"var{0}\", array(\"LAST\", \"MAX\", \"AVERAGE\"), \"%6.2lf {1}\");\n",
+                i,
+                php_escape(&m.unit.as_str().replace('%', "%%"))
+            ));
+            for (label, value, color) in thresholds(m) {
+                template.push_str(&format!(
+                    "$def[{}] .= rrd::hrule({}, \"{}\", \"{} {}\\n\");\n",
+                    i, value, color, label, value
+                ));
+            }
+        }
+
+        template.push_str("?>\n");
+        template
+    }
+
+    /// Generates a JSON array with a Grafana time series panel per metric, which can be pasted
+    /// into the `panels` of a dashboard. Unit, range and thresholds are taken from the
+    /// [MetricDefinition]s. The queries depend on the data source and are left empty.
+    pub fn to_grafana_panels(&self) -> String {
+        let panels = self
+            .metrics
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                let mut steps = vec!["{\"color\":\"green\",\"value\":null}".to_owned()];
+                steps.extend(thresholds(m).map(|(_, value, color)| {
+                    format!("{{\"color\":\"{}\",\"value\":{}}}", color, value)
+                }));
+
+                let mut defaults = format!("\"unit\":{}", json::quote(&grafana_unit(&m.unit)));
+                if let Some(minimum) = finite(m.minimum) {
+                    defaults.push_str(&format!(",\"min\":{}", minimum));
+                }
+                if let Some(maximum) = finite(m.maximum) {
+                    defaults.push_str(&format!(",\"max\":{}", maximum));
+                }
+
+                format!(
+                    "{{\"id\":{},\"type\":\"timeseries\",\"title\":{},\"description\":{},\
+                     \"gridPos\":{{\"h\":8,\"w\":12,\"x\":{},\"y\":{}}},\
+                     \"fieldConfig\":{{\"defaults\":{{{},\
+                     \"thresholds\":{{\"mode\":\"absolute\",\"steps\":[{}]}},\
+                     \"custom\":{{\"thresholdsStyle\":{{\"mode\":\"line\"}}}}}},\"overrides\":[]}},\
+                     \"targets\":[]}}",
+                    i + 1,
+                    json::quote(&m.name),
+                    json::quote(&m.description),
+                    i % 2 * 12,
+                    i / 2 * 8,
+                    defaults,
+                    steps.join(",")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        format!("[{}]", panels)
+    }
+}
+
+/// Returns the finite thresholds of the metric with their label and color.
+fn thresholds(m: &MetricDefinition) -> impl Iterator<Item = (&'static str, f64, &'static str)> {
+    [
+        ("Warning", m.warning, WARNING_COLOR),
+        ("Critical", m.critical, CRITICAL_COLOR),
+    ]
+    .into_iter()
+    .filter_map(|(label, value, color)| finite(value).map(|v| (label, v, color)))
+}
+
+fn finite(value: Option<f64>) -> Option<f64> {
+    value.filter(|v| v.is_finite())
+}
+
+/// Escapes a string for a double quoted PHP string.
+fn php_escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('$', "\\$")
+}
+
+/// Returns the Grafana unit id for the unit. Units without equivalent are used as suffix.
+fn grafana_unit(unit: &Unit) -> String {
+    let id = match unit {
+        Unit::None => "none",
+        Unit::Seconds => "s",
+        Unit::Milliseconds => "ms",
+        Unit::Microseconds => "µs",
+        Unit::Percentage => "percent",
+        Unit::Bytes => "bytes",
+        Unit::Kilobytes => "deckbytes",
+        Unit::Megabytes => "decmbytes",
+        Unit::Gigabytes => "decgbytes",
+        Unit::Terabytes => "dectbytes",
+        Unit::Petabytes => "decpbytes",
+        Unit::Kibibytes => "kbytes",
+        Unit::Mebibytes => "mbytes",
+        Unit::Gibibytes => "gbytes",
+        Unit::Tebibytes => "tbytes",
+        Unit::Pebibytes => "pbytes",
+        Unit::Counter => "short",
+        Unit::Bits => "decbits",
+        Unit::BitsPerSecond => "bps",
+        Unit::PacketsPerSecond => "pps",
+        Unit::Hertz => "hertz",
+        Unit::Iops => "iops",
+        Unit::Other(_) => return format!("suffix:{}", unit.as_str()),
+    };
+    id.to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> MetricCatalog {
+        MetricCatalog::new()
+            .with_definition(
+                MetricDefinition::new("used_pct", Unit::Percentage, "Used space")
+                    .with_range(0.0, 100.0)
+                    .with_thresholds(80.0, None),
+            )
+            .with_metric("load$1", Unit::None, "Load")
+    }
+
+    #[test]
+    fn test_pnp_template() {
+        assert_eq!(
+            catalog().to_pnp_template(),
+            r##"<?php
+
+# Used space
+$opt[1] = "--vertical-label \"%\" --title \"$hostname / $servicedesc: used_pct\" --lower-limit 0 --upper-limit 100 --rigid";
+$def[1] = rrd::def("var1", $RRDFILE[1], $DS[1], "AVERAGE");
+$def[1] .= rrd::line1("var1", "#1F78B4", "used_pct");
+$def[1] .= rrd::gprint("var1", array("LAST", "MAX", "AVERAGE"), "%6.2lf %%");
+$def[1] .= rrd::hrule(80, "#FFC125", "Warning 80\n");
+
+# Load
+$opt[2] = "--vertical-label \"\" --title \"$hostname / $servicedesc: load\$1\"";
+$def[2] = rrd::def("var2", $RRDFILE[2], $DS[2], "AVERAGE");
+$def[2] .= rrd::line1("var2", "#1F78B4", "load\$1");
+$def[2] .= rrd::gprint("var2", array("LAST", "MAX", "AVERAGE"), "%6.2lf ");
+?>
+"##
+        );
+    }
+
+    #[test]
+    fn test_grafana_panels() {
+        let panels = catalog().to_grafana_panels();
+        assert!(panels.starts_with(
+            r##"[{"id":1,"type":"timeseries","title":"used_pct","description":"Used space","gridPos":{"h":8,"w":12,"x":0,"y":0},"fieldConfig":{"defaults":{"unit":"percent","min":0,"max":100,"thresholds":{"mode":"absolute","steps":[{"color":"green","value":null},{"color":"#FFC125","value":80}]},"custom":{"thresholdsStyle":{"mode":"line"}}},"overrides":[]},"targets":[]},"##
+        ));
+        assert!(panels.contains(r#""gridPos":{"h":8,"w":12,"x":12,"y":0}"#));
+    }
+}
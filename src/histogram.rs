@@ -0,0 +1,190 @@
+use crate::{CheckResult, Metric, PerfString, TriggerIfValue, Unit};
+
+/// Counts observed values, e.g. request latencies, in buckets with upper bounds. It is rendered as
+/// one cumulative performance data entry per bucket, like `latency_le_10ms` and
+/// `latency_le_inf`, followed by `latency_count` and `latency_sum`. Thresholds are evaluated on a
+/// chosen percentile, which is estimated from the buckets as the upper bound of the bucket it
+/// falls into, or the largest value for the last bucket.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{Histogram, Resource, ServiceState, TriggerIfValue, Unit};
+///
+/// let mut histogram = Histogram::new("latency", [10.0, 100.0, 1000.0])
+///     .with_unit(Unit::Milliseconds)
+///     .with_percentile_thresholds(95.0, 100.0, 1000.0, TriggerIfValue::Greater);
+/// for latency in [4.0, 8.0, 12.0, 40.0, 250.0] {
+///     histogram.observe(latency);
+/// }
+///
+/// let resource = Resource::new("web").with_result(histogram);
+/// assert_eq!(resource.state(), ServiceState::Warning);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    name: String,
+    unit: Unit,
+    bounds: Vec<f64>,
+    /// The count per bucket, the last one has no upper bound.
+    counts: Vec<u64>,
+    sum: f64,
+    max: f64,
+    thresholds: Option<(f64, Option<f64>, Option<f64>, TriggerIfValue)>,
+}
+
+impl Histogram {
+    /// Creates a histogram with the given upper bounds. A bucket without upper bound is always
+    /// added, values which are not a number are ignored.
+    pub fn new(name: impl Into<String>, bounds: impl IntoIterator<Item = f64>) -> Self {
+        let mut bounds: Vec<_> = bounds.into_iter().filter(|b| b.is_finite()).collect();
+        bounds.sort_by(f64::total_cmp);
+        bounds.dedup();
+
+        Self {
+            name: name.into(),
+            unit: Default::default(),
+            counts: vec![0; bounds.len() + 1],
+            bounds,
+            sum: 0.0,
+            max: f64::NEG_INFINITY,
+            thresholds: Default::default(),
+        }
+    }
+
+    pub fn with_unit(mut self, unit: Unit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// Evaluates the thresholds on the given percentile between 0 and 100.
+    pub fn with_percentile_thresholds(
+        mut self,
+        percentile: f64,
+        warning: impl Into<Option<f64>>,
+        critical: impl Into<Option<f64>>,
+        trigger_if_value: TriggerIfValue,
+    ) -> Self {
+        self.thresholds = Some((
+            percentile.clamp(0.0, 100.0),
+            warning.into(),
+            critical.into(),
+            trigger_if_value,
+        ));
+        self
+    }
+
+    pub fn observe(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        let bucket = self.bounds.partition_point(|&b| b < value);
+        self.counts[bucket] += 1;
+        self.sum += value;
+        self.max = self.max.max(value);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the estimated percentile, or `None` without values.
+    pub fn percentile(&self, percentile: f64) -> Option<f64> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0 * count as f64).ceil() as u64).max(1);
+
+        let mut cumulative = 0;
+        for (i, n) in self.counts.iter().enumerate() {
+            cumulative += n;
+            if cumulative >= rank {
+                let bound = self.bounds.get(i).copied().unwrap_or(self.max);
+                return Some(bound.min(self.max));
+            }
+        }
+        None
+    }
+
+    fn perf_strings(&self) -> Vec<PerfString> {
+        let entry = |label: String, value: &f64, unit: Unit| {
+            PerfString::new(&label, value, unit, None, None, None, None)
+        };
+
+        let mut cumulative = 0;
+        let mut perf_strings = Vec::with_capacity(self.counts.len() + 2);
+        for (i, n) in self.counts.iter().enumerate() {
+            cumulative += n;
+            let bound = match self.bounds.get(i) {
+                Some(bound) => format!("{}{}", bound, self.unit.as_str()),
+                None => "inf".to_owned(),
+            };
+            perf_strings.push(entry(
+                format!("{}_le_{}", self.name, bound),
+                &(cumulative as f64),
+                Unit::None,
+            ));
+        }
+        perf_strings.push(entry(
+            format!("{}_count", self.name),
+            &(cumulative as f64),
+            Unit::None,
+        ));
+        perf_strings.push(entry(
+            format!("{}_sum", self.name),
+            &self.sum,
+            self.unit.clone(),
+        ));
+        perf_strings
+    }
+}
+
+impl From<Histogram> for CheckResult {
+    fn from(histogram: Histogram) -> Self {
+        let perf_strings = histogram.perf_strings();
+        let mut result = match histogram.thresholds {
+            Some((percentile, warning, critical, trigger)) => {
+                let value = histogram.percentile(percentile).unwrap_or(f64::NAN);
+                let name = format!("{}_p{}", histogram.name, percentile);
+                CheckResult::from(
+                    Metric::new(name, value)
+                        .with_unit(histogram.unit)
+                        .with_thresholds(warning, critical, trigger),
+                )
+            }
+            None => CheckResult::new(),
+        };
+
+        result.perf_strings.extend(perf_strings);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Resource, ServiceState};
+
+    #[test]
+    fn test_histogram() {
+        let mut histogram = Histogram::new("lat", [100.0, 10.0])
+            .with_unit(Unit::Milliseconds)
+            .with_percentile_thresholds(50.0, 50.0, None, TriggerIfValue::Greater);
+        for value in [5.0, 10.0, 11.0, 150.0, f64::NAN] {
+            histogram.observe(value);
+        }
+
+        assert_eq!(histogram.count(), 4);
+        assert_eq!(histogram.percentile(50.0), Some(10.0));
+        assert_eq!(histogram.percentile(100.0), Some(150.0));
+
+        let (state, output) = Resource::new("web").with_result(histogram).nagios_result();
+        assert_eq!(state, ServiceState::Ok);
+        assert_eq!(
+            output,
+            "web is OK|'lat_p50'=10ms;50;;; 'lat_le_10ms'=2;;;; 'lat_le_100ms'=3;;;; \
+             'lat_le_inf'=4;;;; 'lat_count'=4;;;; 'lat_sum'=176ms;;;;"
+        );
+    }
+}
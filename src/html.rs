@@ -0,0 +1,128 @@
+/// Defines how HTML in the summary and messages of a [Resource] is treated. Nagios CGIs may
+/// interpret HTML in plugin output, so text from untrusted sources should be escaped.
+///
+/// [Resource]: crate::Resource
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum HtmlMode {
+    /// The output is emitted as is.
+    #[default]
+    Raw,
+    /// `<`, `>`, `&`, `"` and `'` are replaced with HTML entities.
+    Escape,
+    /// Like [HtmlMode::Escape], but well-formed links (`<a href="https://...">text</a>`) are kept,
+    /// e.g. to link to runbooks. Only `http` and `https` URLs made of RFC 3986 characters other
+    /// than `&` and `'` are allowed and the link text is escaped.
+    EscapeExceptLinks,
+}
+
+impl HtmlMode {
+    pub(crate) fn apply(&self, s: &str) -> String {
+        match self {
+            HtmlMode::Raw => s.to_owned(),
+            HtmlMode::Escape => escape(s, false),
+            HtmlMode::EscapeExceptLinks => escape(s, true),
+        }
+    }
+}
+
+fn escape(s: &str, allow_links: bool) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    let mut in_link = false;
+    let mut rest = s;
+
+    while let Some(c) = rest.chars().next() {
+        if allow_links && !in_link {
+            if let Some(tag) = link_start(rest) {
+                escaped.push_str(tag);
+                rest = &rest[tag.len()..];
+                in_link = true;
+                continue;
+            }
+        }
+        if in_link && rest.starts_with("</a>") {
+            escaped.push_str("</a>");
+            rest = &rest["</a>".len()..];
+            in_link = false;
+            continue;
+        }
+
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+
+    // An unclosed link would swallow the rest of the output in the CGIs.
+    if in_link {
+        escaped.push_str("</a>");
+    }
+
+    escaped
+}
+
+/// Returns the opening tag if `s` starts with a well-formed link to a http(s) URL.
+fn link_start(s: &str) -> Option<&str> {
+    const PREFIX: &str = "<a href=\"";
+    let url = s.strip_prefix(PREFIX)?;
+    let end = url.find("\">")?;
+    let url = &url[..end];
+
+    let valid =
+        (url.starts_with("https://") || url.starts_with("http://")) && url.chars().all(is_url_char);
+
+    if valid {
+        Some(&s[..PREFIX.len() + end + 2])
+    } else {
+        None
+    }
+}
+
+/// Returns whether the character may appear in a link target. Quotes would end the attribute and
+/// `&` could start an entity, so both are rejected together with everything outside RFC 3986.
+fn is_url_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-._~:/?#[]@!$()*+,;=%".contains(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_mode_apply() {
+        let s = r#"<b>bad</b> see <a href="https://wiki/runbook?a=1;b=2">runbook & co</a>"#;
+
+        assert_eq!(HtmlMode::Raw.apply(s), s);
+        assert_eq!(
+            HtmlMode::Escape.apply(s),
+            "&lt;b&gt;bad&lt;/b&gt; see &lt;a href=&quot;https://wiki/runbook?a=1;b=2&quot;&gt;\
+             runbook &amp; co&lt;/a&gt;"
+        );
+        assert_eq!(
+            HtmlMode::EscapeExceptLinks.apply(s),
+            r#"&lt;b&gt;bad&lt;/b&gt; see <a href="https://wiki/runbook?a=1;b=2">runbook &amp; co</a>"#
+        );
+        assert_eq!(
+            HtmlMode::EscapeExceptLinks.apply(r#"<a href="https://x?a=1&b=2">y</a>"#),
+            "&lt;a href=&quot;https://x?a=1&amp;b=2&quot;&gt;y&lt;/a&gt;"
+        );
+        assert_eq!(
+            HtmlMode::EscapeExceptLinks.apply(r#"<a href="javascript:x">y</a>"#),
+            "&lt;a href=&quot;javascript:x&quot;&gt;y&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_link_attribute_injection() {
+        let escaped =
+            HtmlMode::EscapeExceptLinks.apply(r#"<a href="https://x"onmouseover="alert(1)">y</a>"#);
+        assert_eq!(
+            escaped,
+            "&lt;a href=&quot;https://x&quot;onmouseover=&quot;alert(1)&quot;&gt;y&lt;/a&gt;"
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{ToPerfString, Unit};
+
+const PREFIXES: [(f64, &str); 6] = [
+    (1e18, "E"),
+    (1e15, "P"),
+    (1e12, "T"),
+    (1e9, "G"),
+    (1e6, "M"),
+    (1e3, "k"),
+];
+
+/// Renders the value with a decimal prefix and at most three significant digits, followed by the
+/// unit, e.g. `1.23MB` or `4.5G`. Meant for messages read by humans, performance data should
+/// always contain the raw value.
+///
+/// Values below 1000 and values of units which already have a prefix (`KB`, `ms`, ...) or don't
+/// take one (`%`) are rendered as is.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{humanize, Unit};
+///
+/// assert_eq!(humanize(1_234_567.0, &Unit::Bytes), "1.23MB");
+/// assert_eq!(humanize(4_500_000_000.0, &Unit::None), "4.5G");
+/// assert_eq!(humanize(42.0, &Unit::Percentage), "42%");
+/// ```
+pub fn humanize(value: f64, unit: &Unit) -> String {
+    let takes_prefix = matches!(
+        unit,
+        Unit::None
+            | Unit::Bytes
+            | Unit::Counter
+            | Unit::Bits
+            | Unit::BitsPerSecond
+            | Unit::PacketsPerSecond
+            | Unit::Hertz
+            | Unit::Iops
+    );
+
+    let prefix = PREFIXES
+        .iter()
+        .find(|(factor, _)| takes_prefix && value.abs() >= *factor);
+    match prefix {
+        Some((factor, prefix)) => {
+            let scaled = value / factor;
+            format!("{}{}{}", round_significant(scaled), prefix, unit.as_str())
+        }
+        None => format!("{}{}", value, unit.as_str()),
+    }
+}
+
+/// Formats a value of a metric for messages, see [humanize]. Values which aren't numbers are
+/// rendered as is.
+pub(crate) fn humanize_value<T: ToPerfString>(value: &T, unit: &Unit) -> String {
+    let raw = value.to_perf_string();
+    match raw.parse::<f64>() {
+        Ok(number) if number.is_finite() && number.abs() >= 1e3 => humanize(number, unit),
+        _ => format!("{}{}", raw, unit.as_str()),
+    }
+}
+
+/// Rounds a value between 1 and 1000 to three significant digits without trailing zeros.
+fn round_significant(value: f64) -> String {
+    let decimals = match value.abs() {
+        v if v < 10.0 => 2,
+        v if v < 100.0 => 1,
+        _ => 0,
+    };
+    let s = format!("{:.*}", decimals, value);
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.').to_owned()
+    } else {
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_humanize() {
+        assert_eq!(humanize(999.0, &Unit::None), "999");
+        assert_eq!(humanize(1000.0, &Unit::None), "1k");
+        assert_eq!(humanize(45_678.0, &Unit::Bits), "45.7kb");
+        assert_eq!(humanize(-2_500_000.0, &Unit::None), "-2.5M");
+        assert_eq!(humanize(5000.0, &Unit::Megabytes), "5000MB");
+        assert_eq!(humanize_value(&123_456_789u64, &Unit::Bytes), "123MB");
+        assert_eq!(humanize_value(&42, &Unit::Seconds), "42s");
+    }
+}
@@ -0,0 +1,50 @@
+//! Minimal helpers to write JSON without pulling in a serializer.
+use crate::PerfString;
+
+/// Returns the given string as a quoted and escaped JSON string.
+pub(crate) fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Returns the quoted string or `null`.
+pub(crate) fn opt(s: Option<&str>) -> String {
+    s.map_or_else(|| "null".to_owned(), quote)
+}
+
+/// Returns all parts of the entry as a JSON object.
+pub(crate) fn perf_string(s: &PerfString) -> String {
+    format!(
+        "{{\"label\":{},\"value\":{},\"unit\":{},\"warning\":{},\"critical\":{},\"min\":{},\"max\":{}}}",
+        quote(&s.label),
+        quote(&s.value),
+        quote(s.unit.as_str()),
+        quote(&s.warning),
+        quote(&s.critical),
+        quote(&s.minimum),
+        quote(&s.maximum),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("a \"b\"\n\\\u{1}"), r#""a \"b\"\n\\\u0001""#);
+    }
+}
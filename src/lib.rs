@@ -1,14 +1,136 @@
 //! This crate provides utilities to write Icinga/Nagios checks/plugins.
 //! If you want to use this library only for compatible output take a look at the [Resource].
-//! If you also want error handling, take a look at [safe_run].
+//! If you also want error handling, take a look at [safe_run] or [Runner].
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::Formatter;
+use std::future::Future;
+use std::io::IsTerminal;
+use std::process::{ExitCode, Termination};
 
+use crate::perf_conflict::PerfConflict;
 use crate::ServiceState::{Critical, Warning};
 use std::str::FromStr;
 
+pub use crate::baseline::Baseline;
+pub use crate::bulk::BulkCheck;
+pub use crate::cache::Cache;
+pub use crate::catalog::{
+    ListFormat, ListFormatError, MetricCatalog, MetricDefinition, LIST_METRICS_ARG,
+};
+pub use crate::check::{
+    AsyncCheck, Check, CheckError, CheckFilter, CheckFilterError, CheckFuture, CheckRegistry,
+};
+pub use crate::debug::DebugJson;
+pub use crate::error_policy::{ErrorClass, ErrorPolicy, ErrorPolicyParseError};
+pub use crate::exit_summary::ExitSummary;
+pub use crate::explain::Explain;
+pub use crate::forecast::Forecast;
+pub use crate::histogram::Histogram;
+pub use crate::html::HtmlMode;
+pub use crate::humanize::humanize;
+pub use crate::macros::MacroExpander;
+pub use crate::metric_filter::{MetricFilter, MetricFilterError};
+pub use crate::metric_policy::{MetricPolicy, MetricPolicyError};
+pub use crate::mrtg::MrtgError;
+pub use crate::pattern::{Pattern, PatternError};
+pub use crate::percentiles::Percentiles;
+pub use crate::perf_conflict::PerfConflictPolicy;
+pub use crate::perf_parse::{
+    parse_perf_data, parse_plugin_output, rethreshold, PerfDataParseError, PluginOutput,
+};
+pub use crate::quantity::{Quantity, QuantityParseError, UnitConversionError};
+pub use crate::range::{ThresholdRange, ThresholdRangeParseError};
+pub use crate::relabel::Relabel;
+pub use crate::runner::{Runner, TimeoutArgError};
+pub use crate::sampling::{sample_n, Samples};
+pub use crate::secret::{Secret, SecretError};
+pub use crate::severity::SeverityOrdering;
+pub use crate::state_dir::{state_dir, RejectedStateDir, StateDirError, DEFAULT_STATE_PATH};
+pub use crate::state_file::StateFile;
+pub use crate::sub_check::SubChecks;
+pub use crate::summary::SummaryHeader;
+pub use crate::thresholds::{NamedThreshold, NamedThresholdParseError, NamedThresholds};
+pub use crate::timer::{MetricTimer, Timings};
+pub use crate::timestamp::{OutputTimestamp, TimestampFormat};
+pub use crate::tolerance::FailureTolerance;
+#[cfg(feature = "uom")]
+pub use crate::typed_quantity::UomQuantity;
+pub use crate::value::Value;
+
+mod args;
+mod baseline;
+mod bulk;
+mod cache;
+mod catalog;
+mod check;
+mod checkmk;
+#[cfg(feature = "clap")]
+pub mod cli;
+pub mod compat;
+pub mod config;
+pub mod config_generator;
+mod debug;
+mod error_policy;
+mod escalation;
+pub mod exec;
+mod exit_summary;
+mod expiry;
+mod explain;
+mod forecast;
+mod graph;
+mod histogram;
+mod html;
+mod humanize;
+mod json;
+mod macros;
+#[cfg(feature = "man")]
+pub mod man;
+mod metric_filter;
+mod metric_policy;
+mod mrtg;
+pub mod net;
+#[cfg(feature = "otel")]
+mod otel;
+mod pattern;
+mod percentiles;
+mod perf_conflict;
+mod perf_parse;
+#[cfg(all(feature = "privdrop", unix))]
+pub mod privilege;
+mod prometheus;
+mod quantity;
+mod range;
+mod relabel;
+mod runner;
+mod sampling;
+mod secret;
+mod self_metrics;
+#[cfg(feature = "serde")]
+mod serialization;
+mod severity;
+#[cfg(all(feature = "signals", unix))]
+pub mod signal;
+pub mod sink;
+mod state_dir;
+mod state_file;
+mod sub_check;
+mod summary;
+#[cfg(all(feature = "syslog", unix))]
+mod syslog;
+mod terminal;
+mod thresholds;
+mod timer;
+mod timestamp;
+mod tolerance;
+#[cfg(feature = "uom")]
+mod typed_quantity;
+mod value;
+
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "UPPERCASE"))]
 /// Represents the state of a service / resource.
 pub enum ServiceState {
     Ok,
@@ -29,6 +151,17 @@ impl ServiceState {
         }
     }
 
+    /// Returns the state for an exit code of a plugin, or `None` if it isn't one of 0 to 3.
+    pub fn from_exit_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(ServiceState::Ok),
+            1 => Some(ServiceState::Warning),
+            2 => Some(ServiceState::Critical),
+            3 => Some(ServiceState::Unknown),
+            _ => None,
+        }
+    }
+
     /// Returns a number for ordering purposes. Ordering is Ok < Unknown < Warning < Critical.
     /// So if you order you get the best to worst state.
     fn order_number(&self) -> u8 {
@@ -43,7 +176,7 @@ impl ServiceState {
 
 impl PartialOrd for ServiceState {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.order_number().partial_cmp(&other.order_number())
+        Some(self.cmp(other))
     }
 }
 
@@ -53,6 +186,12 @@ impl Ord for ServiceState {
     }
 }
 
+impl From<ServiceState> for ExitCode {
+    fn from(state: ServiceState) -> Self {
+        ExitCode::from(state.exit_code() as u8)
+    }
+}
+
 impl fmt::Display for ServiceState {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let s = match self {
@@ -101,11 +240,25 @@ pub enum Unit {
     Gigabytes,
     Terabytes,
     Counter,
+    Petabytes,
+    /// 1024 bytes.
+    Kibibytes,
+    Mebibytes,
+    Gibibytes,
+    Tebibytes,
+    Pebibytes,
+    Bits,
+    BitsPerSecond,
+    PacketsPerSecond,
+    Hertz,
+    /// I/O operations per second.
+    Iops,
     Other(UnitString),
 }
 
 impl Unit {
-    fn as_str(&self) -> &str {
+    /// Returns the unit as it is written in the performance data.
+    pub fn as_str(&self) -> &str {
         match self {
             Unit::None => "",
             Unit::Seconds => "s",
@@ -118,11 +271,57 @@ impl Unit {
             Unit::Gigabytes => "GB",
             Unit::Terabytes => "TB",
             Unit::Counter => "c",
+            Unit::Petabytes => "PB",
+            Unit::Kibibytes => "KiB",
+            Unit::Mebibytes => "MiB",
+            Unit::Gibibytes => "GiB",
+            Unit::Tebibytes => "TiB",
+            Unit::Pebibytes => "PiB",
+            Unit::Bits => "b",
+            Unit::BitsPerSecond => "bps",
+            Unit::PacketsPerSecond => "pps",
+            Unit::Hertz => "Hz",
+            Unit::Iops => "iops",
             Unit::Other(s) => &s.0,
         }
     }
 }
 
+impl FromStr for Unit {
+    type Err = UnitStringCreateError;
+
+    /// Parses the unit as it is written in the performance data. Unknown units become
+    /// [Unit::Other].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unit = match s {
+            "" => Unit::None,
+            "s" => Unit::Seconds,
+            "ms" => Unit::Milliseconds,
+            "us" => Unit::Microseconds,
+            "%" => Unit::Percentage,
+            "B" => Unit::Bytes,
+            "KB" => Unit::Kilobytes,
+            "MB" => Unit::Megabytes,
+            "GB" => Unit::Gigabytes,
+            "TB" => Unit::Terabytes,
+            "c" => Unit::Counter,
+            "PB" => Unit::Petabytes,
+            "KiB" => Unit::Kibibytes,
+            "MiB" => Unit::Mebibytes,
+            "GiB" => Unit::Gibibytes,
+            "TiB" => Unit::Tebibytes,
+            "PiB" => Unit::Pebibytes,
+            "b" => Unit::Bits,
+            "bps" => Unit::BitsPerSecond,
+            "pps" => Unit::PacketsPerSecond,
+            "Hz" => Unit::Hertz,
+            "iops" => Unit::Iops,
+            other => Unit::Other(UnitString::new(other)?),
+        };
+        Ok(unit)
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
 /// This error is returned if a [UnitString] is created with an invalid string.
@@ -134,23 +333,68 @@ pub enum UnitStringCreateError {
 
 #[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord)]
 /// Newtype wrapper around a string to ensure only valid strings end up in the performance data.
-pub struct UnitString(String);
+pub struct UnitString(Cow<'static, str>);
 
 impl UnitString {
     pub fn new(s: impl Into<String>) -> Result<Self, UnitStringCreateError> {
         let s = s.into();
-        if ('0'..='9').chain(['"', ';']).any(|c| s.contains(c)) {
-            Err(UnitStringCreateError::InvalidCharacters)
-        } else {
+        if UnitString::is_valid(&s) {
             Ok(UnitString::new_unchecked(s))
+        } else {
+            Err(UnitStringCreateError::InvalidCharacters)
         }
     }
 
     pub fn new_unchecked(s: impl Into<String>) -> Self {
-        UnitString(s.into())
+        UnitString(Cow::Owned(s.into()))
+    }
+
+    /// Creates a [UnitString] from a static string. Panics if the string is invalid, which turns
+    /// into a compile time error if used in a const context. See [unit!] for a convenient way to
+    /// do this.
+    pub const fn from_static(s: &'static str) -> Self {
+        if !UnitString::is_valid(s) {
+            panic!("expected unit string to not include numbers, semicolons or quotes");
+        }
+        UnitString(Cow::Borrowed(s))
+    }
+
+    const fn is_valid(s: &str) -> bool {
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            if matches!(bytes[i], b'0'..=b'9' | b'"' | b';') {
+                return false;
+            }
+            i += 1;
+        }
+        true
     }
 }
 
+/// Creates a [Unit::Other] from a string literal which is validated at compile time.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{unit, Metric};
+///
+/// let metric = Metric::new("distance", 42).with_unit(unit!("km"));
+/// ```
+///
+/// Invalid unit strings are rejected by the compiler:
+///
+/// ```compile_fail
+/// let unit = nagiosplugin::unit!("km2");
+/// ```
+#[macro_export]
+macro_rules! unit {
+    ($s:literal) => {{
+        const UNIT: $crate::UnitString = $crate::UnitString::from_static($s);
+        $crate::Unit::Other(UNIT)
+    }};
+}
+
 impl FromStr for UnitString {
     type Err = UnitStringCreateError;
 
@@ -159,8 +403,11 @@ impl FromStr for UnitString {
     }
 }
 
-/// Defines if a metric triggers if value is greater or less than the thresholds.
+/// Defines if a metric triggers if value is greater or less than the thresholds. For ranges which
+/// trigger outside or inside of two bounds, see [Metric::with_threshold_ranges].
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum TriggerIfValue {
     Greater,
     Less,
@@ -175,17 +422,59 @@ impl From<&TriggerIfValue> for Ordering {
     }
 }
 
+/// Defines how a [Metric] whose value is not a number (`NaN`) is evaluated. The performance data
+/// value of such a metric is always reported as undetermined (`U`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum NanPolicy {
+    /// The metric reports the given state. Defaults to [ServiceState::Unknown].
+    State(ServiceState),
+    /// The metric doesn't influence the state.
+    Skip,
+}
+
+impl Default for NanPolicy {
+    fn default() -> Self {
+        NanPolicy::State(ServiceState::Unknown)
+    }
+}
+
+type WarningCriticalRanges<T> = (Option<ThresholdRange<T>>, Option<ThresholdRange<T>>);
+
 /// Defines a metric with a required name and value. Also takes optional thresholds (warning, critical)
 /// minimum, maximum. Can also be set to ignore thresholds and have a fixed [ServiceState].
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Metric<T> {
     name: String,
     value: T,
+    #[cfg_attr(feature = "serde", serde(default))]
     unit: Unit,
     thresholds: Option<(Option<T>, Option<T>, TriggerIfValue)>,
+    ranges: Option<WarningCriticalRanges<T>>,
     min: Option<T>,
     max: Option<T>,
     fixed_state: Option<ServiceState>,
+    fixed_state_reason: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    nan_policy: NanPolicy,
+    #[cfg_attr(feature = "serde", serde(default))]
+    validation: Validation,
+    total: Option<T>,
+    message_template: Option<String>,
+}
+
+/// Defines if and how the value of a [Metric] is checked for plausibility.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+enum Validation {
+    #[default]
+    Off,
+    /// The valid range is derived from the unit.
+    ByUnit,
+    Range(Option<f64>, Option<f64>),
 }
 
 impl<T> Metric<T> {
@@ -195,12 +484,20 @@ impl<T> Metric<T> {
             value,
             unit: Default::default(),
             thresholds: Default::default(),
+            ranges: Default::default(),
             min: Default::default(),
             max: Default::default(),
             fixed_state: Default::default(),
+            fixed_state_reason: Default::default(),
+            nan_policy: Default::default(),
+            validation: Default::default(),
+            total: Default::default(),
+            message_template: Default::default(),
         }
     }
 
+    /// Sets single value thresholds. Use [Metric::with_threshold_ranges] to alert if the value is
+    /// outside or inside of a range.
     pub fn with_thresholds(
         mut self,
         warning: impl Into<Option<T>>,
@@ -211,6 +508,33 @@ impl<T> Metric<T> {
         self
     }
 
+    /// Sets thresholds in the range format of the plugin guidelines, e.g. parsed from the `-w`
+    /// and `-c` arguments. They take precedence over [Metric::with_thresholds] and are included
+    /// in the performance data as they are.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use nagiosplugin::{Metric, Resource, ServiceState, ThresholdRange};
+    ///
+    /// let warning: ThresholdRange<f64> = "20:80".parse().unwrap();
+    /// let critical: ThresholdRange<f64> = "10:90".parse().unwrap();
+    /// let resource = Resource::new("tank")
+    ///     .with_result(Metric::new("level", 15.0).with_threshold_ranges(warning, critical));
+    ///
+    /// let (state, output) = resource.nagios_result();
+    /// assert_eq!(state, ServiceState::Warning);
+    /// assert!(output.contains("'level'=15;20:80;10:90;;"));
+    /// ```
+    pub fn with_threshold_ranges(
+        mut self,
+        warning: impl Into<Option<ThresholdRange<T>>>,
+        critical: impl Into<Option<ThresholdRange<T>>>,
+    ) -> Self {
+        self.ranges = Some((warning.into(), critical.into()));
+        self
+    }
+
     pub fn with_minimum(mut self, minimum: T) -> Self {
         self.min = Some(minimum);
         self
@@ -228,17 +552,155 @@ impl<T> Metric<T> {
         self
     }
 
+    /// Like [Metric::with_fixed_state], but the reason is included in the message, so forced
+    /// states can be told apart from real ones.
+    pub fn with_fixed_state_reason(
+        mut self,
+        state: ServiceState,
+        reason: impl Into<String>,
+    ) -> Self {
+        self.fixed_state = Some(state);
+        self.fixed_state_reason = Some(reason.into());
+        self
+    }
+
     pub fn with_unit(mut self, unit: Unit) -> Self {
         self.unit = unit;
         self
     }
+
+    /// Sets how the metric is evaluated if its value is not a number. A fixed state takes
+    /// precedence.
+    pub fn with_nan_policy(mut self, policy: NanPolicy) -> Self {
+        self.nan_policy = policy;
+        self
+    }
+
+    /// Checks the value against the valid range of its unit, so collection bugs show up as
+    /// [ServiceState::Unknown] instead of bogus states. Counters, sizes, bits, rates, frequencies
+    /// and IOPS must not be negative and percentages must be between 0 and 100. Other units are
+    /// not checked. A fixed state takes precedence.
+    pub fn with_validation(mut self) -> Self {
+        self.validation = Validation::ByUnit;
+        self
+    }
+
+    /// Like [Metric::with_validation], but with the given range instead of the one of the unit,
+    /// e.g. for percentages which may exceed 100.
+    pub fn with_valid_range(
+        mut self,
+        minimum: impl Into<Option<f64>>,
+        maximum: impl Into<Option<f64>>,
+    ) -> Self {
+        self.validation = Validation::Range(minimum.into(), maximum.into());
+        self
+    }
+
+    /// Treats the value as the used part of a capacity, e.g. of a disk. Instead of a single entry
+    /// the performance data contains `<name>_used`, `<name>_total` and `<name>_used_pct`. Only the
+    /// used value is checked against the thresholds. The total is also used as maximum of the used
+    /// value, unless a maximum is set.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use nagiosplugin::{Metric, Resource, TriggerIfValue, Unit};
+    ///
+    /// let resource = Resource::new("disk").with_result(
+    ///     Metric::new("root", 750)
+    ///         .with_unit(Unit::Gigabytes)
+    ///         .with_total(1000)
+    ///         .with_thresholds(800, 900, TriggerIfValue::Greater),
+    /// );
+    /// assert_eq!(
+    ///     resource.nagios_result().1,
+    ///     "disk is OK|'root_used'=750GB;800;900;;1000 'root_total'=1000GB;;;; 'root_used_pct'=75%;;;0;100"
+    /// );
+    /// ```
+    pub fn with_total(mut self, total: T) -> Self {
+        self.total = Some(total);
+        self
+    }
+
+    /// Replaces the message which is generated if the value exceeds a threshold. The
+    /// placeholders `{name}`, `{state}`, `{value}`, `{unit}` and `{threshold}` are replaced with
+    /// the name, the state, the value and the exceeded threshold as in the performance data, and
+    /// the unit.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use nagiosplugin::{Metric, Resource, TriggerIfValue, Unit};
+    ///
+    /// let resource = Resource::new("db").with_result(
+    ///     Metric::new("replication_lag", 95)
+    ///         .with_unit(Unit::Seconds)
+    ///         .with_thresholds(60, 300, TriggerIfValue::Greater)
+    ///         .with_message_template("{state}: {name} is {value}{unit} behind, over {threshold}{unit}"),
+    /// );
+    /// assert!(resource
+    ///     .nagios_result()
+    ///     .1
+    ///     .contains("WARNING: replication_lag is 95s behind, over 60s"));
+    /// ```
+    pub fn with_message_template(mut self, template: impl Into<String>) -> Self {
+        self.message_template = Some(template.into());
+        self
+    }
+}
+
+impl<T: ToPerfString> Metric<T> {
+    /// Returns a description of the violation if the value is outside of the valid range.
+    fn range_violation(&self) -> Option<String> {
+        let (minimum, maximum) = match self.validation {
+            Validation::Off => return None,
+            Validation::ByUnit => match self.unit {
+                Unit::Percentage => (Some(0.0), Some(100.0)),
+                Unit::Counter
+                | Unit::Bytes
+                | Unit::Kilobytes
+                | Unit::Megabytes
+                | Unit::Gigabytes
+                | Unit::Terabytes
+                | Unit::Petabytes
+                | Unit::Kibibytes
+                | Unit::Mebibytes
+                | Unit::Gibibytes
+                | Unit::Tebibytes
+                | Unit::Pebibytes
+                | Unit::Bits
+                | Unit::BitsPerSecond
+                | Unit::PacketsPerSecond
+                | Unit::Hertz
+                | Unit::Iops => (Some(0.0), None),
+                _ => return None,
+            },
+            Validation::Range(minimum, maximum) => (minimum, maximum),
+        };
+
+        let value = self.value.to_perf_string();
+        let number = value.parse::<f64>().ok()?;
+        if minimum.is_some_and(|m| number < m) || maximum.is_some_and(|m| number > m) {
+            let bound = |b: Option<f64>| b.map(|b| b.to_string()).unwrap_or_default();
+            Some(format!(
+                "value '{}' is outside of the valid range '{}:{}'",
+                value,
+                bound(minimum),
+                bound(maximum)
+            ))
+        } else {
+            None
+        }
+    }
 }
 
 /// Represents a single performance metric.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PerfData<T> {
     name: String,
     value: T,
+    #[cfg_attr(feature = "serde", serde(default))]
     unit: Unit,
     warning: Option<T>,
     critical: Option<T>,
@@ -265,6 +727,16 @@ impl<T: ToPerfString> PerfData<T> {
         self
     }
 
+    pub fn with_warning(mut self, warning: T) -> Self {
+        self.warning = Some(warning);
+        self
+    }
+
+    pub fn with_critical(mut self, critical: T) -> Self {
+        self.critical = Some(critical);
+        self
+    }
+
     pub fn with_minimum(mut self, minimum: T) -> Self {
         self.minimum = Some(minimum);
         self
@@ -296,10 +768,90 @@ impl<T: ToPerfString> From<PerfData<T>> for PerfString {
     }
 }
 
-/// Newtype wrapper around a string to ensure only valid strings end up in the final output.
-/// This is used for the performance data / metric part of the output.
+/// Creates a [PerfData] in a terse way. Takes a name, a value with an optional unit and optional
+/// `warn`, `crit`, `min` and `max` values. The unit has to be one of the built-in units
+/// (`s`, `ms`, `us`, `%`, `B`, `KB`, `MB`, `GB`, `TB`, `c`, `PB`, `KiB`, `MiB`, `GiB`, `TiB`,
+/// `PiB`, `b`, `bps`, `pps`, `Hz`, `iops`). If the value is not a literal or a
+/// single identifier, it has to be wrapped in parentheses.
+///
+/// Numeric literals for `warn`, `crit`, `min` and `max` are converted to the type of the value
+/// like `as` does, so integer and float literals can be mixed. Other expressions must have the
+/// type of the value.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{perf_data, CheckResult};
+///
+/// let result = CheckResult::new()
+///     .with_perf_data(perf_data!("rta" => 0.123 ms, warn 100, crit 500));
+/// ```
+#[macro_export]
+macro_rules! perf_data {
+    (@unit $pd:expr; s $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Seconds; $($rest)*) };
+    (@unit $pd:expr; ms $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Milliseconds; $($rest)*) };
+    (@unit $pd:expr; us $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Microseconds; $($rest)*) };
+    (@unit $pd:expr; % $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Percentage; $($rest)*) };
+    (@unit $pd:expr; B $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Bytes; $($rest)*) };
+    (@unit $pd:expr; KB $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Kilobytes; $($rest)*) };
+    (@unit $pd:expr; MB $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Megabytes; $($rest)*) };
+    (@unit $pd:expr; GB $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Gigabytes; $($rest)*) };
+    (@unit $pd:expr; TB $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Terabytes; $($rest)*) };
+    (@unit $pd:expr; c $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Counter; $($rest)*) };
+    (@unit $pd:expr; PB $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Petabytes; $($rest)*) };
+    (@unit $pd:expr; KiB $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Kibibytes; $($rest)*) };
+    (@unit $pd:expr; MiB $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Mebibytes; $($rest)*) };
+    (@unit $pd:expr; GiB $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Gibibytes; $($rest)*) };
+    (@unit $pd:expr; TiB $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Tebibytes; $($rest)*) };
+    (@unit $pd:expr; PiB $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Pebibytes; $($rest)*) };
+    (@unit $pd:expr; b $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Bits; $($rest)*) };
+    (@unit $pd:expr; bps $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, BitsPerSecond; $($rest)*) };
+    (@unit $pd:expr; pps $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, PacketsPerSecond; $($rest)*) };
+    (@unit $pd:expr; Hz $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Hertz; $($rest)*) };
+    (@unit $pd:expr; iops $($rest:tt)*) => { $crate::perf_data!(@unit_set $pd, Iops; $($rest)*) };
+    (@unit $pd:expr; $($rest:tt)*) => { $crate::perf_data!(@opts $pd; $($rest)*) };
+    (@unit_set $pd:expr, $unit:ident; $($rest:tt)*) => {
+        $crate::perf_data!(@opts $pd.with_unit($crate::Unit::$unit); $($rest)*)
+    };
+
+    (@opts $pd:expr; $(,)?) => { $pd };
+    (@opts $pd:expr; , warn $($rest:tt)*) => { $crate::perf_data!(@opt $pd, with_warning; $($rest)*) };
+    (@opts $pd:expr; , crit $($rest:tt)*) => { $crate::perf_data!(@opt $pd, with_critical; $($rest)*) };
+    (@opts $pd:expr; , min $($rest:tt)*) => { $crate::perf_data!(@opt $pd, with_minimum; $($rest)*) };
+    (@opts $pd:expr; , max $($rest:tt)*) => { $crate::perf_data!(@opt $pd, with_maximum; $($rest)*) };
+    // Literals are cast to the type of the value, so `warn 100` works for a value of `0.5`.
+    (@opt $pd:expr, $method:ident; $v:literal $(, $($rest:tt)*)?) => {
+        $crate::perf_data!(@opts $pd.$method({
+            #[allow(clippy::unnecessary_cast)]
+            let v = $v as _;
+            v
+        }); $(, $($rest)*)?)
+    };
+    (@opt $pd:expr, $method:ident; $v:expr $(, $($rest:tt)*)?) => {
+        $crate::perf_data!(@opts $pd.$method($v); $(, $($rest)*)?)
+    };
+
+    ($name:expr => $value:literal $($rest:tt)*) => {
+        $crate::perf_data!(@unit $crate::PerfData::new($name, $value); $($rest)*)
+    };
+    ($name:expr => $value:tt $($rest:tt)*) => {
+        $crate::perf_data!(@unit $crate::PerfData::new($name, $value); $($rest)*)
+    };
+}
+
+/// A single formatted entry of the performance data / metric part of the output. All values are
+/// already converted to their string representation.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct PerfString(String);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PerfString {
+    label: String,
+    value: String,
+    unit: Unit,
+    warning: String,
+    critical: String,
+    minimum: String,
+    maximum: String,
+}
 
 impl PerfString {
     pub fn new<T>(
@@ -315,30 +867,85 @@ impl PerfString {
         T: ToPerfString,
     {
         // TODO: Sanitize name
-        let value = value.to_perf_string();
-        let warning = warning.map_or_else(|| "".to_owned(), |v| v.to_perf_string());
-        let critical = critical.map_or_else(|| "".to_owned(), |v| v.to_perf_string());
-        let minimum = minimum.map_or_else(|| "".to_owned(), |v| v.to_perf_string());
-        let maximum = maximum.map_or_else(|| "".to_owned(), |v| v.to_perf_string());
-        PerfString(format!(
-            "'{}'={}{};{};{};{};{}",
-            name,
+        // Non-finite values are left out, so `NaN` and `inf` never reach the performance data.
+        let to_string = |v: Option<&T>| {
+            v.map(|v| v.to_perf_string())
+                .filter(|v| !is_non_finite(v))
+                .unwrap_or_default()
+        };
+        let minimum = to_string(minimum);
+        let maximum = to_string(maximum);
+
+        // Infinite values are clamped to the minimum or maximum, if given. Everything else is
+        // reported as undetermined.
+        let value = match value.to_perf_string() {
+            v if v == "inf" && !maximum.is_empty() => maximum.clone(),
+            v if v == "-inf" && !minimum.is_empty() => minimum.clone(),
+            v if is_non_finite(&v) => UNDETERMINED_VALUE.to_owned(),
+            v => v,
+        };
+
+        PerfString {
+            label: name.to_owned(),
             value,
-            unit.as_str(),
-            warning,
-            critical,
+            unit,
+            warning: to_string(warning),
+            critical: to_string(critical),
             minimum,
-            maximum
-        ))
+            maximum,
+        }
+    }
+
+    /// Returns the label of this entry.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Returns the value of this entry.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn unit(&self) -> &Unit {
+        &self.unit
+    }
+}
+
+/// The value the plugin guidelines define for values which can't be determined.
+const UNDETERMINED_VALUE: &str = "U";
+
+fn is_non_finite(value: &str) -> bool {
+    matches!(value, "NaN" | "inf" | "-inf")
+}
+
+impl fmt::Display for PerfString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let unit = if self.value == UNDETERMINED_VALUE {
+            ""
+        } else {
+            self.unit.as_str()
+        };
+
+        write!(
+            f,
+            "'{}'={}{};{};{};{};{}",
+            self.label, self.value, unit, self.warning, self.critical, self.minimum, self.maximum
+        )
     }
 }
 
 /// Represents a single item of a check. Multiple of these are used to form a [Resource].
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CheckResult {
     state: Option<ServiceState>,
     message: Option<String>,
-    perf_string: Option<PerfString>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    details: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    perf_strings: Vec<PerfString>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    evaluation: Option<MetricEvaluation>,
 }
 
 impl CheckResult {
@@ -347,7 +954,50 @@ impl CheckResult {
         Self {
             state: Default::default(),
             message: Default::default(),
-            perf_string: Default::default(),
+            details: Default::default(),
+            perf_strings: Default::default(),
+            evaluation: Default::default(),
+        }
+    }
+
+    /// Converts the outcome of a step which may fail on its own, e.g. one probe of several. An
+    /// error becomes a [ServiceState::Unknown] result with the message `<name>: <error>`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use nagiosplugin::{CheckResult, Metric, Resource, ServiceState};
+    ///
+    /// fn probe_dns() -> Result<Metric<f64>, String> {
+    ///     Err("SERVFAIL".to_owned())
+    /// }
+    ///
+    /// let resource = Resource::new("site")
+    ///     .with_result(CheckResult::from_result("http", Ok::<_, String>(Metric::new("http_time", 0.2))))
+    ///     .with_result(CheckResult::from_result("dns", probe_dns()));
+    ///
+    /// let (state, output) = resource.nagios_result();
+    /// assert_eq!(state, ServiceState::Unknown);
+    /// assert!(output.contains("dns: SERVFAIL"));
+    /// ```
+    pub fn from_result<T: Into<CheckResult>, E: fmt::Display>(
+        name: &str,
+        result: Result<T, E>,
+    ) -> Self {
+        Self::from_result_with_error_state(name, result, ServiceState::Unknown)
+    }
+
+    /// Like [CheckResult::from_result], but an error is reported with the given state.
+    pub fn from_result_with_error_state<T: Into<CheckResult>, E: fmt::Display>(
+        name: &str,
+        result: Result<T, E>,
+        error_state: ServiceState,
+    ) -> Self {
+        match result {
+            Ok(value) => value.into(),
+            Err(err) => Self::new()
+                .with_state(error_state)
+                .with_message(format!("{}: {}", name, err)),
         }
     }
 
@@ -361,10 +1011,18 @@ impl CheckResult {
         self
     }
 
-    /// Sets the performance data of this result. Takes anything that implements [`Into<PerfString>`].
-    /// This includes [`PerfData`].
+    /// Adds a line which is rendered indented below the message in the long output, e.g. for a
+    /// per-item breakdown. Keeps the message short while preserving the details.
+    pub fn with_detail(mut self, line: impl Into<String>) -> Self {
+        self.details.push(line.into());
+        self
+    }
+
+    /// Adds performance data to this result. Takes anything that implements [`Into<PerfString>`].
+    /// This includes [`PerfData`]. Can be called multiple times, e.g. for the response time and the
+    /// size of a single request.
     pub fn with_perf_data(mut self, perf_data: impl Into<PerfString>) -> Self {
-        self.perf_string = Some(perf_data.into());
+        self.perf_strings.push(perf_data.into());
         self
     }
 }
@@ -377,71 +1035,281 @@ impl Default for CheckResult {
 
 impl<T: PartialOrd + ToPerfString> From<Metric<T>> for CheckResult {
     fn from(metric: Metric<T>) -> Self {
+        // NaN is the only value which isn't comparable to itself.
+        let is_nan = metric.value.partial_cmp(&metric.value).is_none();
+        let violation = if is_nan {
+            None
+        } else {
+            metric.range_violation()
+        };
+
+        // Whether the value breaches the warning and the critical threshold, if they are checked.
+        // The state and the diagnostic output are both derived from this.
+        let evaluated = metric.fixed_state.is_none() && !is_nan && violation.is_none();
+        let breaches = match (&metric.ranges, &metric.thresholds) {
+            (Some((warning, critical)), _) if evaluated => {
+                let alerting = |range: &Option<ThresholdRange<T>>| {
+                    range.as_ref().map(|r| r.is_alerting(&metric.value))
+                };
+                Some((alerting(warning), alerting(critical)))
+            }
+            (None, Some((warning, critical, trigger))) if evaluated => {
+                let ord: Ordering = trigger.into();
+                let breached = |threshold: &Option<T>| {
+                    let cmp = metric.value.partial_cmp(threshold.as_ref()?);
+                    Some(cmp == Some(ord) || cmp == Some(Ordering::Equal))
+                };
+                Some((breached(warning), breached(critical)))
+            }
+            _ => None,
+        };
+
         let state = if let Some(state) = metric.fixed_state {
             Some(state)
-        } else if let Some((warning, critical, trigger)) = &metric.thresholds {
-            let ord: Ordering = trigger.into();
-            let warning_cmp = warning.as_ref().and_then(|w| metric.value.partial_cmp(w));
-            let critical_cmp = critical.as_ref().and_then(|w| metric.value.partial_cmp(w));
-
-            [(critical_cmp, Critical), (warning_cmp, Warning)]
-                .iter()
-                .filter_map(|(cmp, state)| cmp.as_ref().map(|cmp| (cmp, state)))
-                .filter_map(|(&cmp, &state)| {
-                    if cmp == ord || cmp == Ordering::Equal {
-                        Some(state)
-                    } else {
-                        None
-                    }
-                })
-                .next()
+        } else if is_nan {
+            match metric.nan_policy {
+                NanPolicy::State(state) => Some(state),
+                NanPolicy::Skip => None,
+            }
+        } else if violation.is_some() {
+            Some(ServiceState::Unknown)
+        } else if let Some((warning, critical)) = breaches {
+            match (warning, critical) {
+                (_, Some(true)) => Some(Critical),
+                (Some(true), _) => Some(Warning),
+                _ => None,
+            }
         } else {
             None
         };
 
-        let message = match state {
-            Some(state) if state != ServiceState::Ok => {
-                let (warning, critical, _) = metric.thresholds.as_ref().unwrap();
-                let threshold = match state {
-                    ServiceState::Warning => warning.as_ref().unwrap(),
-                    ServiceState::Critical => critical.as_ref().unwrap(),
-                    _ => unreachable!(),
-                };
+        let threshold = |state| {
+            let (warning, critical, _) = metric.thresholds.as_ref()?;
+            match state {
+                ServiceState::Warning => warning.as_ref(),
+                ServiceState::Critical => critical.as_ref(),
+                _ => None,
+            }
+        };
+
+        let range = |state| {
+            let (warning, critical) = metric.ranges.as_ref()?;
+            match state {
+                ServiceState::Warning => warning.as_ref(),
+                ServiceState::Critical => critical.as_ref(),
+                _ => None,
+            }
+        };
+
+        let message = match (state, &metric.fixed_state_reason) {
+            (Some(state), Some(reason)) => Some(format!(
+                "metric '{}' is {}: {}",
+                &metric.name,
+                state,
+                forced_state_note(state, reason)
+            )),
+            (Some(state), None) if is_nan && metric.fixed_state.is_none() => Some(format!(
+                "metric '{}' is {}: value is not a number",
+                &metric.name, state
+            )),
+            (Some(state), None) if violation.is_some() && metric.fixed_state.is_none() => {
                 Some(format!(
-                    "metric '{}' is {}: value '{}' has exceeded threshold of '{}'",
+                    "metric '{}' is {}: {}",
                     &metric.name,
                     state,
-                    metric.value.to_perf_string(),
-                    threshold.to_perf_string(),
+                    violation.as_deref().unwrap_or_default()
                 ))
             }
+            (Some(state), None) if state != ServiceState::Ok && metric.ranges.is_some() => {
+                match (range(state), &metric.message_template) {
+                    (Some(range), Some(template)) => Some(fill_message_template(
+                        template,
+                        &[
+                            ("name", metric.name.clone()),
+                            ("state", state.to_string()),
+                            ("value", metric.value.to_perf_string()),
+                            ("unit", metric.unit.as_str().to_owned()),
+                            ("threshold", range.to_perf_string()),
+                        ],
+                    )),
+                    (Some(range), None) => Some(format!(
+                        "metric '{}' is {}: value '{}' is {} the range '{}'",
+                        &metric.name,
+                        state,
+                        humanize::humanize_value(&metric.value, &metric.unit),
+                        if range.is_inside() {
+                            "inside"
+                        } else {
+                            "outside"
+                        },
+                        range.to_perf_string(),
+                    )),
+                    (None, _) => Some(format!("metric '{}' is {}", &metric.name, state)),
+                }
+            }
+            (Some(state), None) if state != ServiceState::Ok => match threshold(state) {
+                Some(threshold) if metric.message_template.is_some() => {
+                    let template = metric.message_template.as_deref().unwrap_or_default();
+                    Some(fill_message_template(
+                        template,
+                        &[
+                            ("name", metric.name.clone()),
+                            ("state", state.to_string()),
+                            ("value", metric.value.to_perf_string()),
+                            ("unit", metric.unit.as_str().to_owned()),
+                            ("threshold", threshold.to_perf_string()),
+                        ],
+                    ))
+                }
+                Some(threshold) => Some(format!(
+                    "metric '{}' is {}: value '{}' has exceeded threshold of '{}'",
+                    &metric.name,
+                    state,
+                    humanize::humanize_value(&metric.value, &metric.unit),
+                    humanize::humanize_value(threshold, &metric.unit),
+                )),
+                None => Some(format!("metric '{}' is {}", &metric.name, state)),
+            },
             _ => None,
         };
 
-        let perf_string = {
+        let perf_strings = {
             let (warning, critical) = if let Some((warning, critical, _)) = &metric.thresholds {
                 (warning.as_ref(), critical.as_ref())
             } else {
                 (None, None)
             };
 
-            PerfString::new(
-                &metric.name,
-                &metric.value,
-                metric.unit,
+            let mut perf_strings = match &metric.total {
+                Some(total) => capacity_perf_strings(&metric, total, warning, critical),
+                None => vec![PerfString::new(
+                    &metric.name,
+                    &metric.value,
+                    metric.unit.clone(),
+                    warning,
+                    critical,
+                    metric.min.as_ref(),
+                    metric.max.as_ref(),
+                )],
+            };
+            if let (Some((warning, critical)), Some(first)) =
+                (&metric.ranges, perf_strings.first_mut())
+            {
+                let to_string = |r: &Option<ThresholdRange<T>>| {
+                    r.as_ref().map(|r| r.to_perf_string()).unwrap_or_default()
+                };
+                first.warning = to_string(warning);
+                first.critical = to_string(critical);
+            }
+            perf_strings
+        };
+
+        let evaluation = {
+            let (warning, critical, trigger) = match (&metric.ranges, &metric.thresholds) {
+                (Some((warning, critical)), _) => (
+                    warning.as_ref().map(|r| r.to_perf_string()),
+                    critical.as_ref().map(|r| r.to_perf_string()),
+                    None,
+                ),
+                (None, Some((warning, critical, trigger))) => (
+                    warning.as_ref().map(|v| v.to_perf_string()),
+                    critical.as_ref().map(|v| v.to_perf_string()),
+                    Some(*trigger),
+                ),
+                (None, None) => (None, None, None),
+            };
+
+            MetricEvaluation {
+                name: metric.name.clone(),
+                value: metric.value.to_perf_string(),
                 warning,
                 critical,
-                metric.min.as_ref(),
-                metric.max.as_ref(),
-            )
+                trigger,
+                fixed_state: metric.fixed_state,
+                state,
+                warning_breached: breaches.and_then(|(warning, _)| warning),
+                critical_breached: breaches.and_then(|(_, critical)| critical),
+                reason: match metric.fixed_state {
+                    Some(_) => None,
+                    None if is_nan => Some("value is not a number".to_owned()),
+                    None => violation,
+                },
+            }
         };
 
         CheckResult {
             state,
             message,
-            perf_string: Some(perf_string),
+            details: Vec::new(),
+            perf_strings,
+            evaluation: Some(evaluation),
+        }
+    }
+}
+
+/// Returns the used, total and percentage entries of a metric with a total, see
+/// [Metric::with_total]. The percentage is left out if it can't be calculated.
+fn capacity_perf_strings<T: ToPerfString>(
+    metric: &Metric<T>,
+    total: &T,
+    warning: Option<&T>,
+    critical: Option<&T>,
+) -> Vec<PerfString> {
+    let mut perf_strings = vec![
+        PerfString::new(
+            &format!("{}_used", metric.name),
+            &metric.value,
+            metric.unit.clone(),
+            warning,
+            critical,
+            metric.min.as_ref(),
+            Some(metric.max.as_ref().unwrap_or(total)),
+        ),
+        PerfString::new(
+            &format!("{}_total", metric.name),
+            total,
+            metric.unit.clone(),
+            None,
+            None,
+            None,
+            None,
+        ),
+    ];
+
+    let parse = |v: &T| v.to_perf_string().parse::<f64>().ok();
+    if let (Some(used), Some(total)) = (parse(&metric.value), parse(total)) {
+        if total > 0.0 {
+            let percentage = (used / total * 10000.0).round() / 100.0;
+            perf_strings.push(PerfString::new(
+                &format!("{}_used_pct", metric.name),
+                &percentage,
+                Unit::Percentage,
+                None,
+                None,
+                Some(&0.0),
+                Some(&100.0),
+            ));
         }
     }
+
+    perf_strings
+}
+
+/// Records how the state of a [Metric] was determined, for diagnostic output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MetricEvaluation {
+    name: String,
+    value: String,
+    warning: Option<String>,
+    critical: Option<String>,
+    trigger: Option<TriggerIfValue>,
+    fixed_state: Option<ServiceState>,
+    state: Option<ServiceState>,
+    /// Whether the thresholds are breached, if they were checked.
+    warning_breached: Option<bool>,
+    critical_breached: Option<bool>,
+    /// Why the thresholds weren't checked, e.g. because the value is not a number.
+    reason: Option<String>,
 }
 
 /// Implement this if you have a value which can be converted to a performance metric value.
@@ -474,13 +1342,40 @@ impl_to_perf_string!(i128);
 impl_to_perf_string!(f32);
 impl_to_perf_string!(f64);
 
+/// Decimals are written exactly, without trailing zeros.
+#[cfg(feature = "rust_decimal")]
+impl ToPerfString for rust_decimal::Decimal {
+    fn to_perf_string(&self) -> String {
+        self.normalize().to_string()
+    }
+}
+
 /// Represents a single service / resource from the perspective of Icinga.
 #[derive(Debug, PartialEq, Eq)]
 pub struct Resource {
     name: String,
     results: Vec<CheckResult>,
     fixed_state: Option<ServiceState>,
+    fixed_state_reason: Option<String>,
+    empty_state: ServiceState,
     description: Option<String>,
+    output_prefix: Option<String>,
+    output_suffix: Option<String>,
+    summary_header: SummaryHeader,
+    timestamp: Option<OutputTimestamp>,
+    max_perf_line_length: Option<usize>,
+    message_separator: Option<String>,
+    max_messages_length: Option<usize>,
+    html_mode: HtmlMode,
+    terminal_preview: bool,
+    debug_json: Option<DebugJson>,
+    explain: Option<Explain>,
+    verbosity: u8,
+    metric_filter: Option<MetricFilter>,
+    relabel: Option<Relabel>,
+    perf_conflict_policy: Option<PerfConflictPolicy>,
+    perf_conflicts: Vec<PerfConflict>,
+    severity_ordering: SeverityOrdering,
 }
 
 impl Resource {
@@ -490,10 +1385,60 @@ impl Resource {
             name: name.into(),
             results: Default::default(),
             fixed_state: Default::default(),
+            fixed_state_reason: Default::default(),
+            empty_state: ServiceState::Ok,
             description: Default::default(),
+            output_prefix: Default::default(),
+            output_suffix: Default::default(),
+            summary_header: Default::default(),
+            timestamp: Default::default(),
+            max_perf_line_length: Default::default(),
+            message_separator: Default::default(),
+            max_messages_length: Default::default(),
+            html_mode: Default::default(),
+            terminal_preview: Default::default(),
+            debug_json: Default::default(),
+            explain: Default::default(),
+            verbosity: Default::default(),
+            metric_filter: Default::default(),
+            relabel: Default::default(),
+            perf_conflict_policy: Default::default(),
+            perf_conflicts: Default::default(),
+            severity_ordering: Default::default(),
         }
     }
 
+    /// Creates a new instance with the given name and a result for each of the given metrics.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use nagiosplugin::{Metric, Resource, TriggerIfValue};
+    ///
+    /// let usage = [("/", 42), ("/var", 93)];
+    /// let resource = Resource::from_metrics(
+    ///     "disk",
+    ///     usage
+    ///         .iter()
+    ///         .map(|(mount, value)| Metric::new(*mount, *value).with_thresholds(80, 90, TriggerIfValue::Greater)),
+    /// )
+    /// .with_description("disk usage");
+    /// ```
+    pub fn from_metrics<T: PartialOrd + ToPerfString>(
+        name: impl Into<String>,
+        metrics: impl IntoIterator<Item = Metric<T>>,
+    ) -> Self {
+        Self::from_results(name, metrics)
+    }
+
+    /// Creates a new instance with the given name and results.
+    pub fn from_results(
+        name: impl Into<String>,
+        results: impl IntoIterator<Item = impl Into<CheckResult>>,
+    ) -> Self {
+        Self::new(name).with_results(results)
+    }
+
     /// If a fixed state is set, the coressponding [Resource] will always report the given state regardless of the
     /// actual state of the [CheckResult]s.
     pub fn with_fixed_state(mut self, state: ServiceState) -> Self {
@@ -501,11 +1446,42 @@ impl Resource {
         self
     }
 
+    /// Like [Resource::with_fixed_state], but the reason is included in the summary, e.g.
+    /// `foo is OK: all good (state forced to OK: maintenance override via --force-ok)`, so
+    /// operators can tell forced states from real ones.
+    pub fn with_fixed_state_reason(
+        mut self,
+        state: ServiceState,
+        reason: impl Into<String>,
+    ) -> Self {
+        self.fixed_state = Some(state);
+        self.fixed_state_reason = Some(reason.into());
+        self
+    }
+
+    /// Sets the state which is reported if the resource has no results, e.g.
+    /// [ServiceState::Unknown] so broken data collection doesn't go unnoticed. Defaults to
+    /// [ServiceState::Ok].
+    pub fn with_empty_state(mut self, state: ServiceState) -> Self {
+        self.empty_state = state;
+        self
+    }
+
     pub fn with_result(mut self, result: impl Into<CheckResult>) -> Self {
         self.push_result(result);
         self
     }
 
+    pub fn with_results(
+        mut self,
+        results: impl IntoIterator<Item = impl Into<CheckResult>>,
+    ) -> Self {
+        for result in results {
+            self.push_result(result);
+        }
+        self
+    }
+
     pub fn with_description(mut self, description: impl Into<String>) -> Self {
         self.set_description(description);
         self
@@ -515,83 +1491,541 @@ impl Resource {
         self.description = Some(description.into());
     }
 
-    pub fn push_result(&mut self, result: impl Into<CheckResult>) {
-        self.results.push(result.into());
+    /// Puts the given text, e.g. the environment or datacenter, separated by a space in front of
+    /// the summary line.
+    pub fn with_output_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.output_prefix = Some(prefix.into());
+        self
     }
 
-    /// Calculates the state and message of this resource
-    pub fn nagios_result(self) -> (ServiceState, String) {
-        let (state, messages, perf_string) = {
-            let mut final_state = ServiceState::Ok;
-
-            let mut messages = String::new();
-            let mut perf_string = String::new();
-
-            for result in self.results {
-                if let Some(state) = result.state {
-                    if final_state < state {
-                        final_state = state;
-                    }
-                }
-
-                if let Some(message) = result.message {
-                    messages.push_str(message.trim());
-                    messages.push('\n');
-                }
-
-                if let Some(s) = result.perf_string {
-                    perf_string.push(' ');
-                    perf_string.push_str(s.0.trim());
-                }
-            }
-
-            if let Some(state) = self.fixed_state {
-                final_state = state;
-            }
+    /// Appends the given text separated by a space to the summary line.
+    pub fn with_output_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.output_suffix = Some(suffix.into());
+        self
+    }
 
-            (final_state, messages, perf_string)
-        };
+    /// Sets the header of the summary line, which defaults to `<name> is <STATE>`.
+    pub fn with_summary_header(mut self, header: SummaryHeader) -> Self {
+        self.summary_header = header;
+        self
+    }
 
-        let description = {
-            let mut s = String::new();
-            s.push_str(&self.name);
-            s.push_str(" is ");
-            s.push_str(&state.to_string());
+    /// Includes the time the data was collected in the output, either at the end of the summary or
+    /// of the long output.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use nagiosplugin::{OutputTimestamp, Resource, TimestampFormat};
+    ///
+    /// let resource = Resource::new("foo")
+    ///     .with_timestamp(OutputTimestamp::now().with_format(TimestampFormat::Epoch));
+    /// ```
+    pub fn with_timestamp(mut self, timestamp: OutputTimestamp) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
 
-            if let Some(description) = self.description {
-                s.push_str(": ");
-                s.push_str(description.trim());
+    pub fn push_result(&mut self, result: impl Into<CheckResult>) {
+        let mut result = result.into();
+        if self.metric_filter.as_ref().is_none_or(|f| f.keeps(&result)) {
+            if let Some(relabel) = &self.relabel {
+                relabel.relabel(&mut result);
             }
-            s
-        };
-
-        let mut result = String::new();
-        result.push_str(&description);
-
-        if !messages.is_empty() {
-            result.push_str("\n\n");
-            result.push_str(&messages);
+            let errors = match self.perf_conflict_policy {
+                Some(policy) => perf_conflict::resolve(
+                    policy,
+                    &mut self.results,
+                    &mut self.perf_conflicts,
+                    &mut result,
+                ),
+                None => Vec::new(),
+            };
+            self.results.push(result);
+            self.results.extend(errors);
         }
+    }
 
-        if !perf_string.is_empty() {
-            result.push_str("|");
-            result.push_str(perf_string.trim());
-        }
+    /// Drops the results of metrics not selected by the filter, including the ones already added,
+    /// so they influence neither the state nor the output.
+    pub fn with_metric_filter(mut self, filter: MetricFilter) -> Self {
+        self.results.retain(|r| filter.keeps(r));
+        self.metric_filter = Some(filter);
+        self
+    }
 
-        (state, result)
+    /// Detects performance data entries sharing a label, including the ones already added, and
+    /// handles conflicting ones according to the policy. From verbosity 2 on, see
+    /// [Resource::with_verbosity], the conflicts are listed in the long output.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use nagiosplugin::{Metric, PerfConflictPolicy, Resource};
+    ///
+    /// let resource = Resource::new("disks")
+    ///     .with_perf_conflict_policy(PerfConflictPolicy::Drop)
+    ///     .with_result(Metric::new("sda", 10))
+    ///     .with_result(Metric::new("sda", 20))
+    ///     .with_result(Metric::new("sdb", 30));
+    /// assert_eq!(resource.nagios_result().1, "disks is OK|'sdb'=30;;;;");
+    /// ```
+    pub fn with_perf_conflict_policy(mut self, policy: PerfConflictPolicy) -> Self {
+        self.perf_conflict_policy = Some(policy);
+        // The stored results were already filtered and relabeled, so only the conflicts are
+        // resolved here.
+        for mut result in std::mem::take(&mut self.results) {
+            let errors = perf_conflict::resolve(
+                policy,
+                &mut self.results,
+                &mut self.perf_conflicts,
+                &mut result,
+            );
+            self.results.push(result);
+            self.results.extend(errors);
+        }
+        self
+    }
+
+    /// Sets which state wins if the results have different states. By default a
+    /// [ServiceState::Unknown] result is hidden by a warning or critical one.
+    pub fn with_severity_ordering(mut self, ordering: SeverityOrdering) -> Self {
+        self.severity_ordering = ordering;
+        self
+    }
+
+    /// Renames the performance data labels of all results, including the ones already added. A
+    /// [MetricFilter] still sees the original names.
+    pub fn with_relabel(mut self, relabel: Relabel) -> Self {
+        for result in &mut self.results {
+            relabel.relabel(result);
+        }
+        self.relabel = Some(relabel);
+        self
+    }
+
+    /// Splits the performance data into lines of at most the given length. The first line is put
+    /// behind the summary and the remaining lines behind the long output, as described in the
+    /// [Nagios plugin API](https://assets.nagios.com/downloads/nagioscore/docs/nagioscore/3/en/pluginapi.html).
+    /// A single entry longer than the given length gets a line of its own.
+    pub fn with_max_perf_line_length(mut self, length: usize) -> Self {
+        self.max_perf_line_length = Some(length);
+        self
+    }
+
+    /// Joins the messages with the given separator and appends them to the summary line, e.g.
+    /// `", "` for agents which only read a single line. By default each message is written on a
+    /// line of its own below the summary.
+    pub fn with_message_separator(mut self, separator: impl Into<String>) -> Self {
+        self.message_separator = Some(separator.into());
+        self
+    }
+
+    /// Truncates the joined messages to at most the given length in bytes. Truncated messages end
+    /// with `...`.
+    pub fn with_max_messages_length(mut self, length: usize) -> Self {
+        self.max_messages_length = Some(length);
+        self
+    }
+
+    /// Sets how HTML in the summary and messages is treated. Performance data is never changed.
+    pub fn with_html_mode(mut self, mode: HtmlMode) -> Self {
+        self.html_mode = mode;
+        self
+    }
+
+    /// If set and stdout is a terminal, [RunResult::print_and_exit] prints a colored, aligned
+    /// preview for humans instead of the plugin output. If stdout is not a terminal, e.g. if the
+    /// plugin is run by the monitoring system, the plain output is printed as usual.
+    pub fn with_terminal_preview(mut self, enabled: bool) -> Self {
+        self.terminal_preview = enabled;
+        self
+    }
+
+    /// If set, [RunResult::print_and_exit] additionally writes a JSON dump of all results, metric
+    /// values, thresholds and the states determined from them to the given target. Meant to be
+    /// wired to a hidden debug flag of the plugin.
+    pub fn with_debug_json(mut self, target: DebugJson) -> Self {
+        self.debug_json = Some(target);
+        self
+    }
+
+    /// If set, the output explains for each metric the thresholds, the comparisons and why the
+    /// state was chosen. Meant to be wired to a flag like `--explain` of the plugin, for when it
+    /// isn't obvious why a check alerted.
+    pub fn with_explain(mut self, target: Explain) -> Self {
+        self.explain = Some(target);
+        self
+    }
+
+    /// Sets the verbosity given with `-v` as defined by the
+    /// [Monitoring Plugins guidelines](https://www.monitoring-plugins.org/doc/guidelines.html#AEN41).
+    /// From level 2 on, the long output contains a trace of the comparisons of each metric with
+    /// its thresholds, like `trace: metric 'load': value 83 vs warn 80: breach; vs crit 90: ok =>
+    /// WARNING`.
+    pub fn with_verbosity(mut self, level: u8) -> Self {
+        self.verbosity = level;
+        self
+    }
+
+    /// Returns a JSON dump of all results, metric values, thresholds and the states determined
+    /// from them. See [Self::with_debug_json].
+    pub fn debug_json(&self) -> String {
+        debug::to_json(self)
+    }
+
+    /// Calculates the state and message of this resource
+    pub fn nagios_result(self) -> (ServiceState, String) {
+        let summary = self.exit_summary();
+        (summary.state, summary.output)
+    }
+
+    /// Like [Self::nagios_result], but returns the parts of the output separately as well.
+    pub fn exit_summary(self) -> ExitSummary {
+        let max_perf_line_length = self.max_perf_line_length;
+        let evaluation = self.evaluate();
+
+        let result = match max_perf_line_length {
+            None => {
+                let mut result = evaluation.output();
+                if !evaluation.perf_strings.is_empty() {
+                    result.push('|');
+                    result.push_str(&join_perf_strings(&evaluation.perf_strings));
+                }
+                result
+            }
+            Some(length) => {
+                let mut lines = split_perf_strings(&evaluation.perf_strings, length).into_iter();
+
+                // With a message separator the messages stay on the summary line, so they have to
+                // come before the first chunk of performance data.
+                let inline_messages = evaluation.message_separator.is_some();
+                let mut result = if inline_messages {
+                    evaluation.output()
+                } else {
+                    evaluation.summary.clone()
+                };
+                if let Some(line) = lines.next() {
+                    result.push_str(" | ");
+                    result.push_str(&line);
+                }
+
+                if !inline_messages && !evaluation.messages.is_empty() {
+                    result.push_str(evaluation.messages_separator());
+                    result.push_str(&evaluation.long_output());
+                }
+
+                let remaining = lines.collect::<Vec<_>>();
+                if !remaining.is_empty() {
+                    if !result.ends_with('\n') {
+                        result.push('\n');
+                    }
+                    result.push_str("| ");
+                    result.push_str(&remaining.join("\n"));
+                }
+                result
+            }
+        };
+
+        ExitSummary {
+            state: evaluation.state,
+            summary: evaluation.summary,
+            long_output: evaluation.messages,
+            perf_strings: evaluation.perf_strings,
+            output: result,
+        }
+    }
+
+    /// Renders this resource as the JSON body expected by the Icinga 2 API for the
+    /// `process-check-result` action. The target (`type`, `filter` or the URL parameter) is left to
+    /// the caller.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use nagiosplugin::{Metric, Resource};
+    ///
+    /// let body = Resource::new("foo")
+    ///     .with_result(Metric::new("test", 15))
+    ///     .to_icinga_api_json();
+    ///
+    /// assert_eq!(
+    ///     body,
+    ///     r#"{"exit_status":0,"plugin_output":"foo is OK","performance_data":["'test'=15;;;;"]}"#
+    /// );
+    /// ```
+    pub fn to_icinga_api_json(self) -> String {
+        let evaluation = self.evaluate();
+        let perf_data = evaluation
+            .perf_strings
+            .iter()
+            .map(|s| json::quote(&s.to_string()))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        format!(
+            "{{\"exit_status\":{},\"plugin_output\":{},\"performance_data\":[{}]}}",
+            evaluation.state.exit_code(),
+            json::quote(&evaluation.output()),
+            perf_data
+        )
+    }
+
+    /// Calculates the state, the summary line, the messages and the performance data entries of
+    /// this resource.
+    fn evaluate(self) -> Evaluation {
+        let state = self.state();
+        let forced_state_note = self.forced_state_note();
+        let explanation = match self.explain {
+            Some(Explain::LongOutput) => explain::explain(&self),
+            _ => Vec::new(),
+        };
+        let trace = match (self.verbosity, self.perf_conflict_policy) {
+            (2.., Some(policy)) => explain::trace(&self)
+                .into_iter()
+                .chain(self.perf_conflicts.iter().map(|c| c.describe(policy)))
+                .collect(),
+            (2.., None) => explain::trace(&self),
+            _ => Vec::new(),
+        };
+        let mut messages = Vec::new();
+        let mut perf_strings = Vec::new();
+
+        for result in self.results {
+            if let Some(message) = result.message {
+                messages.push(self.html_mode.apply(message.trim()));
+            }
+            for detail in result.details {
+                messages.push(format!("  {}", self.html_mode.apply(detail.trim())));
+            }
+
+            perf_strings.extend(result.perf_strings);
+        }
+
+        if let Some(timestamp) = self.timestamp.as_ref().filter(|t| t.is_in_long_output()) {
+            messages.push(timestamp.render());
+        }
+        messages.extend(explanation);
+        messages.extend(trace);
+
+        let summary = {
+            let mut s = String::new();
+            if let Some(prefix) = self.output_prefix {
+                s.push_str(prefix.trim());
+                s.push(' ');
+            }
+            s.push_str(&self.summary_header.render(
+                &self.name,
+                state,
+                self.description.as_deref().map(str::trim),
+            ));
+            if let Some(note) = forced_state_note {
+                s.push_str(" (");
+                s.push_str(&note);
+                s.push(')');
+            }
+            if let Some(timestamp) = self.timestamp.as_ref().filter(|t| !t.is_in_long_output()) {
+                s.push_str(" (");
+                s.push_str(&timestamp.render());
+                s.push(')');
+            }
+            if let Some(suffix) = self.output_suffix {
+                s.push(' ');
+                s.push_str(suffix.trim());
+            }
+            self.html_mode.apply(&s)
+        };
+
+        Evaluation {
+            state,
+            summary,
+            messages,
+            message_separator: self.message_separator,
+            max_messages_length: self.max_messages_length,
+            perf_strings,
+        }
+    }
+
+    /// Returns the note about the fixed state if a reason was given.
+    fn forced_state_note(&self) -> Option<String> {
+        let state = self.fixed_state?;
+        let reason = self.fixed_state_reason.as_ref()?;
+        Some(forced_state_note(state, reason))
+    }
+
+    /// Returns the name of this resource.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the performance data entries of all results.
+    pub fn perf_strings(&self) -> impl Iterator<Item = &PerfString> {
+        self.results.iter().flat_map(|r| &r.perf_strings)
+    }
+
+    /// Returns the worst state of all results according to the [SeverityOrdering] or the fixed
+    /// state, if set. Without results the state set by [Self::with_empty_state] is returned.
+    pub fn state(&self) -> ServiceState {
+        self.fixed_state.unwrap_or_else(|| {
+            if self.results.is_empty() {
+                return self.empty_state;
+            }
+
+            self.results
+                .iter()
+                .filter_map(|r| r.state)
+                .fold(ServiceState::Ok, |a, b| self.severity_ordering.worst(a, b))
+        })
     }
 
     /// Calls [Self::nagios_result] and prints the result to stdout. It will also exit with the
     /// corresponding exit code based on the state.
     fn print_and_exit(self) -> ! {
-        let (state, s) = self.nagios_result();
+        std::process::exit(self.print().exit_code());
+    }
+
+    /// Prints the result to stdout like [Self::print_and_exit] and returns the state.
+    fn print(self) -> ServiceState {
+        if let Some(target) = &self.debug_json {
+            if let Err(err) = target.write(&self.debug_json()) {
+                eprintln!("failed to write debug dump: {}", err);
+            }
+        }
+        if self.explain == Some(Explain::Stderr) {
+            Explain::write_stderr(&explain::explain(&self));
+        }
+
+        let (state, s) = if self.terminal_preview && std::io::stdout().is_terminal() {
+            terminal::preview(self)
+        } else {
+            self.nagios_result()
+        };
         println!("{}", &s);
-        std::process::exit(state.exit_code());
+        state
+    }
+}
+
+/// The evaluated parts of a [Resource] which are needed to render the output.
+struct Evaluation {
+    state: ServiceState,
+    summary: String,
+    messages: Vec<String>,
+    /// The separator for messages, if they are appended to the summary line.
+    message_separator: Option<String>,
+    max_messages_length: Option<usize>,
+    perf_strings: Vec<PerfString>,
+}
+
+impl Evaluation {
+    /// The messages, each terminated by a newline, or joined by the message separator.
+    fn long_output(&self) -> String {
+        match &self.message_separator {
+            None => {
+                let output: String = self.messages.iter().map(|m| format!("{}\n", m)).collect();
+                match self.max_messages_length {
+                    Some(length) if output.len() > length => truncate(&output, length) + "\n",
+                    _ => output,
+                }
+            }
+            Some(separator) => {
+                let output = self.messages.join(separator);
+                match self.max_messages_length {
+                    Some(length) => truncate(&output, length),
+                    None => output,
+                }
+            }
+        }
+    }
+
+    /// What is put between the summary and the long output.
+    fn messages_separator(&self) -> &str {
+        self.message_separator.as_deref().unwrap_or("\n\n")
+    }
+
+    /// The summary and the long output, without performance data.
+    fn output(&self) -> String {
+        let mut output = self.summary.clone();
+        if !self.messages.is_empty() {
+            output.push_str(self.messages_separator());
+            output.push_str(&self.long_output());
+        }
+        output
+    }
+}
+
+fn forced_state_note(state: ServiceState, reason: &str) -> String {
+    format!("state forced to {}: {}", state, reason.trim())
+}
+
+/// Replaces the `{key}` placeholders in the template. Unknown placeholders are kept as they are.
+fn fill_message_template(template: &str, values: &[(&str, String)]) -> String {
+    let mut message = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        message.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find('}').and_then(|end| {
+            let (_, value) = values.iter().find(|(key, _)| *key == &rest[1..end])?;
+            Some((value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                message.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                message.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    message.push_str(rest);
+    message
+}
+
+/// Truncates the string to at most `length` bytes including the `...` marker, respecting char
+/// boundaries.
+fn truncate(s: &str, length: usize) -> String {
+    const MARKER: &str = "...";
+    if s.len() <= length {
+        return s.to_owned();
+    }
+
+    let mut end = length.saturating_sub(MARKER.len());
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", &s[..end], MARKER)
+}
+
+/// Packs the entries into lines of at most `length` characters.
+fn split_perf_strings(perf_strings: &[PerfString], length: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for s in perf_strings.iter().map(|s| s.to_string()) {
+        match lines.last_mut() {
+            Some(line) if line.len() + 1 + s.len() <= length => {
+                line.push(' ');
+                line.push_str(&s);
+            }
+            _ => lines.push(s),
+        }
     }
+    lines
+}
+
+fn join_perf_strings(perf_strings: &[PerfString]) -> String {
+    perf_strings
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 /// Helper function to safely run a check with a defined [ServiceState] on error and return a [RunResult] which can be used to print and exit.
 ///
+/// The error can be of any type. Use a [Runner] for timeouts, logging and the other options, which
+/// need errors implementing [fmt::Display].
+///
 /// ## Example
 ///
 /// ```no_run
@@ -623,8 +2057,21 @@ pub fn safe_run<E>(
     }
 }
 
+/// Like [safe_run], for checks which are futures. See [Runner::safe_run_async].
+pub async fn safe_run_async<E: fmt::Display, F: Future<Output = Result<Resource, E>>>(
+    f: impl FnOnce() -> F,
+    error_state: ServiceState,
+) -> RunResult<E> {
+    Runner::new()
+        .with_error_state(error_state)
+        .safe_run_async(f)
+        .await
+}
+
 /// The result of a runner execution.
 #[derive(Debug)]
+// Only a single instance exists per run, boxing the resource isn't worth the API change.
+#[allow(clippy::large_enum_variant)]
 pub enum RunResult<E> {
     /// The run was successful and it contains the returned [Resource].
     Ok(Resource),
@@ -634,20 +2081,142 @@ pub enum RunResult<E> {
 
 impl<E: std::fmt::Display> RunResult<E> {
     pub fn print_and_exit(self) -> ! {
+        std::process::exit(self.print().exit_code());
+    }
+
+    fn print(self) -> ServiceState {
         match self {
-            RunResult::Ok(resource) => resource.print_and_exit(),
+            RunResult::Ok(resource) => resource.print(),
             RunResult::Err(state, msg) => {
                 println!("{}: {}", state, msg);
-                std::process::exit(state.exit_code());
+                state
             }
         }
     }
 }
 
+/// Allows to return the result from `main`, which prints it and exits with the exit code of the
+/// state, like [RunResult::print_and_exit].
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::error::Error;
+///
+/// use nagiosplugin::{safe_run, Resource, RunResult, ServiceState};
+///
+/// fn main() -> RunResult<Box<dyn Error>> {
+///     safe_run(do_check, ServiceState::Critical)
+/// }
+///
+/// fn do_check() -> Result<Resource, Box<dyn Error>> {
+///     Ok(Resource::new("foo"))
+/// }
+/// ```
+impl<E: std::fmt::Display> Termination for RunResult<E> {
+    fn report(self) -> ExitCode {
+        self.print().into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_check_result_multiple_perf_data() {
+        let (_, output) = Resource::new("http")
+            .with_result(
+                CheckResult::new()
+                    .with_message("request ok")
+                    .with_perf_data(PerfData::new("time", 0.25).with_unit(Unit::Seconds))
+                    .with_perf_data(PerfData::new("size", 1024).with_unit(Unit::Bytes)),
+            )
+            .nagios_result();
+
+        assert!(output.ends_with("|'time'=0.25s;;;; 'size'=1024B;;;;"));
+    }
+
+    #[test]
+    fn test_check_result_from_result() {
+        let result = CheckResult::from_result("a", Ok::<_, String>(Metric::new("a", 1)));
+        assert_eq!(result.state, None);
+        assert_eq!(result.perf_strings.len(), 1);
+
+        let result = CheckResult::from_result_with_error_state(
+            "b",
+            Err::<CheckResult, _>("refused"),
+            ServiceState::Critical,
+        );
+        assert_eq!(result.state, Some(ServiceState::Critical));
+        assert_eq!(result.message.as_deref(), Some("b: refused"));
+    }
+
+    #[test]
+    fn test_service_state_exit_code() {
+        for state in [
+            ServiceState::Ok,
+            ServiceState::Warning,
+            ServiceState::Critical,
+            ServiceState::Unknown,
+        ] {
+            assert_eq!(ServiceState::from_exit_code(state.exit_code()), Some(state));
+            assert_eq!(
+                ExitCode::from(state),
+                ExitCode::from(state.exit_code() as u8)
+            );
+        }
+        assert_eq!(ServiceState::from_exit_code(4), None);
+        assert_eq!(ServiceState::from_exit_code(-1), None);
+    }
+
+    #[test]
+    fn test_run_result_report() {
+        let result = RunResult::Err(ServiceState::Warning, "woops");
+        assert_eq!(result.report(), ExitCode::from(1));
+
+        let result =
+            RunResult::<&str>::Ok(Resource::new("foo").with_fixed_state(ServiceState::Critical));
+        assert_eq!(result.report(), ExitCode::from(2));
+    }
+
+    #[test]
+    fn test_check_result_details() {
+        let (_, output) = Resource::new("raid")
+            .with_result(
+                CheckResult::new()
+                    .with_state(ServiceState::Critical)
+                    .with_message("2 disks failed")
+                    .with_detail("sda: failed")
+                    .with_detail("sdc: failed"),
+            )
+            .nagios_result();
+
+        assert_eq!(
+            output,
+            "raid is CRITICAL\n\n2 disks failed\n  sda: failed\n  sdc: failed\n"
+        );
+    }
+
+    #[test]
+    fn test_resource_from_metrics() {
+        let resource = Resource::from_metrics(
+            "disk",
+            [("/", 42), ("/var", 93)]
+                .into_iter()
+                .map(|(m, v)| Metric::new(m, v).with_thresholds(80, 90, TriggerIfValue::Greater)),
+        );
+
+        assert_eq!(resource.state(), ServiceState::Critical);
+        assert_eq!(resource.perf_strings().count(), 2);
+
+        let resource = Resource::from_results(
+            "foo",
+            vec![CheckResult::new().with_state(ServiceState::Warning)],
+        );
+        assert_eq!(resource.state(), ServiceState::Warning);
+    }
+
     #[test]
     fn test_resource_nagios_result() {
         let (state, s) = Resource::new("foo")
@@ -674,6 +2243,114 @@ mod tests {
         assert_eq!(state, ServiceState::Critical);
     }
 
+    #[test]
+    fn test_fixed_state_reason() {
+        let (state, output) = Resource::new("foo")
+            .with_description("all good")
+            .with_fixed_state_reason(ServiceState::Ok, "maintenance override via --force-ok")
+            .with_result(
+                Metric::new("a", 100)
+                    .with_thresholds(40, 50, TriggerIfValue::Greater)
+                    .with_fixed_state_reason(ServiceState::Warning, "known issue"),
+            )
+            .with_result(Metric::new("b", 1).with_fixed_state(ServiceState::Critical))
+            .nagios_result();
+
+        assert_eq!(state, ServiceState::Ok);
+        assert!(output.starts_with(
+            "foo is OK: all good (state forced to OK: maintenance override via --force-ok)\n"
+        ));
+        assert!(output.contains("metric 'a' is WARNING: state forced to WARNING: known issue\n"));
+        assert!(output.contains("metric 'b' is CRITICAL\n"));
+    }
+
+    #[test]
+    fn test_resource_with_empty_state() {
+        assert_eq!(Resource::new("foo").state(), ServiceState::Ok);
+
+        let resource = Resource::new("foo").with_empty_state(ServiceState::Unknown);
+        assert_eq!(resource.state(), ServiceState::Unknown);
+
+        let resource = resource.with_result(Metric::new("a", 1));
+        assert_eq!(resource.state(), ServiceState::Ok);
+    }
+
+    #[test]
+    fn test_output_prefix_suffix() {
+        let (_, output) = Resource::new("foo")
+            .with_description("all good")
+            .with_output_prefix("[prod]")
+            .with_output_suffix("(dc1)")
+            .with_result(CheckResult::new().with_message("bar"))
+            .nagios_result();
+
+        assert_eq!(output, "[prod] foo is OK: all good (dc1)\n\nbar\n");
+    }
+
+    #[test]
+    fn test_summary_header() {
+        let resource = || {
+            Resource::new("disk")
+                .with_description("93% used")
+                .with_result(CheckResult::new().with_state(ServiceState::Warning))
+        };
+
+        let (_, output) = resource()
+            .with_summary_header(SummaryHeader::State)
+            .nagios_result();
+        assert_eq!(output, "WARNING: 93% used");
+
+        let (state, output) = resource()
+            .with_summary_header(SummaryHeader::None)
+            .nagios_result();
+        assert_eq!(state, ServiceState::Warning);
+        assert_eq!(output, "93% used");
+    }
+
+    #[test]
+    fn test_timestamp() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let time = UNIX_EPOCH + Duration::from_secs(1_715_934_600);
+
+        let (_, output) = Resource::new("foo")
+            .with_timestamp(OutputTimestamp::new(time))
+            .nagios_result();
+        assert_eq!(output, "foo is OK (measured at 2024-05-17T08:30:00Z)");
+
+        let (_, output) = Resource::new("foo")
+            .with_result(CheckResult::new().with_message("bar"))
+            .with_timestamp(
+                OutputTimestamp::new(time)
+                    .with_format(TimestampFormat::Epoch)
+                    .in_long_output(),
+            )
+            .nagios_result();
+        assert_eq!(output, "foo is OK\n\nbar\nmeasured at 1715934600\n");
+    }
+
+    #[test]
+    fn test_message_separator() {
+        let resource = || {
+            Resource::new("foo")
+                .with_result(CheckResult::new().with_message("first message"))
+                .with_result(CheckResult::new().with_message("second message"))
+                .with_result(Metric::new("a", 1))
+        };
+
+        let (_, output) = resource().with_message_separator(", ").nagios_result();
+        assert_eq!(output, "foo is OK, first message, second message|'a'=1;;;;");
+
+        let (_, output) = resource()
+            .with_message_separator(", ")
+            .with_max_messages_length(20)
+            .nagios_result();
+        assert_eq!(output, "foo is OK, first message, se...|'a'=1;;;;");
+
+        let (_, output) = resource().with_max_messages_length(20).nagios_result();
+        assert_eq!(output, "foo is OK\n\nfirst message\nsec...\n|'a'=1;;;;");
+    }
+
     #[test]
     fn test_resource_with_ok_result() {
         let (state, msg) = Resource::new("foo")
@@ -688,10 +2365,232 @@ mod tests {
         assert!(msg.contains("test"));
     }
 
+    #[test]
+    fn test_resource_with_max_perf_line_length() {
+        let resource = Resource::new("foo")
+            .with_max_perf_line_length(20)
+            .with_result(Metric::new("a", 1))
+            .with_result(Metric::new("b", 2))
+            .with_result(Metric::new("c", 3).with_thresholds(2, 5, TriggerIfValue::Greater));
+
+        let (_, s) = resource.nagios_result();
+        assert_eq!(
+            s,
+            "foo is WARNING | 'a'=1;;;; 'b'=2;;;;\n\n\
+             metric 'c' is WARNING: value '3' has exceeded threshold of '2'\n\
+             | 'c'=3;2;5;;"
+        );
+
+        let (_, s) = Resource::new("foo")
+            .with_max_perf_line_length(10)
+            .with_result(Metric::new("a", 1))
+            .with_result(Metric::new("b", 2))
+            .with_result(Metric::new("c", 3))
+            .nagios_result();
+        assert_eq!(s, "foo is OK | 'a'=1;;;;\n| 'b'=2;;;;\n'c'=3;;;;");
+
+        let (_, s) = Resource::new("foo")
+            .with_message_separator(", ")
+            .with_max_perf_line_length(20)
+            .with_result(Metric::new("a", 1))
+            .with_result(Metric::new("b", 2))
+            .with_result(Metric::new("c", 3).with_thresholds(2, 5, TriggerIfValue::Greater))
+            .nagios_result();
+        assert_eq!(
+            s,
+            "foo is WARNING, metric 'c' is WARNING: value '3' has exceeded threshold of '2' \
+             | 'a'=1;;;; 'b'=2;;;;\n| 'c'=3;2;5;;"
+        );
+    }
+
+    #[test]
+    fn test_resource_with_html_mode() {
+        let (_, s) = Resource::new("foo")
+            .with_html_mode(HtmlMode::Escape)
+            .with_description("<b>")
+            .with_result(CheckResult::new().with_message("a & b"))
+            .nagios_result();
+
+        assert_eq!(s, "foo is OK: &lt;b&gt;\n\na &amp; b\n");
+    }
+
     #[test]
     fn test_perf_string_new() {
         let s = PerfString::new("foo", &12, Unit::None, Some(&42), None, None, Some(&60));
-        assert_eq!(&s.0, "'foo'=12;42;;;60")
+        assert_eq!(s.to_string(), "'foo'=12;42;;;60")
+    }
+
+    #[test]
+    fn test_perf_data_macro() {
+        let s: PerfString = perf_data!("rta" => 0.5 ms, warn 1.0, crit 2.0, min 0.0).into();
+        assert_eq!(s.to_string(), "'rta'=0.5ms;1;2;0;");
+
+        let value = 12;
+        let s: PerfString = perf_data!("foo" => value, max 60).into();
+        assert_eq!(s.to_string(), "'foo'=12;;;;60");
+
+        let s: PerfString = perf_data!("in" => 1500 bps, max 10000).into();
+        assert_eq!(s.to_string(), "'in'=1500bps;;;;10000");
+
+        let s: PerfString = perf_data!("rta" => 0.123 ms, warn 100, crit 500).into();
+        assert_eq!(s.to_string(), "'rta'=0.123ms;100;500;;");
+
+        let (warning, max) = (80_u64, 100);
+        let s: PerfString = perf_data!("disk" => 93_u64 %, warn warning, crit 90, max max).into();
+        assert_eq!(s.to_string(), "'disk'=93%;80;90;;100");
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn test_decimal_metric() {
+        use rust_decimal::Decimal;
+
+        let result: CheckResult = Metric::new("price", Decimal::new(1_000_000_001, 9))
+            .with_thresholds(Decimal::ONE, None, TriggerIfValue::Greater)
+            .into();
+
+        assert_eq!(result.state, Some(ServiceState::Warning));
+        assert_eq!(
+            result.perf_strings[0].to_string(),
+            "'price'=1.000000001;1;;;"
+        );
+    }
+
+    #[test]
+    fn test_non_finite_values() {
+        let result: CheckResult = Metric::new("a", f64::NAN)
+            .with_unit(Unit::Seconds)
+            .with_thresholds(1.0, f64::INFINITY, TriggerIfValue::Greater)
+            .into();
+        assert_eq!(result.state, Some(ServiceState::Unknown));
+        assert_eq!(
+            result.message.as_deref(),
+            Some("metric 'a' is UNKNOWN: value is not a number")
+        );
+        assert_eq!(result.perf_strings[0].to_string(), "'a'=U;1;;;");
+
+        let result: CheckResult = Metric::new("a", f64::NAN)
+            .with_nan_policy(NanPolicy::Skip)
+            .into();
+        assert_eq!(result.state, None);
+
+        let result: CheckResult = Metric::new("a", f64::INFINITY)
+            .with_maximum(100.0)
+            .with_thresholds(80.0, 90.0, TriggerIfValue::Greater)
+            .into();
+        assert_eq!(result.state, Some(ServiceState::Critical));
+        assert_eq!(result.perf_strings[0].to_string(), "'a'=100;80;90;;100");
+    }
+
+    #[test]
+    fn test_metric_validation() {
+        let result: CheckResult = Metric::new("a", -5)
+            .with_unit(Unit::Bytes)
+            .with_thresholds(10, 20, TriggerIfValue::Less)
+            .with_validation()
+            .into();
+        assert_eq!(result.state, Some(ServiceState::Unknown));
+        assert_eq!(
+            result.message.as_deref(),
+            Some("metric 'a' is UNKNOWN: value '-5' is outside of the valid range '0:'")
+        );
+
+        let result: CheckResult = Metric::new("a", 120.0)
+            .with_unit(Unit::Percentage)
+            .with_validation()
+            .into();
+        assert_eq!(result.state, Some(ServiceState::Unknown));
+
+        let result: CheckResult = Metric::new("a", 120.0)
+            .with_unit(Unit::Percentage)
+            .with_valid_range(0.0, None)
+            .into();
+        assert_eq!(result.state, None);
+    }
+
+    #[test]
+    fn test_metric_with_total() {
+        let result: CheckResult = Metric::new("mem", 3)
+            .with_total(4)
+            .with_thresholds(2, 3, TriggerIfValue::Greater)
+            .into();
+        assert_eq!(result.state, Some(ServiceState::Critical));
+        let perf_data: Vec<_> = result.perf_strings.iter().map(|p| p.to_string()).collect();
+        assert_eq!(
+            perf_data,
+            [
+                "'mem_used'=3;2;3;;4",
+                "'mem_total'=4;;;;",
+                "'mem_used_pct'=75%;;;0;100"
+            ]
+        );
+
+        let result: CheckResult = Metric::new("mem", 0).with_total(0).into();
+        assert_eq!(result.perf_strings.len(), 2);
+    }
+
+    #[test]
+    fn test_metric_message_humanized() {
+        let result: CheckResult = Metric::new("traffic", 2_345_678)
+            .with_unit(Unit::BitsPerSecond)
+            .with_thresholds(1_000_000, None, TriggerIfValue::Greater)
+            .into();
+
+        assert_eq!(
+            result.message.as_deref(),
+            Some("metric 'traffic' is WARNING: value '2.35Mbps' has exceeded threshold of '1Mbps'")
+        );
+        assert_eq!(
+            result.perf_strings[0].to_string(),
+            "'traffic'=2345678bps;1000000;;;"
+        );
+    }
+
+    #[test]
+    fn test_metric_message_template() {
+        let result: CheckResult = Metric::new("temp", 71)
+            .with_thresholds(60, 70, TriggerIfValue::Greater)
+            .with_message_template("{name} at {value} over {threshold} ({state}) {other}")
+            .into();
+
+        assert_eq!(
+            result.message.as_deref(),
+            Some("temp at 71 over 70 (CRITICAL) {other}")
+        );
+    }
+
+    #[test]
+    fn test_metric_threshold_ranges() {
+        let metric = |value| {
+            Metric::new("temp", value).with_threshold_ranges(
+                ThresholdRange::outside(10, 20),
+                ThresholdRange::inside(None, 5),
+            )
+        };
+
+        let result: CheckResult = metric(15).into();
+        assert_eq!(result.state, None);
+        assert_eq!(result.perf_strings[0].to_string(), "'temp'=15;10:20;@~:5;;");
+
+        let result: CheckResult = metric(25).into();
+        assert_eq!(result.state, Some(ServiceState::Warning));
+        assert_eq!(
+            result.message.as_deref(),
+            Some("metric 'temp' is WARNING: value '25' is outside the range '10:20'")
+        );
+
+        let result: CheckResult = metric(-3).into();
+        assert_eq!(result.state, Some(ServiceState::Critical));
+        assert_eq!(
+            result.message.as_deref(),
+            Some("metric 'temp' is CRITICAL: value '-3' is inside the range '@~:5'")
+        );
+    }
+
+    #[test]
+    fn test_unit_macro() {
+        assert_eq!(unit!("km").as_str(), "km");
+        assert!(UnitString::new("km2").is_err());
     }
 
     #[test]
@@ -759,7 +2658,7 @@ mod tests {
             .with_unit(Unit::Megabytes)
             .into();
 
-        result.perf_string.unwrap().0.contains("MB");
+        result.perf_strings[0].to_string().contains("MB");
 
         assert_eq!(result.state, None);
     }
@@ -788,5 +2687,13 @@ mod tests {
         let result = safe_run(|| do_check(false), ServiceState::Critical);
 
         matches!(result, RunResult::Err(_, _));
+
+        // Errors don't need to implement Display.
+        struct Opaque;
+        let result = safe_run(|| Err::<Resource, _>(Opaque), ServiceState::Warning);
+        assert!(matches!(
+            result,
+            RunResult::Err(ServiceState::Warning, Opaque)
+        ));
     }
 }
@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+
+/// Expands Nagios style macros like `$HOSTNAME$`, `$SERVICEDESC$` or `$ARG1$` in strings, e.g.
+/// in message templates. Macros are resolved from the given values and, if enabled, from the
+/// environment variables the scheduler exports, like `NAGIOS_HOSTNAME` or `ICINGA_HOSTNAME`.
+/// `$$` is an escaped `$`, unknown macros are kept as they are.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::MacroExpander;
+///
+/// let expander = MacroExpander::new()
+///     .with_macro("HOSTNAME", "db1")
+///     .with_args(["/var/lib/mysql"]);
+///
+/// assert_eq!(
+///     expander.expand("disk $ARG1$ on $HOSTNAME$ costs $$5, $UNKNOWN$"),
+///     "disk /var/lib/mysql on db1 costs $5, $UNKNOWN$"
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MacroExpander {
+    macros: BTreeMap<String, String>,
+    environment: bool,
+}
+
+/// The prefixes of the environment variables macros are exported as.
+const ENV_PREFIXES: [&str; 2] = ["NAGIOS_", "ICINGA_"];
+
+impl MacroExpander {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value of a macro, e.g. `HOSTNAME`, without the `$`.
+    pub fn with_macro(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.macros.insert(name.into(), value.into());
+        self
+    }
+
+    /// Sets `$ARG1$`, `$ARG2$` and so on to the given values.
+    pub fn with_args(mut self, args: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        for (i, arg) in args.into_iter().enumerate() {
+            self.macros.insert(format!("ARG{}", i + 1), arg.into());
+        }
+        self
+    }
+
+    /// Resolves macros without a given value from the environment variables `NAGIOS_<NAME>` and
+    /// `ICINGA_<NAME>`.
+    pub fn with_environment(mut self) -> Self {
+        self.environment = true;
+        self
+    }
+
+    fn resolve(&self, name: &str) -> Option<String> {
+        if let Some(value) = self.macros.get(name) {
+            return Some(value.clone());
+        }
+        if !self.environment {
+            return None;
+        }
+        ENV_PREFIXES
+            .iter()
+            .find_map(|prefix| std::env::var(format!("{}{}", prefix, name)).ok())
+    }
+
+    /// Replaces all known macros in the given string.
+    pub fn expand(&self, s: &str) -> String {
+        let mut output = String::with_capacity(s.len());
+        let mut rest = s;
+
+        while let Some(start) = rest.find('$') {
+            output.push_str(&rest[..start]);
+            rest = &rest[start + 1..];
+
+            let Some(end) = rest.find('$') else {
+                output.push('$');
+                break;
+            };
+            let name = &rest[..end];
+            let is_macro = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+
+            if name.is_empty() {
+                output.push('$');
+                rest = &rest[1..];
+            } else if let Some(value) = is_macro.then(|| self.resolve(name)).flatten() {
+                output.push_str(&value);
+                rest = &rest[end + 1..];
+            } else if is_macro {
+                output.push('$');
+                output.push_str(&rest[..=end]);
+                rest = &rest[end + 1..];
+            } else {
+                // Not a macro, the closing `$` may start the next one.
+                output.push('$');
+            }
+        }
+        output.push_str(rest);
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let expander = MacroExpander::new()
+            .with_macro("HOSTNAME", "web1")
+            .with_args(["a", "b"]);
+
+        assert_eq!(expander.expand("$HOSTNAME$/$ARG2$"), "web1/b");
+        assert_eq!(expander.expand("$ARG3$"), "$ARG3$");
+        assert_eq!(expander.expand("5$ and $ARG1$"), "5$ and a");
+        assert_eq!(expander.expand("$$HOSTNAME$$"), "$HOSTNAME$");
+        assert_eq!(expander.expand("trailing $"), "trailing $");
+    }
+}
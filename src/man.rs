@@ -0,0 +1,160 @@
+//! Man pages for plugins which parse their arguments with [clap], laid out like the
+//! [Nagios Plugin Development Guidelines](https://nagios-plugins.org/doc/guidelines.html)
+//! recommend: besides the options, the page explains the threshold syntax and the exit codes and
+//! lists examples.
+//!
+//! ## Example
+//!
+//! ```
+//! use clap::{Arg, Command};
+//! use nagiosplugin::man::ManPage;
+//!
+//! let cmd = Command::new("check_disk")
+//!     .about("Checks the usage of a disk")
+//!     .arg(Arg::new("warning").short('w').help("Warning threshold"));
+//! let mut page = Vec::new();
+//! ManPage::new(cmd)
+//!     .with_example("check_disk -w 80 -c 90", "Warns above 80% and is critical above 90%.")
+//!     .write(&mut page)
+//!     .unwrap();
+//! ```
+
+use std::io;
+use std::io::Write;
+
+use clap::Command;
+use clap_mangen::roff::{bold, roman, Roff};
+use clap_mangen::Man;
+
+use crate::ServiceState;
+
+/// The ranges of the guidelines and when they raise an alert.
+const RANGES: [(&str, &str); 5] = [
+    ("10", "Alert if the value is < 0 or > 10."),
+    ("10:", "Alert if the value is < 10."),
+    (
+        "~:10",
+        "Alert if the value is > 10, there is no lower bound.",
+    ),
+    ("10:20", "Alert if the value is < 10 or > 20."),
+    ("@10:20", "Alert if the value is >= 10 and <= 20."),
+];
+
+/// Builds the man page of a plugin from its [Command].
+#[derive(Debug, Clone)]
+pub struct ManPage {
+    cmd: Command,
+    examples: Vec<(String, String)>,
+}
+
+impl ManPage {
+    pub fn new(cmd: Command) -> Self {
+        Self {
+            cmd,
+            examples: Vec::new(),
+        }
+    }
+
+    /// Adds an example invocation with a description to the EXAMPLES section.
+    pub fn with_example(
+        mut self,
+        command: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        self.examples.push((command.into(), description.into()));
+        self
+    }
+
+    /// Writes the man page in roff format.
+    pub fn write(&self, out: &mut dyn Write) -> io::Result<()> {
+        let man = Man::new(self.cmd.clone());
+        man.render_title(out)?;
+        man.render_name_section(out)?;
+        man.render_synopsis_section(out)?;
+        man.render_description_section(out)?;
+        if self.cmd.get_arguments().any(|arg| !arg.is_hide_set()) {
+            man.render_options_section(out)?;
+        }
+
+        let mut roff = Roff::new();
+        roff.control("SH", ["THRESHOLDS"]);
+        roff.text([roman(
+            "Thresholds are ranges in the format [@]start:end. start is 0 if omitted and ~ \
+             stands for negative infinity. An alert is raised if the value is outside of the \
+             range, or inside of it if the range starts with @.",
+        )]);
+        for (range, description) in RANGES {
+            roff.control("TP", []);
+            roff.text([bold(range)]);
+            roff.text([roman(description)]);
+        }
+
+        roff.control("SH", ["EXIT STATUS"]);
+        for state in [
+            ServiceState::Ok,
+            ServiceState::Warning,
+            ServiceState::Critical,
+            ServiceState::Unknown,
+        ] {
+            roff.control("TP", []);
+            roff.text([bold(state.exit_code().to_string())]);
+            roff.text([roman(state.to_string())]);
+        }
+
+        if !self.examples.is_empty() {
+            roff.control("SH", ["EXAMPLES"]);
+            for (command, description) in &self.examples {
+                roff.control("TP", []);
+                roff.text([bold(command)]);
+                roff.text([roman(description)]);
+            }
+        }
+        roff.to_writer(out)?;
+
+        if self.cmd.get_version().is_some() {
+            man.render_version_section(out)?;
+        }
+        if self.cmd.get_author().is_some() {
+            man.render_authors_section(out)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Arg;
+
+    #[test]
+    fn test_write() {
+        let cmd = Command::new("check_foo")
+            .version("1.0.0")
+            .arg(Arg::new("warning").short('w').help("Warning threshold"));
+        let mut out = Vec::new();
+        ManPage::new(cmd)
+            .with_example("check_foo -w 10:", "Warns below 10.")
+            .write(&mut out)
+            .unwrap();
+        let page = String::from_utf8(out).unwrap();
+
+        let sections: Vec<_> = page
+            .lines()
+            .filter_map(|line| line.strip_prefix(".SH "))
+            .collect();
+        assert_eq!(
+            sections,
+            [
+                "NAME",
+                "SYNOPSIS",
+                "DESCRIPTION",
+                "OPTIONS",
+                "THRESHOLDS",
+                "\"EXIT STATUS\"",
+                "EXAMPLES",
+                "VERSION"
+            ]
+        );
+        assert!(page.contains("check_foo \\-w 10:"));
+    }
+}
@@ -0,0 +1,125 @@
+use crate::args;
+use crate::pattern::{Pattern, PatternError};
+use crate::CheckResult;
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+/// This error is returned by [MetricFilter::from_args].
+pub enum MetricFilterError {
+    #[error("missing value for {0}")]
+    MissingValue(String),
+    #[error(transparent)]
+    InvalidPattern(#[from] PatternError),
+}
+
+/// Selects metrics by name with [Pattern]s, e.g. for inventory checks which discover their items
+/// dynamically. If include patterns are given, a metric has to match one of them, and it must not
+/// match any exclude pattern. Set it with [crate::Resource::with_metric_filter] to drop the results
+/// of other metrics before the state is determined. Results without a metric are always kept.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{Metric, MetricFilter, Resource};
+///
+/// let filter = MetricFilter::from_args(["--include", "^eth", "--exclude=_test$"]).unwrap();
+/// let resource = Resource::new("interfaces")
+///     .with_metric_filter(filter)
+///     .with_result(Metric::new("eth0", 1))
+///     .with_result(Metric::new("eth0_test", 2))
+///     .with_result(Metric::new("lo", 3));
+///
+/// let (_, output) = resource.nagios_result();
+/// assert_eq!(output, "interfaces is OK|'eth0'=1;;;;");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetricFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl MetricFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the filter from the given arguments. Other arguments are ignored, so they can be
+    /// handled by the plugin itself.
+    ///
+    /// | Flag                  | Option                       |
+    /// |-----------------------|------------------------------|
+    /// | `--include <PATTERN>` | [MetricFilter::with_include] |
+    /// | `--exclude <PATTERN>` | [MetricFilter::with_exclude] |
+    ///
+    /// Both can be repeated. Values are either the next argument or separated by `=`. Arguments
+    /// after `--` and the values of the standard plugin options like `-w` are skipped, as in
+    /// [crate::net::NetOptions::from_args].
+    pub fn from_args<I, S>(args: I) -> Result<Self, MetricFilterError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let options = args::Options {
+            values: &["--include", "--exclude"],
+            ..Default::default()
+        };
+        let found = args::scan(args, options).map_err(MetricFilterError::MissingValue)?;
+
+        let mut filter = Self::new();
+        for (key, value) in found {
+            let pattern = value.unwrap_or_default().parse()?;
+            match key.as_str() {
+                "--include" => filter.include.push(pattern),
+                _ => filter.exclude.push(pattern),
+            }
+        }
+
+        Ok(filter)
+    }
+
+    pub fn with_include(mut self, pattern: Pattern) -> Self {
+        self.include.push(pattern);
+        self
+    }
+
+    /// Drops metrics matching the pattern. Takes precedence over [MetricFilter::with_include].
+    pub fn with_exclude(mut self, pattern: Pattern) -> Self {
+        self.exclude.push(pattern);
+        self
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| p.is_match(name)))
+            && !self.exclude.iter().any(|p| p.is_match(name))
+    }
+
+    /// Returns whether the result is kept. Results are identified by the name of their metric or
+    /// else by their first performance data label.
+    pub(crate) fn keeps(&self, result: &CheckResult) -> bool {
+        let name = match (&result.evaluation, result.perf_strings.first()) {
+            (Some(evaluation), _) => &evaluation.name,
+            (None, Some(perf_string)) => &perf_string.label,
+            (None, None) => return true,
+        };
+        self.matches(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let filter = MetricFilter::from_args(["-w", "1", "--exclude", "^sda"]).unwrap();
+        assert!(filter.matches("sdb"));
+        assert!(!filter.matches("sda1"));
+
+        let filter = filter.with_include("^sd".parse().unwrap());
+        assert!(filter.matches("sdb"));
+        assert!(!filter.matches("nvme0n1"));
+
+        assert!(MetricFilter::from_args(["--include"]).is_err());
+        assert!(MetricFilter::from_args(["--include=("]).is_err());
+    }
+}
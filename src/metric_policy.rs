@@ -0,0 +1,137 @@
+use std::collections::BTreeSet;
+
+use crate::{args, Metric, ServiceState};
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+/// This error is returned by [MetricPolicy::from_args].
+pub enum MetricPolicyError {
+    #[error("missing value for {0}")]
+    MissingValue(String),
+}
+
+/// Per-metric overrides given by the operator, so a known-bad metric can be silenced without
+/// changing the plugin or setting a downtime for the whole service. Ignored metrics are dropped
+/// including their performance data, metrics forced to OK keep their performance data.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{Metric, MetricPolicy, Resource, ServiceState, TriggerIfValue};
+///
+/// let policy = MetricPolicy::from_args(["--force-ok", "sdb", "--ignore-metric=sdc"]).unwrap();
+/// let metrics = ["sda", "sdb", "sdc"]
+///     .into_iter()
+///     .map(|disk| Metric::new(disk, 95).with_thresholds(80, 90, TriggerIfValue::Greater));
+///
+/// let resource = Resource::from_metrics("disks", policy.filter(metrics));
+/// let (state, output) = resource.nagios_result();
+/// assert_eq!(state, ServiceState::Critical);
+/// assert!(output.contains("'sdb'=95"));
+/// assert!(!output.contains("sdc"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetricPolicy {
+    ignored: BTreeSet<String>,
+    forced_ok: BTreeSet<String>,
+}
+
+impl MetricPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the policy from the given arguments. Other arguments are ignored, so they can be
+    /// handled by the plugin itself.
+    ///
+    /// | Flag                     | Option                         |
+    /// |--------------------------|--------------------------------|
+    /// | `--ignore-metric <NAME>` | [MetricPolicy::with_ignored]   |
+    /// | `--force-ok <NAME>`      | [MetricPolicy::with_forced_ok] |
+    ///
+    /// Both can be repeated. Values are either the next argument or separated by `=`. Arguments
+    /// after `--` and the values of the standard plugin options like `-w` are skipped, as in
+    /// [crate::net::NetOptions::from_args].
+    pub fn from_args<I, S>(args: I) -> Result<Self, MetricPolicyError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let options = args::Options {
+            values: &["--ignore-metric", "--force-ok"],
+            ..Default::default()
+        };
+        let found = args::scan(args, options).map_err(MetricPolicyError::MissingValue)?;
+
+        let mut policy = Self::new();
+        for (key, value) in found {
+            let value = value.unwrap_or_default();
+            match key.as_str() {
+                "--ignore-metric" => policy.push_ignored(value),
+                _ => policy.push_forced_ok(value),
+            }
+        }
+
+        Ok(policy)
+    }
+
+    /// Drops the metric with the given name.
+    pub fn with_ignored(mut self, name: impl Into<String>) -> Self {
+        self.push_ignored(name);
+        self
+    }
+
+    pub fn push_ignored(&mut self, name: impl Into<String>) {
+        self.ignored.insert(name.into());
+    }
+
+    /// Forces the state of the metric with the given name to [ServiceState::Ok].
+    pub fn with_forced_ok(mut self, name: impl Into<String>) -> Self {
+        self.push_forced_ok(name);
+        self
+    }
+
+    pub fn push_forced_ok(&mut self, name: impl Into<String>) {
+        self.forced_ok.insert(name.into());
+    }
+
+    /// Returns `None` if the metric is ignored, otherwise the metric with the overrides applied.
+    pub fn apply<T>(&self, metric: Metric<T>) -> Option<Metric<T>> {
+        if self.ignored.contains(&metric.name) {
+            None
+        } else if self.forced_ok.contains(&metric.name) {
+            Some(metric.with_fixed_state_reason(ServiceState::Ok, "overridden via --force-ok"))
+        } else {
+            Some(metric)
+        }
+    }
+
+    /// Applies the policy to all metrics, dropping the ignored ones.
+    pub fn filter<T>(&self, metrics: impl IntoIterator<Item = Metric<T>>) -> Vec<Metric<T>> {
+        metrics.into_iter().filter_map(|m| self.apply(m)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckResult, TriggerIfValue};
+
+    #[test]
+    fn test_from_args() {
+        let policy =
+            MetricPolicy::from_args(["-H", "db1", "--ignore-metric", "a", "--force-ok=b"]).unwrap();
+        assert_eq!(
+            policy,
+            MetricPolicy::new().with_ignored("a").with_forced_ok("b")
+        );
+        assert!(MetricPolicy::from_args(["--force-ok"]).is_err());
+
+        let metric = |name| Metric::new(name, 5).with_thresholds(1, 2, TriggerIfValue::Greater);
+        assert!(policy.apply(metric("a")).is_none());
+        let result: CheckResult = policy.apply(metric("b")).unwrap().into();
+        assert_eq!(result.state, Some(ServiceState::Ok));
+        let result: CheckResult = policy.apply(metric("c")).unwrap().into();
+        assert_eq!(result.state, Some(ServiceState::Critical));
+    }
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use crate::{Resource, UNDETERMINED_VALUE};
+
+#[derive(Debug, thiserror::Error)]
+#[error("MRTG output needs exactly 2 performance data entries, found {0}")]
+/// This error is returned by [Resource::to_mrtg] if the resource doesn't have exactly two
+/// performance data entries.
+pub struct MrtgError(usize);
+
+impl Resource {
+    /// Renders the two performance data entries of this resource in the format MRTG expects from
+    /// external scripts: the first value, the second value, the uptime and the name of the
+    /// target, each on a line of its own. The description is used as name, if set. Undetermined
+    /// values are written as `UNKNOWN`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use nagiosplugin::{Metric, Resource};
+    ///
+    /// let output = Resource::new("eth0")
+    ///     .with_result(Metric::new("in", 1024))
+    ///     .with_result(Metric::new("out", 512))
+    ///     .to_mrtg(Some(Duration::from_secs(3 * 86400 + 7500)))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(output, "1024\n512\n3 days, 2:05\neth0\n");
+    /// ```
+    pub fn to_mrtg(&self, uptime: Option<Duration>) -> Result<String, MrtgError> {
+        let values: Vec<_> = self
+            .perf_strings()
+            .map(|p| match p.value() {
+                UNDETERMINED_VALUE => "UNKNOWN",
+                value => value,
+            })
+            .collect();
+        let [first, second] = values[..] else {
+            return Err(MrtgError(values.len()));
+        };
+
+        Ok(format!(
+            "{}\n{}\n{}\n{}\n",
+            first,
+            second,
+            uptime.map(format_uptime).unwrap_or_default(),
+            self.description.as_deref().unwrap_or(&self.name)
+        ))
+    }
+}
+
+/// Formats the uptime like `uptime` does, e.g. `3 days, 2:05`.
+fn format_uptime(uptime: Duration) -> String {
+    let minutes = uptime.as_secs() / 60;
+    let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+
+    match days {
+        0 => format!("{}:{:02}", hours, minutes),
+        1 => format!("1 day, {}:{:02}", hours, minutes),
+        _ => format!("{} days, {}:{:02}", days, hours, minutes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Metric;
+
+    #[test]
+    fn test_to_mrtg() {
+        let resource = Resource::new("foo")
+            .with_description("Traffic on eth0")
+            .with_result(Metric::new("in", f64::NAN))
+            .with_result(Metric::new("out", 1.5));
+        assert_eq!(
+            resource.to_mrtg(None).unwrap(),
+            "UNKNOWN\n1.5\n\nTraffic on eth0\n"
+        );
+
+        let resource = Resource::new("foo").with_result(Metric::new("in", 1));
+        assert_eq!(
+            resource.to_mrtg(None).unwrap_err().to_string(),
+            "MRTG output needs exactly 2 performance data entries, found 1"
+        );
+
+        assert_eq!(
+            format_uptime(Duration::from_secs(86400 + 59)),
+            "1 day, 0:00"
+        );
+    }
+}
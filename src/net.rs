@@ -0,0 +1,635 @@
+//! Network settings shared by all checks of a plugin, read from the standard command line flags.
+//! Parsing them in one place keeps the flags and their behavior consistent across plugins.
+//!
+//! The network sinks of this crate take them with `with_net_options`: they resolve addresses with
+//! [NetOptions::resolve], so `-4` and `-6` apply, and bind UDP sockets to the source address.
+//! `InfluxSink` sends HTTP requests through the proxy. The sinks only speak plain TCP, UDP and
+//! HTTP, so the TLS options ([NetOptions::ca_file], the client certificate, [NetOptions::ca_pin],
+//! [NetOptions::insecure] and [NetOptions::sni]) are only parsed and meant to be passed on to the
+//! TLS client the plugin uses.
+//!
+//! This crate doesn't contain an Icinga 2 API client either. [crate::Resource::to_icinga_api_json]
+//! renders the body of a `process-check-result` request, which the plugin sends with its own
+//! client. To authenticate like a satellite, that client has to present [NetOptions::client_cert]
+//! and [NetOptions::client_key] and to only accept a server certificate chain containing the CA
+//! given by [NetOptions::ca_pin]; the options just carry the paths and the fingerprint.
+//!
+//! ## Example
+//!
+//! ```
+//! use nagiosplugin::net::NetOptions;
+//!
+//! let options = NetOptions::from_args(["check_api", "--proxy", "http://proxy:3128", "-k"]).unwrap();
+//! assert_eq!(options.proxy(), Some("http://proxy:3128"));
+//! assert!(options.insecure());
+//! ```
+
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::args;
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+/// This error is returned by [NetOptions::from_args].
+pub enum NetOptionsError {
+    #[error("missing value for {0}")]
+    MissingValue(String),
+    #[error("invalid source address '{0}'")]
+    InvalidSourceAddress(String),
+    #[error("invalid CA pin '{0}', expected a SHA-256 fingerprint in hex")]
+    InvalidCaPin(String),
+}
+
+/// A host given on the command line, usually with `-H`: a DNS name or an IP literal. IPv6
+/// literals may be enclosed in brackets. With the `clap` feature it can be used with
+/// `value_parser!(Host)`.
+///
+/// IPv6 addresses are displayed in brackets, so a port can be appended.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::net::Host;
+///
+/// let host: Host = "[2001:db8::1]".parse().unwrap();
+/// assert_eq!(host.to_string(), "[2001:db8::1]");
+/// assert!("-foo.example.com".parse::<Host>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Host {
+    Name(String),
+    Ip(IpAddr),
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[non_exhaustive]
+/// This error is returned by the [FromStr] implementation of [Host].
+pub enum HostParseError {
+    #[error("host must not be empty")]
+    Empty,
+    #[error("host name '{0}' is longer than 253 characters")]
+    TooLong(String),
+    #[error("invalid label '{label}' in host name '{host}'")]
+    InvalidLabel { host: String, label: String },
+    #[error("invalid IPv6 address '{0}'")]
+    InvalidIpv6(String),
+}
+
+impl FromStr for Host {
+    type Err = HostParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(HostParseError::Empty);
+        }
+        if let Some(inner) = s.strip_prefix('[') {
+            return inner
+                .strip_suffix(']')
+                .and_then(|inner| inner.parse::<Ipv6Addr>().ok())
+                .map(|ip| Host::Ip(ip.into()))
+                .ok_or_else(|| HostParseError::InvalidIpv6(s.to_owned()));
+        }
+        if let Ok(ip) = s.parse::<IpAddr>() {
+            return Ok(Host::Ip(ip));
+        }
+
+        let name = s.strip_suffix('.').unwrap_or(s);
+        if name.len() > 253 {
+            return Err(HostParseError::TooLong(s.to_owned()));
+        }
+        let valid_label = |label: &str| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        };
+        if let Some(label) = name.split('.').find(|label| !valid_label(label)) {
+            return Err(HostParseError::InvalidLabel {
+                host: s.to_owned(),
+                label: label.to_owned(),
+            });
+        }
+
+        Ok(Host::Name(s.to_owned()))
+    }
+}
+
+impl fmt::Display for Host {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Host::Name(name) => f.write_str(name),
+            Host::Ip(IpAddr::V6(ip)) => write!(f, "[{}]", ip),
+            Host::Ip(ip) => write!(f, "{}", ip),
+        }
+    }
+}
+
+/// A TCP or UDP port between 1 and 65535. With the `clap` feature it can be used with
+/// `value_parser!(Port)`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Port(u16);
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[error("invalid port '{0}', expected a number between 1 and 65535")]
+/// This error is returned by the [FromStr] implementation of [Port].
+pub struct PortParseError(String);
+
+impl Port {
+    pub fn get(&self) -> u16 {
+        self.0
+    }
+}
+
+impl FromStr for Port {
+    type Err = PortParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse() {
+            Ok(port) if port != 0 => Ok(Port(port)),
+            _ => Err(PortParseError(s.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for Port {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The address family used for connections, selected with `-4` and `-6` like in the classic
+/// monitoring plugins.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum AddressFamily {
+    /// Both IPv4 and IPv6, in the order returned by the resolver.
+    #[default]
+    Any,
+    Ipv4,
+    Ipv6,
+}
+
+impl AddressFamily {
+    pub fn matches(&self, address: &IpAddr) -> bool {
+        match self {
+            AddressFamily::Any => true,
+            AddressFamily::Ipv4 => address.is_ipv4(),
+            AddressFamily::Ipv6 => address.is_ipv6(),
+        }
+    }
+}
+
+/// The proxy, TLS and address settings of a plugin. See the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetOptions {
+    proxy: Option<String>,
+    ca_file: Option<PathBuf>,
+    client_cert: Option<PathBuf>,
+    client_key: Option<PathBuf>,
+    ca_pin: Option<[u8; 32]>,
+    insecure: bool,
+    sni: Option<String>,
+    source_address: Option<IpAddr>,
+    address_family: AddressFamily,
+}
+
+impl NetOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the options from the given arguments. Other arguments are ignored, so they can be
+    /// handled by the plugin itself.
+    ///
+    /// | Flag                        | Option                        |
+    /// |-----------------------------|-------------------------------|
+    /// | `--proxy <URL>`             | [NetOptions::proxy]           |
+    /// | `--ca-file <PATH>`          | [NetOptions::ca_file]         |
+    /// | `--client-cert <PATH>`      | [NetOptions::client_cert]     |
+    /// | `--client-key <PATH>`       | [NetOptions::client_key]      |
+    /// | `--ca-pin <SHA256>`         | [NetOptions::ca_pin]          |
+    /// | `-k`, `--insecure`          | [NetOptions::insecure]        |
+    /// | `--sni <NAME>`              | [NetOptions::sni]             |
+    /// | `--source-address <IP>`     | [NetOptions::source_address]  |
+    /// | `-4`, `--ipv4`              | [NetOptions::address_family]  |
+    /// | `-6`, `--ipv6`              | [NetOptions::address_family]  |
+    ///
+    /// Values are either the next argument or separated by `=`. Arguments after `--` are ignored.
+    /// The values of the standard plugin options `-H`, `-p`, `-w`, `-c` and `-t` and their long
+    /// forms are skipped, so e.g. `-w -6` isn't taken for `-6`. Use [NetOptions::from_args_with]
+    /// for other options of the plugin which take a value.
+    pub fn from_args<I, S>(args: I) -> Result<Self, NetOptionsError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self::from_args_with(args, &[])
+    }
+
+    /// Like [NetOptions::from_args], but skips the values of the given options as well.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use nagiosplugin::net::{AddressFamily, NetOptions};
+    ///
+    /// let options = NetOptions::from_args_with(["check_api", "--label", "-6"], &["--label"]);
+    /// assert_eq!(options.unwrap().address_family(), AddressFamily::Any);
+    /// ```
+    pub fn from_args_with<I, S>(args: I, value_options: &[&str]) -> Result<Self, NetOptionsError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let found = args::scan(
+            args,
+            args::Options {
+                values: &[
+                    "--proxy",
+                    "--ca-file",
+                    "--client-cert",
+                    "--client-key",
+                    "--ca-pin",
+                    "--sni",
+                    "--source-address",
+                ],
+                flags: &["-k", "--insecure", "-4", "--ipv4", "-6", "--ipv6"],
+                skip: value_options,
+            },
+        )
+        .map_err(NetOptionsError::MissingValue)?;
+
+        let mut options = Self::new();
+        for (key, value) in found {
+            let value = value.unwrap_or_default();
+            match key.as_str() {
+                "-k" | "--insecure" => options.insecure = true,
+                "-4" | "--ipv4" => options.address_family = AddressFamily::Ipv4,
+                "-6" | "--ipv6" => options.address_family = AddressFamily::Ipv6,
+                "--proxy" => options.proxy = Some(value),
+                "--ca-file" => options.ca_file = Some(value.into()),
+                "--client-cert" => options.client_cert = Some(value.into()),
+                "--client-key" => options.client_key = Some(value.into()),
+                "--ca-pin" => {
+                    let pin =
+                        parse_fingerprint(&value).ok_or(NetOptionsError::InvalidCaPin(value))?;
+                    options.ca_pin = Some(pin);
+                }
+                "--sni" => options.sni = Some(value),
+                _ => {
+                    let address = value
+                        .parse()
+                        .map_err(|_| NetOptionsError::InvalidSourceAddress(value))?;
+                    options.source_address = Some(address);
+                }
+            }
+        }
+
+        Ok(options)
+    }
+
+    pub fn with_proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    pub fn with_ca_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca_file = Some(path.into());
+        self
+    }
+
+    /// Sets the certificate and the private key used to authenticate against the server, e.g. the
+    /// Icinga 2 API which accepts the certificates of its satellites and agents.
+    pub fn with_client_cert(mut self, cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        self.client_cert = Some(cert.into());
+        self.client_key = Some(key.into());
+        self
+    }
+
+    /// Pins the CA by the SHA-256 fingerprint of its DER encoded certificate, see
+    /// [NetOptions::ca_pin].
+    pub fn with_ca_pin(mut self, fingerprint: [u8; 32]) -> Self {
+        self.ca_pin = Some(fingerprint);
+        self
+    }
+
+    pub fn with_insecure(mut self, insecure: bool) -> Self {
+        self.insecure = insecure;
+        self
+    }
+
+    pub fn with_sni(mut self, name: impl Into<String>) -> Self {
+        self.sni = Some(name.into());
+        self
+    }
+
+    pub fn with_source_address(mut self, address: IpAddr) -> Self {
+        self.source_address = Some(address);
+        self
+    }
+
+    pub fn with_address_family(mut self, family: AddressFamily) -> Self {
+        self.address_family = family;
+        self
+    }
+
+    /// The URL of the proxy for HTTP(S) connections.
+    pub fn proxy(&self) -> Option<&str> {
+        self.proxy.as_deref()
+    }
+
+    /// A PEM file with the CA certificates to trust instead of the system ones.
+    pub fn ca_file(&self) -> Option<&Path> {
+        self.ca_file.as_deref()
+    }
+
+    /// A PEM file with the client certificate. It may contain the private key as well, if
+    /// [NetOptions::client_key] is not set.
+    pub fn client_cert(&self) -> Option<&Path> {
+        self.client_cert.as_deref()
+    }
+
+    /// A PEM file with the private key of the client certificate.
+    pub fn client_key(&self) -> Option<&Path> {
+        self.client_key.as_deref()
+    }
+
+    /// The SHA-256 fingerprint of the DER encoded CA certificate the server certificate must be
+    /// issued by, e.g. the CA of an Icinga 2 cluster. On the command line it is given in hex, with
+    /// or without colons, as printed by `openssl x509 -fingerprint -sha256`. Checking it is up to
+    /// the TLS client of the plugin.
+    pub fn ca_pin(&self) -> Option<&[u8; 32]> {
+        self.ca_pin.as_ref()
+    }
+
+    /// If set, certificates are not verified.
+    pub fn insecure(&self) -> bool {
+        self.insecure
+    }
+
+    /// The server name sent in the TLS handshake instead of the host name.
+    pub fn sni(&self) -> Option<&str> {
+        self.sni.as_deref()
+    }
+
+    /// The local address outgoing connections are bound to. The standard library can't bind TCP
+    /// connections before connecting, so [NetOptions::connect] rejects it.
+    pub fn source_address(&self) -> Option<IpAddr> {
+        self.source_address
+    }
+
+    pub fn address_family(&self) -> AddressFamily {
+        self.address_family
+    }
+
+    /// Resolves the host and returns the addresses of the selected family. IP addresses are used
+    /// as they are. If a source address is set, only addresses of its family are returned, since a
+    /// connection can't be bound to it otherwise. Returns an error if no address is left.
+    pub fn resolve(&self, host: &Host, port: u16) -> io::Result<Vec<SocketAddr>> {
+        let resolved: Vec<_> = match host {
+            Host::Name(name) => (name.as_str(), port).to_socket_addrs()?.collect(),
+            Host::Ip(ip) => vec![SocketAddr::new(*ip, port)],
+        };
+        let addresses: Vec<_> = resolved
+            .into_iter()
+            .filter(|a| self.address_family.matches(&a.ip()))
+            .filter(|a| {
+                self.source_address
+                    .is_none_or(|source| source.is_ipv4() == a.is_ipv4())
+            })
+            .collect();
+
+        if addresses.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no matching address found for '{}'", host),
+            ));
+        }
+        Ok(addresses)
+    }
+
+    /// Like [NetOptions::resolve] for an address like `host`, `host:port` or `[2001:db8::1]:port`.
+    /// The port defaults to `default_port`.
+    pub fn resolve_address(&self, address: &str, default_port: u16) -> io::Result<Vec<SocketAddr>> {
+        let (host, port) = split_address(address, default_port)?;
+        self.resolve(&host, port)
+    }
+
+    /// Connects to the first reachable of the resolved addresses, see
+    /// [NetOptions::resolve_address]. Fails if a source address is set.
+    pub fn connect(
+        &self,
+        address: &str,
+        default_port: u16,
+        timeout: Duration,
+    ) -> io::Result<TcpStream> {
+        if let Some(source) = self.source_address {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "TCP connections can't be bound to the source address {}",
+                    source
+                ),
+            ));
+        }
+
+        let mut last_error = None;
+        for address in self.resolve_address(address, default_port)? {
+            match TcpStream::connect_timeout(&address, timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(err) => last_error = Some(err),
+            }
+        }
+        Err(last_error
+            .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address found")))
+    }
+
+    /// Binds a UDP socket for sending to the given address, to the source address if set.
+    pub fn bind_udp(&self, target: &SocketAddr) -> io::Result<UdpSocket> {
+        let local = match (self.source_address, target) {
+            (Some(source), _) => source,
+            (None, SocketAddr::V4(_)) => Ipv4Addr::UNSPECIFIED.into(),
+            (None, SocketAddr::V6(_)) => Ipv6Addr::UNSPECIFIED.into(),
+        };
+        UdpSocket::bind(SocketAddr::new(local, 0))
+    }
+}
+
+/// Splits an address into the host and the port, which defaults to `default_port`.
+fn split_address(address: &str, default_port: u16) -> io::Result<(Host, u16)> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid address '{}'", address),
+        )
+    };
+
+    // IPv6 literals without brackets contain colons, but no port.
+    if let Ok(ip) = address.parse::<IpAddr>() {
+        return Ok((Host::Ip(ip), default_port));
+    }
+    let (host, port) = match address.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') || host.ends_with(']') => {
+            let port: Port = port.parse().map_err(|_| invalid())?;
+            (host, port.get())
+        }
+        _ => (address, default_port),
+    };
+    Ok((host.parse().map_err(|_| invalid())?, port))
+}
+
+/// Parses a SHA-256 fingerprint given as hex digits, optionally separated by colons.
+fn parse_fingerprint(s: &str) -> Option<[u8; 32]> {
+    let digits: Vec<u8> = s
+        .chars()
+        .filter(|&c| c != ':')
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect::<Option<_>>()?;
+    if digits.len() != 64 {
+        return None;
+    }
+
+    let mut fingerprint = [0; 32];
+    for (byte, pair) in fingerprint.iter_mut().zip(digits.chunks(2)) {
+        *byte = pair[0] << 4 | pair[1];
+    }
+    Some(fingerprint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_args() {
+        let options = NetOptions::from_args([
+            "check",
+            "-H",
+            "example.com",
+            "--ca-file=/etc/ca.pem",
+            "--client-cert",
+            "/etc/icinga2/pki/agent.crt",
+            "--client-key=/etc/icinga2/pki/agent.key",
+            "--sni",
+            "api.example.com",
+            "--source-address",
+            "::1",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            options,
+            NetOptions::new()
+                .with_ca_file("/etc/ca.pem")
+                .with_client_cert("/etc/icinga2/pki/agent.crt", "/etc/icinga2/pki/agent.key")
+                .with_sni("api.example.com")
+                .with_source_address("::1".parse().unwrap())
+        );
+        assert_eq!(
+            NetOptions::from_args(["-6"]).unwrap().address_family(),
+            AddressFamily::Ipv6
+        );
+        assert!(matches!(
+            NetOptions::from_args(["--proxy"]),
+            Err(NetOptionsError::MissingValue(_))
+        ));
+        for args in [&["-w", "-6"][..], &["--", "-6"], &["--label", "-6"]] {
+            let options = NetOptions::from_args_with(args, &["--label"]).unwrap();
+            assert_eq!(options.address_family(), AddressFamily::Any, "{:?}", args);
+        }
+        assert!(matches!(
+            NetOptions::from_args(["--source-address", "foo"]),
+            Err(NetOptionsError::InvalidSourceAddress(_))
+        ));
+
+        let pin = format!("AB:{}", "01".repeat(31));
+        let options = NetOptions::from_args(["--ca-pin", pin.as_str()]).unwrap();
+        let mut expected = [1; 32];
+        expected[0] = 0xab;
+        assert_eq!(options.ca_pin(), Some(&expected));
+        assert!(matches!(
+            NetOptions::from_args(["--ca-pin", "abcd"]),
+            Err(NetOptionsError::InvalidCaPin(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_address() {
+        let options = NetOptions::new().with_address_family(AddressFamily::Ipv4);
+        assert_eq!(
+            options.resolve_address("127.0.0.1:8125", 1).unwrap(),
+            vec!["127.0.0.1:8125".parse().unwrap()]
+        );
+        assert_eq!(
+            NetOptions::new().resolve_address("::1", 10051).unwrap(),
+            vec!["[::1]:10051".parse().unwrap()]
+        );
+        assert!(options.resolve_address("[::1]:8125", 1).is_err());
+        assert!(options.resolve_address("localhost:0", 1).is_err());
+
+        let options = NetOptions::new().with_source_address("127.0.0.1".parse().unwrap());
+        let target = "127.0.0.1:8125".parse().unwrap();
+        let socket = options.bind_udp(&target).unwrap();
+        assert_eq!(
+            socket.local_addr().unwrap().ip(),
+            options.source_address().unwrap()
+        );
+        assert!(options
+            .connect("127.0.0.1:1", 1, Duration::from_secs(1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_host_parse() {
+        assert_eq!(
+            "db-1.example.com.".parse(),
+            Ok(Host::Name("db-1.example.com.".to_owned()))
+        );
+        assert_eq!("10.0.0.1".parse(), Ok(Host::Ip([10, 0, 0, 1].into())));
+        assert_eq!("::1".parse(), Ok(Host::Ip(Ipv6Addr::LOCALHOST.into())));
+        assert_eq!("[::1]".parse(), Ok(Host::Ip(Ipv6Addr::LOCALHOST.into())));
+        assert_eq!(
+            "[::1".parse::<Host>(),
+            Err(HostParseError::InvalidIpv6("[::1".to_owned()))
+        );
+        assert_eq!("".parse::<Host>(), Err(HostParseError::Empty));
+        assert!(matches!(
+            "foo..bar".parse::<Host>(),
+            Err(HostParseError::InvalidLabel { .. })
+        ));
+        assert!("foo bar".parse::<Host>().is_err());
+    }
+
+    #[test]
+    fn test_port_parse() {
+        assert_eq!("443".parse::<Port>().unwrap().get(), 443);
+        assert!("0".parse::<Port>().is_err());
+        assert!("65536".parse::<Port>().is_err());
+        assert!("http".parse::<Port>().is_err());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let host = |s: &str| s.parse::<Host>().unwrap();
+
+        let options = NetOptions::new().with_address_family(AddressFamily::Ipv4);
+        assert_eq!(
+            options.resolve(&host("127.0.0.1"), 80).unwrap(),
+            ["127.0.0.1:80".parse().unwrap()]
+        );
+        assert!(options.resolve(&host("::1"), 80).is_err());
+
+        let options = NetOptions::new().with_source_address("::1".parse().unwrap());
+        assert!(options.resolve(&host("127.0.0.1"), 80).is_err());
+        assert_eq!(
+            options.resolve(&host("[::1]"), 80).unwrap(),
+            ["[::1]:80".parse().unwrap()]
+        );
+    }
+}
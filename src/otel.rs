@@ -0,0 +1,94 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+use opentelemetry::trace::{SpanRef, Status, TraceContextExt, Tracer};
+use opentelemetry::{global, Context, KeyValue};
+
+use crate::{Resource, ServiceState};
+
+const TRACER_NAME: &str = "nagiosplugin";
+
+/// Runs the check inside of a span with the given name. The final state and all performance data
+/// values are recorded as attributes. Spans created inside of the check, e.g. by [SubChecks],
+/// become children of this span.
+///
+/// [SubChecks]: crate::SubChecks
+pub(crate) fn in_span<E: fmt::Display>(
+    name: String,
+    error_state: ServiceState,
+    f: impl FnOnce() -> Result<Resource, E>,
+) -> Result<Resource, E> {
+    global::tracer(TRACER_NAME).in_span(name, |cx| {
+        let result = f();
+        record_result(&cx.span(), error_state, &result);
+        result
+    })
+}
+
+/// Like [in_span], for checks which are futures. The span is the current one whenever the future
+/// is polled, so spans created inside of the check become its children as well.
+pub(crate) async fn in_span_async<E: fmt::Display>(
+    name: String,
+    error_state: ServiceState,
+    future: impl Future<Output = Result<Resource, E>>,
+) -> Result<Resource, E> {
+    let span = global::tracer(TRACER_NAME).start(name);
+    let cx = Context::current_with_span(span);
+    let result = WithContext {
+        future: Box::pin(future),
+        cx: cx.clone(),
+    }
+    .await;
+
+    let span = cx.span();
+    record_result(&span, error_state, &result);
+    span.end();
+    result
+}
+
+struct WithContext<F> {
+    future: Pin<Box<F>>,
+    cx: Context,
+}
+
+impl<F: Future> Future for WithContext<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, task: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let _guard = self.cx.clone().attach();
+        self.future.as_mut().poll(task)
+    }
+}
+
+fn record_result<E: fmt::Display>(
+    span: &SpanRef<'_>,
+    error_state: ServiceState,
+    result: &Result<Resource, E>,
+) {
+    match result {
+        Ok(resource) => record_resource(span, resource),
+        Err(err) => {
+            set_state(span, error_state);
+            span.set_status(Status::error(err.to_string()));
+        }
+    }
+}
+
+fn record_resource(span: &SpanRef<'_>, resource: &Resource) {
+    set_state(span, resource.state());
+
+    for perf_string in resource.perf_strings() {
+        let key = format!("nagios.metric.{}", perf_string.label);
+        match perf_string.value.parse::<f64>() {
+            Ok(value) => span.set_attribute(KeyValue::new(key, value)),
+            Err(_) => span.set_attribute(KeyValue::new(key, perf_string.value.clone())),
+        }
+    }
+}
+
+fn set_state(span: &SpanRef<'_>, state: ServiceState) {
+    span.set_attribute(KeyValue::new("nagios.state", state.to_string()));
+    span.set_attribute(KeyValue::new("nagios.exit_code", state.exit_code() as i64));
+}
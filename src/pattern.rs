@@ -0,0 +1,473 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+/// A regular expression for matching names, e.g. of metrics. Only the common subset is
+/// supported: literals, `.`, character classes like `[a-z]` and `[^0-9]`, the escapes `\d`, `\w`
+/// and `\s`, the anchors `^` and `$`, groups with alternatives like `(sda|sdb)`, and the
+/// quantifiers `*`, `+`, `?` and `{n,m}`. As with `grep`, a pattern matches if it matches any
+/// part of the text, unless it is anchored.
+///
+/// Matching doesn't backtrack, it takes time linear in the length of the text for any pattern, so
+/// patterns like `(a+)+b` from a config file can't stall the plugin.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::Pattern;
+///
+/// let pattern: Pattern = "^(sd[a-z]|nvme\\d+n\\d+)_usage$".parse().unwrap();
+/// assert!(pattern.is_match("nvme0n1_usage"));
+/// assert!(!pattern.is_match("loop0_usage"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    source: String,
+    program: Vec<Inst>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid pattern '{pattern}': {reason}")]
+/// This error is returned by the [FromStr] implementation of [Pattern].
+pub struct PatternError {
+    pattern: String,
+    reason: String,
+}
+
+#[derive(Debug, Clone)]
+struct Piece {
+    node: Node,
+    min: usize,
+    max: Option<usize>,
+}
+
+/// An instruction of the compiled pattern, which is run as a Pike VM: all possible matches are
+/// followed at the same time, in the order a backtracking matcher would try them.
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    Start,
+    End,
+    /// Continues at both positions, preferring the first.
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+/// The maximum number of instructions, as counted quantifiers repeat the compiled pattern.
+const MAX_PROGRAM_SIZE: usize = 10_000;
+
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    Any,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    Group(Vec<Vec<Piece>>),
+    Start,
+    End,
+}
+
+impl Pattern {
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.find(text).is_some()
+    }
+
+    /// Returns the byte range of the leftmost match. Of the matches starting there, it returns
+    /// the one a backtracking matcher would find, i.e. with greedy quantifiers and the first
+    /// matching alternative.
+    pub(crate) fn find(&self, text: &str) -> Option<(usize, usize)> {
+        let chars: Vec<char> = text.chars().collect();
+        let offset = |pos: usize| chars[..pos].iter().map(|c| c.len_utf8()).sum::<usize>();
+
+        // Threads are the instruction and the start of their match, ordered by priority.
+        let mut threads = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        let mut found = None;
+
+        for pos in 0..=chars.len() {
+            if found.is_none() {
+                threads.add(&self.program, &chars, 0, pos, pos);
+            }
+            if threads.list.is_empty() {
+                if found.is_some() {
+                    break;
+                }
+                continue;
+            }
+
+            next.clear();
+            for &(pc, start) in &threads.list {
+                let matched = match &self.program[pc] {
+                    Inst::Match => {
+                        // Threads with a lower priority can't change the result anymore.
+                        found = Some((start, pos));
+                        break;
+                    }
+                    Inst::Char(expected) => chars.get(pos) == Some(expected),
+                    Inst::Any => pos < chars.len(),
+                    Inst::Class { negated, ranges } => chars.get(pos).is_some_and(|c| {
+                        ranges.iter().any(|&(from, to)| (from..=to).contains(c)) != *negated
+                    }),
+                    _ => unreachable!("only consuming instructions are queued"),
+                };
+                if matched {
+                    next.add(&self.program, &chars, pc + 1, pos + 1, start);
+                }
+            }
+            std::mem::swap(&mut threads, &mut next);
+        }
+
+        found.map(|(start, end)| (offset(start), offset(end)))
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Pattern {}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |reason: &str| PatternError {
+            pattern: s.to_owned(),
+            reason: reason.to_owned(),
+        };
+        let mut chars = s.chars().peekable();
+        let alternatives = parse_alternatives(&mut chars, false).map_err(error)?;
+
+        let mut program = Vec::new();
+        compile_alternatives(&alternatives, &mut program).map_err(error)?;
+        program.push(Inst::Match);
+
+        Ok(Self {
+            source: s.to_owned(),
+            program,
+        })
+    }
+}
+
+type Input<'a> = Peekable<Chars<'a>>;
+
+fn parse_alternatives(chars: &mut Input, in_group: bool) -> Result<Vec<Vec<Piece>>, &'static str> {
+    let mut alternatives = vec![Vec::new()];
+
+    loop {
+        let node = match chars.next() {
+            None if in_group => return Err("missing ')'"),
+            None => return Ok(alternatives),
+            Some(')') if in_group => return Ok(alternatives),
+            Some(')') => return Err("unmatched ')'"),
+            Some('|') => {
+                alternatives.push(Vec::new());
+                continue;
+            }
+            Some('*' | '+' | '?' | '{') => return Err("nothing to repeat"),
+            Some('.') => Node::Any,
+            Some('^') => Node::Start,
+            Some('$') => Node::End,
+            Some('(') => {
+                // Non-capturing groups are the same as groups, as nothing is captured.
+                if chars.peek() == Some(&'?') {
+                    chars.next();
+                    if chars.next() != Some(':') {
+                        return Err("unsupported group");
+                    }
+                }
+                Node::Group(parse_alternatives(chars, true)?)
+            }
+            Some('[') => parse_class(chars)?,
+            Some('\\') => match chars.next().ok_or("trailing '\\'")? {
+                c @ ('d' | 'w' | 's') => Node::Class {
+                    negated: false,
+                    ranges: shorthand_ranges(c),
+                },
+                c @ ('D' | 'W' | 'S') => Node::Class {
+                    negated: true,
+                    ranges: shorthand_ranges(c.to_ascii_lowercase()),
+                },
+                c => Node::Char(escaped(c)),
+            },
+            Some(c) => Node::Char(c),
+        };
+
+        let (min, max) = parse_quantifier(chars)?;
+        if matches!(chars.peek(), Some('*' | '+' | '?' | '{')) {
+            return Err("nested quantifiers are not supported");
+        }
+        let sequence = alternatives.last_mut().expect("at least one alternative");
+        sequence.push(Piece { node, min, max });
+    }
+}
+
+fn parse_quantifier(chars: &mut Input) -> Result<(usize, Option<usize>), &'static str> {
+    let quantifier = match chars.peek() {
+        Some('*') => (0, None),
+        Some('+') => (1, None),
+        Some('?') => (0, Some(1)),
+        Some('{') => {
+            chars.next();
+            let mut spec = String::new();
+            loop {
+                match chars.next().ok_or("missing '}'")? {
+                    '}' => break,
+                    c => spec.push(c),
+                }
+            }
+            let number = |s: &str| s.trim().parse::<usize>().map_err(|_| "invalid quantifier");
+            let (min, max) = match spec.split_once(',') {
+                Some((min, "")) => (number(min)?, None),
+                Some((min, max)) => (number(min)?, Some(number(max)?)),
+                None => (number(&spec)?, Some(number(&spec)?)),
+            };
+            if max.is_some_and(|max| max < min) {
+                return Err("invalid quantifier");
+            }
+            return Ok((min, max));
+        }
+        _ => return Ok((1, Some(1))),
+    };
+    chars.next();
+    Ok(quantifier)
+}
+
+fn parse_class(chars: &mut Input) -> Result<Node, &'static str> {
+    let negated = chars.peek() == Some(&'^');
+    if negated {
+        chars.next();
+    }
+
+    let mut ranges = Vec::new();
+    let mut first = true;
+    loop {
+        let c = match chars.next().ok_or("missing ']'")? {
+            ']' if !first => return Ok(Node::Class { negated, ranges }),
+            '\\' => match chars.next().ok_or("missing ']'")? {
+                c @ ('d' | 'w' | 's') => {
+                    ranges.extend(shorthand_ranges(c));
+                    first = false;
+                    continue;
+                }
+                c => escaped(c),
+            },
+            c => c,
+        };
+        first = false;
+
+        if chars.peek() == Some(&'-') {
+            chars.next();
+            match chars.next().ok_or("missing ']'")? {
+                ']' => {
+                    ranges.extend([(c, c), ('-', '-')]);
+                    return Ok(Node::Class { negated, ranges });
+                }
+                end if end < c => return Err("invalid range in class"),
+                end => ranges.push((c, end)),
+            }
+        } else {
+            ranges.push((c, c));
+        }
+    }
+}
+
+fn shorthand_ranges(c: char) -> Vec<(char, char)> {
+    match c {
+        'd' => vec![('0', '9')],
+        'w' => vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')],
+        _ => vec![(' ', ' '), ('\t', '\r')],
+    }
+}
+
+fn escaped(c: char) -> char {
+    match c {
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        c => c,
+    }
+}
+
+fn compile_alternatives(
+    alternatives: &[Vec<Piece>],
+    program: &mut Vec<Inst>,
+) -> Result<(), &'static str> {
+    let mut jumps = Vec::new();
+    for (index, sequence) in alternatives.iter().enumerate() {
+        let split = program.len();
+        let last = index + 1 == alternatives.len();
+        if !last {
+            program.push(Inst::Split(split + 1, 0));
+        }
+        for piece in sequence {
+            compile_piece(piece, program)?;
+        }
+        if !last {
+            jumps.push(program.len());
+            program.push(Inst::Jump(0));
+            program[split] = Inst::Split(split + 1, program.len());
+        }
+    }
+    for jump in jumps {
+        program[jump] = Inst::Jump(program.len());
+    }
+    Ok(())
+}
+
+fn compile_piece(piece: &Piece, program: &mut Vec<Inst>) -> Result<(), &'static str> {
+    for _ in 0..piece.min {
+        compile_node(&piece.node, program)?;
+    }
+
+    match piece.max {
+        None => {
+            let split = program.len();
+            program.push(Inst::Split(split + 1, 0));
+            compile_node(&piece.node, program)?;
+            program.push(Inst::Jump(split));
+            program[split] = Inst::Split(split + 1, program.len());
+        }
+        Some(max) => {
+            let mut splits = Vec::new();
+            for _ in piece.min..max {
+                splits.push(program.len());
+                program.push(Inst::Split(program.len() + 1, 0));
+                compile_node(&piece.node, program)?;
+            }
+            for split in splits {
+                program[split] = Inst::Split(split + 1, program.len());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn compile_node(node: &Node, program: &mut Vec<Inst>) -> Result<(), &'static str> {
+    match node {
+        Node::Char(c) => program.push(Inst::Char(*c)),
+        Node::Any => program.push(Inst::Any),
+        Node::Class { negated, ranges } => program.push(Inst::Class {
+            negated: *negated,
+            ranges: ranges.clone(),
+        }),
+        Node::Group(alternatives) => compile_alternatives(alternatives, program)?,
+        Node::Start => program.push(Inst::Start),
+        Node::End => program.push(Inst::End),
+    }
+
+    if program.len() > MAX_PROGRAM_SIZE {
+        return Err("pattern is too large");
+    }
+    Ok(())
+}
+
+/// The threads at a position of the text, each instruction is only queued once.
+struct Threads {
+    list: Vec<(usize, usize)>,
+    queued: Vec<bool>,
+}
+
+impl Threads {
+    fn new(size: usize) -> Self {
+        Self {
+            list: Vec::new(),
+            queued: vec![false; size],
+        }
+    }
+
+    fn clear(&mut self) {
+        self.list.clear();
+        self.queued.fill(false);
+    }
+
+    /// Follows the instructions which don't consume a character, then queues the thread.
+    fn add(&mut self, program: &[Inst], text: &[char], pc: usize, pos: usize, start: usize) {
+        let mut stack = vec![pc];
+        while let Some(pc) = stack.pop() {
+            if std::mem::replace(&mut self.queued[pc], true) {
+                continue;
+            }
+            match program[pc] {
+                Inst::Split(first, second) => stack.extend([second, first]),
+                Inst::Jump(target) => stack.push(target),
+                Inst::Start if pos == 0 => stack.push(pc + 1),
+                Inst::End if pos == text.len() => stack.push(pc + 1),
+                Inst::Start | Inst::End => {}
+                _ => self.list.push((pc, start)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_match() {
+        let matches =
+            |pattern: &str, text: &str| pattern.parse::<Pattern>().unwrap().is_match(text);
+
+        assert!(matches("usage", "disk_usage_root"));
+        assert!(!matches("^usage", "disk_usage_root"));
+        assert!(matches("^disk_.*_root$", "disk_usage_root"));
+        assert!(matches("^(sd[a-c]|vd[^a])\\d?$", "vdb1"));
+        assert!(!matches("^(sd[a-c]|vd[^a])\\d?$", "vda"));
+        assert!(matches("^a{2,3}b+$", "aaab"));
+        assert!(!matches("^a{2,3}b+$", "ab"));
+        assert!(matches("^x(y|)*z$", "xyyz"));
+        assert!(matches("[.]\\.", "a.."));
+        assert!(matches("^é.$", "éa"));
+
+        for invalid in ["(a", "a)", "*a", "[a", "a{3,1}", "a**", "\\"] {
+            assert!(invalid.parse::<Pattern>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_find() {
+        let pattern: Pattern = "o+".parse().unwrap();
+        assert_eq!(pattern.find("föoo"), Some((3, 5)));
+        assert_eq!(pattern.find("bar"), None);
+
+        // The first alternative wins, even if a later one would match more.
+        let pattern: Pattern = "(a|ab)c?".parse().unwrap();
+        assert_eq!(pattern.find("xabc"), Some((1, 2)));
+        let pattern: Pattern = "x*".parse().unwrap();
+        assert_eq!(pattern.find("ab"), Some((0, 0)));
+    }
+
+    #[test]
+    fn test_no_catastrophic_backtracking() {
+        let pattern: Pattern = "(a+)+b".parse().unwrap();
+        let text = "a".repeat(10_000);
+
+        let start = std::time::Instant::now();
+        assert!(!pattern.is_match(&text));
+        // Backtracking takes seconds for 25 characters already.
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+
+        assert!("a{1000}{1000}".parse::<Pattern>().is_err());
+    }
+}
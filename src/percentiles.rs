@@ -0,0 +1,101 @@
+use crate::{Metric, TriggerIfValue, Unit};
+
+/// Percentiles of the values sampled during a single run, e.g. the response times of 20 requests,
+/// turned into one metric per percentile with shared unit and thresholds. Percentiles are
+/// calculated with the nearest-rank method, values which are not a number are ignored.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{Percentiles, Resource, ServiceState, TriggerIfValue, Unit};
+///
+/// let response_times = [0.12, 0.15, 0.11, 0.95, 0.13, 0.14, 0.12, 0.18, 0.16, 0.13];
+/// let metrics = Percentiles::new(response_times)
+///     .with_unit(Unit::Seconds)
+///     .with_thresholds(0.5, 1.0, TriggerIfValue::Greater)
+///     .metrics("response_time", &[50.0, 95.0, 99.0]);
+///
+/// let resource = Resource::from_metrics("web", metrics);
+/// assert_eq!(resource.state(), ServiceState::Warning);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Percentiles {
+    sorted: Vec<f64>,
+    unit: Unit,
+    thresholds: Option<(Option<f64>, Option<f64>, TriggerIfValue)>,
+}
+
+impl Percentiles {
+    pub fn new(samples: impl IntoIterator<Item = f64>) -> Self {
+        let mut sorted: Vec<_> = samples.into_iter().filter(|v| !v.is_nan()).collect();
+        sorted.sort_by(f64::total_cmp);
+
+        Self {
+            sorted,
+            unit: Default::default(),
+            thresholds: Default::default(),
+        }
+    }
+
+    pub fn with_unit(mut self, unit: Unit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// Sets the thresholds which apply to all percentiles.
+    pub fn with_thresholds(
+        mut self,
+        warning: impl Into<Option<f64>>,
+        critical: impl Into<Option<f64>>,
+        trigger_if_value: TriggerIfValue,
+    ) -> Self {
+        self.thresholds = Some((warning.into(), critical.into(), trigger_if_value));
+        self
+    }
+
+    /// Returns the given percentile between 0 and 100, or `None` without samples.
+    pub fn get(&self, percentile: f64) -> Option<f64> {
+        let n = self.sorted.len();
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * n as f64).ceil() as usize;
+        self.sorted.get(rank.clamp(1, n.max(1)) - 1).copied()
+    }
+
+    /// Returns a metric named `<name>_p<percentile>` for each of the given percentiles, e.g.
+    /// `latency_p95`. Without samples the values are not a number.
+    pub fn metrics(&self, name: &str, percentiles: &[f64]) -> Vec<Metric<f64>> {
+        percentiles
+            .iter()
+            .map(|&p| {
+                let value = self.get(p).unwrap_or(f64::NAN);
+                let mut metric =
+                    Metric::new(format!("{}_p{}", name, p), value).with_unit(self.unit.clone());
+                if let Some((warning, critical, trigger)) = self.thresholds {
+                    metric = metric.with_thresholds(warning, critical, trigger);
+                }
+                metric
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let percentiles = Percentiles::new((1..=20).rev().map(f64::from).chain([f64::NAN]));
+        assert_eq!(percentiles.get(50.0), Some(10.0));
+        assert_eq!(percentiles.get(95.0), Some(19.0));
+        assert_eq!(percentiles.get(99.0), Some(20.0));
+        assert_eq!(percentiles.get(0.0), Some(1.0));
+        assert_eq!(Percentiles::new([]).get(50.0), None);
+
+        let names: Vec<_> = percentiles
+            .metrics("latency", &[50.0, 99.9])
+            .into_iter()
+            .map(|m| m.name)
+            .collect();
+        assert_eq!(names, ["latency_p50", "latency_p99.9"]);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{CheckResult, PerfString, ServiceState};
+
+/// Defines how performance data entries sharing a label but disagreeing on the value or the unit
+/// are handled, e.g. if two discovered items end up with the same label. Entries with the same
+/// value and unit are always merged into the first one, filling its empty thresholds and range.
+/// See [crate::Resource::with_perf_conflict_policy].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PerfConflictPolicy {
+    /// Keeps the first entry and drops the conflicting ones.
+    Merge,
+    /// Drops all entries with the label.
+    Drop,
+    /// Keeps the first entry and adds a [ServiceState::Unknown] result describing the conflict.
+    Error,
+}
+
+/// Two entries sharing a label, recorded for the verbose output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PerfConflict {
+    label: String,
+    kept: String,
+    conflicting: String,
+}
+
+impl PerfConflict {
+    pub(crate) fn describe(&self, policy: PerfConflictPolicy) -> String {
+        let outcome = match policy {
+            PerfConflictPolicy::Merge | PerfConflictPolicy::Error => "kept the first",
+            PerfConflictPolicy::Drop => "dropped both",
+        };
+        format!(
+            "conflict: performance data label '{}': {} and {}, {}",
+            self.label, self.kept, self.conflicting, outcome
+        )
+    }
+}
+
+/// Removes the entries of the new result which are already part of the existing results, applying
+/// the policy to conflicting ones. Returns the results describing the conflicts for
+/// [PerfConflictPolicy::Error].
+pub(crate) fn resolve(
+    policy: PerfConflictPolicy,
+    existing: &mut [CheckResult],
+    conflicts: &mut Vec<PerfConflict>,
+    result: &mut CheckResult,
+) -> Vec<CheckResult> {
+    let mut errors = Vec::new();
+    let mut kept: Vec<PerfString> = Vec::with_capacity(result.perf_strings.len());
+
+    for perf_string in std::mem::take(&mut result.perf_strings) {
+        if policy == PerfConflictPolicy::Drop
+            && conflicts.iter().any(|c| c.label == perf_string.label)
+        {
+            continue;
+        }
+
+        let same_label = existing
+            .iter_mut()
+            .flat_map(|r| r.perf_strings.iter_mut())
+            .chain(kept.iter_mut())
+            .find(|p| p.label == perf_string.label);
+        let Some(first) = same_label else {
+            kept.push(perf_string);
+            continue;
+        };
+
+        if first.value == perf_string.value && first.unit == perf_string.unit {
+            for (field, other) in [
+                (&mut first.warning, perf_string.warning),
+                (&mut first.critical, perf_string.critical),
+                (&mut first.minimum, perf_string.minimum),
+                (&mut first.maximum, perf_string.maximum),
+            ] {
+                if field.is_empty() {
+                    *field = other;
+                }
+            }
+            continue;
+        }
+
+        let conflict = PerfConflict {
+            label: perf_string.label.clone(),
+            kept: first.to_string(),
+            conflicting: perf_string.to_string(),
+        };
+        match policy {
+            PerfConflictPolicy::Merge => {}
+            PerfConflictPolicy::Drop => {
+                for r in existing.iter_mut() {
+                    r.perf_strings.retain(|p| p.label != perf_string.label);
+                }
+                kept.retain(|p| p.label != perf_string.label);
+            }
+            PerfConflictPolicy::Error => errors.push(
+                CheckResult::new()
+                    .with_state(ServiceState::Unknown)
+                    .with_message(format!(
+                        "conflicting performance data for label '{}': {} and {}",
+                        conflict.label, conflict.kept, conflict.conflicting
+                    )),
+            ),
+        }
+        conflicts.push(conflict);
+    }
+
+    result.perf_strings = kept;
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Metric, PerfConflictPolicy, Relabel, Resource, ServiceState, TriggerIfValue};
+
+    fn resource(policy: PerfConflictPolicy) -> Resource {
+        Resource::new("foo")
+            .with_perf_conflict_policy(policy)
+            .with_result(Metric::new("a", 1))
+            .with_result(Metric::new("a", 1).with_thresholds(2, 3, TriggerIfValue::Greater))
+            .with_result(Metric::new("b", 1))
+            .with_result(Metric::new("b", 2))
+            .with_result(Metric::new("b", 3))
+    }
+
+    #[test]
+    fn test_resolve() {
+        let (state, output) = resource(PerfConflictPolicy::Merge).nagios_result();
+        assert_eq!(state, ServiceState::Ok);
+        assert_eq!(output, "foo is OK|'a'=1;2;3;; 'b'=1;;;;");
+
+        let (state, output) = resource(PerfConflictPolicy::Error).nagios_result();
+        assert_eq!(state, ServiceState::Unknown);
+        assert_eq!(
+            output,
+            "foo is UNKNOWN\n\n\
+             conflicting performance data for label 'b': 'b'=1;;;; and 'b'=2;;;;\n\
+             conflicting performance data for label 'b': 'b'=1;;;; and 'b'=3;;;;\n\
+             |'a'=1;2;3;; 'b'=1;;;;"
+        );
+
+        let (_, output) = resource(PerfConflictPolicy::Drop)
+            .with_verbosity(2)
+            .nagios_result();
+        assert!(output.contains(
+            "conflict: performance data label 'b': 'b'=1;;;; and 'b'=2;;;;, dropped both\n"
+        ));
+        assert!(output.ends_with("|'a'=1;2;3;;"));
+    }
+
+    #[test]
+    fn test_policy_after_relabel() {
+        let (_, output) = Resource::new("ports")
+            .with_relabel(Relabel::new().with_replace("^Gi".parse().unwrap(), "GigabitEthernet"))
+            .with_result(Metric::new("Gi0/1", 1))
+            .with_result(Metric::new("Gi0/1", 1))
+            .with_perf_conflict_policy(PerfConflictPolicy::Merge)
+            .nagios_result();
+        assert_eq!(output, "ports is OK|'GigabitEthernet0/1'=1;;;;");
+    }
+}
@@ -0,0 +1,238 @@
+use crate::{CheckResult, Metric, NamedThresholds, PerfString, Resource, ServiceState};
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[error("invalid performance data at '{0}'")]
+/// This error is returned if performance data can't be parsed.
+pub struct PerfDataParseError(String);
+
+/// The output of a plugin split into its parts, see [parse_plugin_output].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginOutput {
+    /// The first line without performance data.
+    pub summary: String,
+    /// The remaining lines without performance data.
+    pub long_output: String,
+    pub perf_strings: Vec<PerfString>,
+}
+
+/// Splits the output of a plugin into summary, long output and performance data. Performance
+/// data is read from the first line after `|` and from all lines after the first `|` in the long
+/// output, as described in the
+/// [Nagios Plugin API](https://assets.nagios.com/downloads/nagioscore/docs/nagioscore/4/en/pluginapi.html).
+pub fn parse_plugin_output(output: &str) -> Result<PluginOutput, PerfDataParseError> {
+    let mut lines = output.lines();
+    let first = lines.next().unwrap_or_default();
+    let (summary, mut perf_data) = match first.split_once('|') {
+        Some((summary, perf_data)) => (summary, perf_data.to_owned()),
+        None => (first, String::new()),
+    };
+
+    let mut long_output = Vec::new();
+    let mut in_perf_data = false;
+    for line in lines {
+        if in_perf_data {
+            perf_data.push(' ');
+            perf_data.push_str(line);
+        } else if let Some((text, perf)) = line.split_once('|') {
+            long_output.push(text);
+            perf_data.push(' ');
+            perf_data.push_str(perf);
+            in_perf_data = true;
+        } else {
+            long_output.push(line);
+        }
+    }
+
+    Ok(PluginOutput {
+        summary: summary.trim().to_owned(),
+        long_output: long_output.join("\n").trim_end().to_owned(),
+        perf_strings: parse_perf_data(&perf_data)?,
+    })
+}
+
+/// Parses space separated performance data entries like `'used space'=93%;80;90;0;100`.
+pub fn parse_perf_data(s: &str) -> Result<Vec<PerfString>, PerfDataParseError> {
+    let mut perf_strings = Vec::new();
+    let mut rest = s.trim_start();
+
+    while !rest.is_empty() {
+        let error = || PerfDataParseError(rest.to_owned());
+
+        let (label, after_label) = match rest.strip_prefix('\'') {
+            Some(quoted) => parse_quoted_label(quoted).ok_or_else(error)?,
+            None => {
+                let end = rest.find('=').ok_or_else(error)?;
+                (rest[..end].to_owned(), &rest[end..])
+            }
+        };
+        let after_label = after_label.strip_prefix('=').ok_or_else(error)?;
+        let end = after_label
+            .find(char::is_whitespace)
+            .unwrap_or(after_label.len());
+        let (data, remaining) = after_label.split_at(end);
+
+        if label.is_empty() || data.is_empty() {
+            return Err(error());
+        }
+        perf_strings.push(parse_entry(label, data).ok_or_else(error)?);
+        rest = remaining.trim_start();
+    }
+
+    Ok(perf_strings)
+}
+
+/// Returns the label and the remaining input. Quotes inside of the label are doubled.
+fn parse_quoted_label(s: &str) -> Option<(String, &str)> {
+    let mut label = String::new();
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if c != '\'' {
+            label.push(c);
+        } else if chars.peek().map(|&(_, c)| c) == Some('\'') {
+            label.push('\'');
+            chars.next();
+        } else {
+            return Some((label, &s[i + 1..]));
+        }
+    }
+    None
+}
+
+fn parse_entry(label: String, data: &str) -> Option<PerfString> {
+    let mut fields = data.split(';');
+    let value_with_unit = fields.next()?;
+    let split = value_with_unit
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+')))
+        .unwrap_or(value_with_unit.len());
+    let (value, unit) = match value_with_unit.split_at(split) {
+        ("", "U") => ("U", ""),
+        ("", _) => return None,
+        (value, unit) => (value, unit),
+    };
+    if value != "U" && value.parse::<f64>().is_err() {
+        return None;
+    }
+
+    let mut next = || fields.next().unwrap_or_default().to_owned();
+    Some(PerfString {
+        label,
+        value: value.to_owned(),
+        unit: unit.parse().ok()?,
+        warning: next(),
+        critical: next(),
+        minimum: next(),
+        maximum: next(),
+    })
+}
+
+/// Re-evaluates the output of another plugin with own thresholds: the performance data entries
+/// named in `thresholds` become [Metric]s with these thresholds, all other entries are ignored.
+/// Missing entries and unparsable output are reported as [ServiceState::Unknown].
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{rethreshold, NamedThresholds, ServiceState};
+///
+/// let thresholds: NamedThresholds<f64> = ["rta=100,200".parse().unwrap()].into_iter().collect();
+/// let output = "PING OK - Packet loss = 0%, RTA = 150.00 ms|rta=150.000000ms;3000;5000;0 pl=0%;80;100;0";
+///
+/// let resource = rethreshold("ping", output, &thresholds);
+/// assert_eq!(resource.state(), ServiceState::Warning);
+/// ```
+pub fn rethreshold(
+    name: impl Into<String>,
+    output: &str,
+    thresholds: &NamedThresholds<f64>,
+) -> Resource {
+    let resource = Resource::new(name);
+    let output = match parse_plugin_output(output) {
+        Ok(output) => output,
+        Err(err) => {
+            return resource.with_result(
+                CheckResult::new()
+                    .with_state(ServiceState::Unknown)
+                    .with_message(err.to_string()),
+            )
+        }
+    };
+
+    let results = thresholds.names().map(|name| {
+        let Some(perf_string) = output.perf_strings.iter().find(|p| p.label == name) else {
+            return CheckResult::new()
+                .with_state(ServiceState::Unknown)
+                .with_message(format!("metric '{}' not found in output", name));
+        };
+
+        let parse = |v: &str| v.parse::<f64>().ok();
+        let mut metric = Metric::new(name, parse(&perf_string.value).unwrap_or(f64::NAN))
+            .with_unit(perf_string.unit.clone());
+        if let Some(minimum) = parse(&perf_string.minimum) {
+            metric = metric.with_minimum(minimum);
+        }
+        if let Some(maximum) = parse(&perf_string.maximum) {
+            metric = metric.with_maximum(maximum);
+        }
+        thresholds.apply(metric).into()
+    });
+
+    resource.with_results(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Unit;
+
+    #[test]
+    fn test_parse_perf_data() {
+        let perf_strings =
+            parse_perf_data("'used space'=93%;80;90;0;100  load1=0.5 'it''s'=U x=-1.5s;;2")
+                .unwrap();
+        let rendered: Vec<_> = perf_strings.iter().map(|p| p.to_string()).collect();
+        assert_eq!(
+            rendered,
+            [
+                "'used space'=93%;80;90;0;100",
+                "'load1'=0.5;;;;",
+                "'it's'=U;;;;",
+                "'x'=-1.5s;;2;;"
+            ]
+        );
+        assert_eq!(perf_strings[2].label(), "it's");
+        assert_eq!(perf_strings[3].unit(), &Unit::Seconds);
+
+        assert!(parse_perf_data("foo").is_err());
+        assert!(parse_perf_data("'foo=1").is_err());
+        assert!(parse_perf_data("foo=abc").is_err());
+    }
+
+    #[test]
+    fn test_parse_plugin_output() {
+        let output = parse_plugin_output("OK - fine | a=1\nline 1\nline 2 | b=2\nc=3\n").unwrap();
+        assert_eq!(output.summary, "OK - fine");
+        assert_eq!(output.long_output, "line 1\nline 2");
+        assert_eq!(output.perf_strings.len(), 3);
+    }
+
+    #[test]
+    fn test_rethreshold() {
+        let thresholds: NamedThresholds<f64> = ["a=10,20".parse().unwrap(), "b=1".parse().unwrap()]
+            .into_iter()
+            .collect();
+        let resource = rethreshold("foo", "OK|a=15;;;0;100 c=1", &thresholds);
+
+        assert_eq!(
+            resource.nagios_result(),
+            (
+                ServiceState::Warning,
+                "foo is WARNING\n\n\
+                 metric 'a' is WARNING: value '15' is outside the range '10'\n\
+                 metric 'b' not found in output\n\
+                 |'a'=15;10;20;0;100"
+                    .to_owned()
+            )
+        );
+    }
+}
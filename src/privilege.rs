@@ -0,0 +1,178 @@
+//! Helpers for plugins which need root privileges for a single operation, e.g. binding a raw
+//! socket or reading a file only readable by root.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use std::error::Error;
+//!
+//! use nagiosplugin::privilege::run_privileged;
+//! use nagiosplugin::{Resource, Runner};
+//!
+//! fn do_check() -> Result<Resource, Box<dyn Error>> {
+//!     // Runs as root, afterwards the process runs as nagios:nagios for good.
+//!     let shadow = run_privileged("nagios", Some("nagios"), || {
+//!         std::fs::read_to_string("/etc/shadow")
+//!     })?;
+//!
+//!     Ok(Resource::new("shadow").with_description(format!("{} entries", shadow.lines().count())))
+//! }
+//!
+//! fn main() {
+//!     // Errors, including failures to drop privileges, are reported as UNKNOWN by default.
+//!     Runner::new().safe_run(do_check).print_and_exit()
+//! }
+//! ```
+use std::ffi::CString;
+use std::io;
+
+/// This error is returned by [run_privileged].
+#[derive(Debug, thiserror::Error)]
+pub enum PrivilegeError<E> {
+    #[error("privileged operation failed: {0}")]
+    Operation(E),
+    #[error("unknown user '{0}'")]
+    UnknownUser(String),
+    #[error("unknown group '{0}'")]
+    UnknownGroup(String),
+    #[error("failed to drop privileges: {0}")]
+    Drop(io::Error),
+}
+
+/// Runs the given operation and then irreversibly switches the process to the given user and
+/// group. If no group is given, the primary group of the user is used. Supplementary groups are
+/// dropped as well. The privileges are dropped even if the operation failed.
+///
+/// If the process doesn't run as root, it must already run as the target user and group, otherwise
+/// an error is returned.
+pub fn run_privileged<T, E>(
+    user: &str,
+    group: Option<&str>,
+    operation: impl FnOnce() -> Result<T, E>,
+) -> Result<T, PrivilegeError<E>> {
+    let result = operation();
+    drop_privileges(user, group)?;
+    result.map_err(PrivilegeError::Operation)
+}
+
+/// Irreversibly switches the process to the given user and group. See [run_privileged].
+pub fn drop_privileges<E>(user: &str, group: Option<&str>) -> Result<(), PrivilegeError<E>> {
+    let (uid, primary_gid) = lookup_user(user)
+        .map_err(PrivilegeError::Drop)?
+        .ok_or_else(|| PrivilegeError::UnknownUser(user.to_owned()))?;
+    let gid = match group {
+        Some(group) => lookup_group(group)
+            .map_err(PrivilegeError::Drop)?
+            .ok_or_else(|| PrivilegeError::UnknownGroup(group.to_owned()))?,
+        None => primary_gid,
+    };
+
+    // SAFETY: plain system calls without pointers besides the single element group list.
+    unsafe {
+        if libc::geteuid() != 0 {
+            return if libc::getuid() == uid && libc::getgid() == gid && libc::getegid() == gid {
+                Ok(())
+            } else {
+                Err(PrivilegeError::Drop(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "not running as root",
+                )))
+            };
+        }
+
+        // The order matters: the groups can't be changed anymore once the user is switched.
+        if libc::setgroups(1, &gid) != 0 || libc::setgid(gid) != 0 || libc::setuid(uid) != 0 {
+            return Err(PrivilegeError::Drop(io::Error::last_os_error()));
+        }
+
+        if uid != 0 && libc::setuid(0) == 0 {
+            return Err(PrivilegeError::Drop(io::Error::other(
+                "privileges could be regained after dropping them",
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+const BUFFER_SIZE: usize = 16 * 1024;
+
+/// Returns the user id and the primary group id. Numeric ids are accepted as well.
+fn lookup_user(name: &str) -> io::Result<Option<(libc::uid_t, libc::gid_t)>> {
+    let name =
+        CString::new(name).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut passwd = std::mem::MaybeUninit::<libc::passwd>::uninit();
+    let mut result = std::ptr::null_mut();
+
+    // SAFETY: all pointers are valid for the duration of the call and the buffer length is
+    // passed along.
+    let rc = unsafe {
+        libc::getpwnam_r(
+            name.as_ptr(),
+            passwd.as_mut_ptr(),
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        )
+    };
+    if rc != 0 {
+        return Err(io::Error::from_raw_os_error(rc));
+    }
+    if result.is_null() {
+        return Ok(name
+            .to_str()
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .map(|uid| (uid, uid)));
+    }
+
+    // SAFETY: getpwnam_r initialized the struct because it returned a result.
+    let passwd = unsafe { passwd.assume_init() };
+    Ok(Some((passwd.pw_uid, passwd.pw_gid)))
+}
+
+/// Returns the group id. Numeric ids are accepted as well.
+fn lookup_group(name: &str) -> io::Result<Option<libc::gid_t>> {
+    let name =
+        CString::new(name).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut group = std::mem::MaybeUninit::<libc::group>::uninit();
+    let mut result = std::ptr::null_mut();
+
+    // SAFETY: all pointers are valid for the duration of the call and the buffer length is
+    // passed along.
+    let rc = unsafe {
+        libc::getgrnam_r(
+            name.as_ptr(),
+            group.as_mut_ptr(),
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        )
+    };
+    if rc != 0 {
+        return Err(io::Error::from_raw_os_error(rc));
+    }
+    if result.is_null() {
+        return Ok(name.to_str().ok().and_then(|n| n.parse().ok()));
+    }
+
+    // SAFETY: getgrnam_r initialized the struct because it returned a result.
+    let group = unsafe { group.assume_init() };
+    Ok(Some(group.gr_gid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(lookup_user("root").unwrap(), Some((0, 0)));
+        assert_eq!(lookup_user("1234").unwrap(), Some((1234, 1234)));
+        assert_eq!(lookup_user("no-such-user").unwrap(), None);
+        assert_eq!(lookup_group("root").unwrap(), Some(0));
+        assert_eq!(lookup_group("no-such-group").unwrap(), None);
+    }
+}
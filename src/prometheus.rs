@@ -0,0 +1,90 @@
+use crate::{Resource, UNDETERMINED_VALUE};
+
+impl Resource {
+    /// Renders the state and all performance data entries of this resource in the Prometheus text
+    /// exposition format, e.g. for the textfile collector of the node exporter. The state is
+    /// exported as `nagios_state` with the exit code as value, each entry as `nagios_perfdata`
+    /// with the label and the unit as labels. Undetermined values are exported as `NaN`.
+    ///
+    /// The textfile collector may read the file while it is written, so write it to a temporary
+    /// file in the same directory and rename it afterwards.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use nagiosplugin::{Metric, Resource, Unit};
+    ///
+    /// let output = Resource::new("disk")
+    ///     .with_result(Metric::new("root", 93).with_unit(Unit::Percentage))
+    ///     .to_prometheus();
+    ///
+    /// let lines: Vec<_> = output.lines().filter(|l| !l.starts_with('#')).collect();
+    /// assert_eq!(
+    ///     lines,
+    ///     [
+    ///         "nagios_state{resource=\"disk\"} 0",
+    ///         "nagios_perfdata{resource=\"disk\",label=\"root\",unit=\"%\"} 93",
+    ///     ]
+    /// );
+    /// ```
+    pub fn to_prometheus(&self) -> String {
+        let resource = escape_label_value(self.name());
+        let mut output = format!(
+            "# HELP nagios_state State of the check as plugin exit code.\n\
+             # TYPE nagios_state gauge\n\
+             nagios_state{{resource=\"{}\"}} {}\n",
+            resource,
+            self.state().exit_code()
+        );
+
+        let mut perf_strings = self.perf_strings().peekable();
+        if perf_strings.peek().is_some() {
+            output.push_str("# HELP nagios_perfdata Performance data of the check.\n");
+            output.push_str("# TYPE nagios_perfdata gauge\n");
+        }
+        for perf_string in perf_strings {
+            output.push_str(&format!(
+                "nagios_perfdata{{resource=\"{}\",label=\"{}\",unit=\"{}\"}} {}\n",
+                resource,
+                escape_label_value(perf_string.label()),
+                escape_label_value(perf_string.unit().as_str()),
+                prometheus_value(perf_string.value())
+            ));
+        }
+        output
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn prometheus_value(value: &str) -> &str {
+    match value {
+        UNDETERMINED_VALUE => "NaN",
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Metric, Resource};
+
+    #[test]
+    fn test_to_prometheus() {
+        let output = Resource::new("say \"hi\"").to_prometheus();
+        assert!(output.ends_with("nagios_state{resource=\"say \\\"hi\\\"\"} 0\n"));
+        assert!(!output.contains("nagios_perfdata"));
+
+        let output = Resource::new("foo")
+            .with_result(Metric::new("a", f64::NAN))
+            .with_result(Metric::new("b", 1.5))
+            .to_prometheus();
+        assert!(output.contains("nagios_state{resource=\"foo\"} 3\n"));
+        assert!(output.contains("nagios_perfdata{resource=\"foo\",label=\"a\",unit=\"\"} NaN\n"));
+        assert!(output.ends_with("nagios_perfdata{resource=\"foo\",label=\"b\",unit=\"\"} 1.5\n"));
+    }
+}
@@ -0,0 +1,205 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Metric, TriggerIfValue, Unit};
+
+/// A value with a [Unit], e.g. a threshold given as `2GB` on the command line. Quantities are
+/// converted into the unit of a metric before they are compared, see
+/// [Metric::with_unit_thresholds].
+///
+/// Decimal prefixes (`KB`, `MB`, ...) are powers of 1000, binary prefixes (`KiB`, `MiB`, ...)
+/// powers of 1024.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{Quantity, Unit};
+///
+/// let quantity: Quantity = "2GB".parse().unwrap();
+/// assert_eq!(quantity.convert_to(&Unit::Megabytes).unwrap(), 2000.0);
+/// assert!(quantity.convert_to(&Unit::Seconds).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: Unit,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+/// This error is returned by the [FromStr] implementation of [Quantity].
+pub enum QuantityParseError {
+    #[error("expected a number followed by an optional unit, got '{0}'")]
+    InvalidNumber(String),
+    #[error("invalid unit: {0}")]
+    InvalidUnit(#[from] crate::UnitStringCreateError),
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("can't convert '{from}' into '{to}'")]
+/// This error is returned if a [Quantity] can't be converted into the requested unit.
+pub struct UnitConversionError {
+    from: String,
+    to: String,
+}
+
+/// The kinds of units which can be converted into each other.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Dimension {
+    Time,
+    Bytes,
+    Bits,
+    Dimensionless,
+}
+
+impl Unit {
+    /// Returns the dimension and the factor to the base unit of the dimension, or `None` if the
+    /// unit can't be converted.
+    fn scale(&self) -> Option<(Dimension, f64)> {
+        const KI: f64 = 1024.0;
+        let scale = match self {
+            Unit::None => (Dimension::Dimensionless, 1.0),
+            Unit::Seconds => (Dimension::Time, 1.0),
+            Unit::Milliseconds => (Dimension::Time, 1e-3),
+            Unit::Microseconds => (Dimension::Time, 1e-6),
+            Unit::Bytes => (Dimension::Bytes, 1.0),
+            Unit::Kilobytes => (Dimension::Bytes, 1e3),
+            Unit::Megabytes => (Dimension::Bytes, 1e6),
+            Unit::Gigabytes => (Dimension::Bytes, 1e9),
+            Unit::Terabytes => (Dimension::Bytes, 1e12),
+            Unit::Petabytes => (Dimension::Bytes, 1e15),
+            Unit::Kibibytes => (Dimension::Bytes, KI),
+            Unit::Mebibytes => (Dimension::Bytes, KI.powi(2)),
+            Unit::Gibibytes => (Dimension::Bytes, KI.powi(3)),
+            Unit::Tebibytes => (Dimension::Bytes, KI.powi(4)),
+            Unit::Pebibytes => (Dimension::Bytes, KI.powi(5)),
+            Unit::Bits => (Dimension::Bits, 1.0),
+            _ => return None,
+        };
+        Some(scale)
+    }
+}
+
+impl Quantity {
+    pub fn new(value: f64, unit: Unit) -> Self {
+        Self { value, unit }
+    }
+
+    /// Returns the value in the given unit. Units which can't be converted, like [Unit::Other],
+    /// are only compatible with themselves. A quantity without unit is taken as is.
+    pub fn convert_to(&self, unit: &Unit) -> Result<f64, UnitConversionError> {
+        if &self.unit == unit || self.unit == Unit::None {
+            return Ok(self.value);
+        }
+
+        match (self.unit.scale(), unit.scale()) {
+            (Some((from, from_factor)), Some((to, to_factor))) if from == to => {
+                Ok(self.value * from_factor / to_factor)
+            }
+            _ => Err(UnitConversionError {
+                from: self.to_string(),
+                to: unit.as_str().to_owned(),
+            }),
+        }
+    }
+}
+
+impl From<f64> for Quantity {
+    fn from(value: f64) -> Self {
+        Self::new(value, Unit::None)
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.value, self.unit.as_str())
+    }
+}
+
+impl FromStr for Quantity {
+    type Err = QuantityParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+')))
+            .unwrap_or(s.len());
+        let (value, unit) = s.split_at(split);
+
+        let value = value
+            .parse()
+            .map_err(|_| QuantityParseError::InvalidNumber(s.to_owned()))?;
+        Ok(Self::new(value, unit.trim().parse()?))
+    }
+}
+
+impl Metric<f64> {
+    /// Like [Metric::with_thresholds], but the thresholds carry their own unit and are converted
+    /// into the unit of the metric. Therefore the unit of the metric has to be set first. Returns an
+    /// error if a threshold can't be converted, instead of comparing unrelated numbers.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use nagiosplugin::{Metric, Quantity, TriggerIfValue, Unit};
+    ///
+    /// let critical: Quantity = "2GB".parse().unwrap();
+    /// let metric = Metric::new("used", 2500.0)
+    ///     .with_unit(Unit::Megabytes)
+    ///     .with_unit_thresholds(None, critical, TriggerIfValue::Greater)
+    ///     .unwrap();
+    /// ```
+    pub fn with_unit_thresholds(
+        self,
+        warning: impl Into<Option<Quantity>>,
+        critical: impl Into<Option<Quantity>>,
+        trigger_if_value: TriggerIfValue,
+    ) -> Result<Self, UnitConversionError> {
+        let convert = |q: Option<Quantity>| q.map(|q| q.convert_to(&self.unit)).transpose();
+        let warning = convert(warning.into())?;
+        let critical = convert(critical.into())?;
+        Ok(self.with_thresholds(warning, critical, trigger_if_value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckResult, ServiceState};
+
+    #[test]
+    fn test_quantity_parse() {
+        assert_eq!(
+            "2GB".parse::<Quantity>().unwrap(),
+            Quantity::new(2.0, Unit::Gigabytes)
+        );
+        assert_eq!(
+            "1.5 ms".parse::<Quantity>().unwrap(),
+            Quantity::new(1.5, Unit::Milliseconds)
+        );
+        assert_eq!("-3".parse::<Quantity>().unwrap(), Quantity::from(-3.0));
+        assert!("GB".parse::<Quantity>().is_err());
+    }
+
+    #[test]
+    fn test_unit_thresholds() {
+        let quantity = Quantity::new(1.0, Unit::Gibibytes);
+        assert_eq!(quantity.convert_to(&Unit::Mebibytes).unwrap(), 1024.0);
+
+        let result: CheckResult = Metric::new("used", 2500.0)
+            .with_unit(Unit::Megabytes)
+            .with_unit_thresholds(
+                "1GB".parse().ok(),
+                "2GB".parse().ok(),
+                TriggerIfValue::Greater,
+            )
+            .unwrap()
+            .into();
+        assert_eq!(result.state, Some(ServiceState::Critical));
+
+        assert!(Metric::new("time", 1.0)
+            .with_unit(Unit::Seconds)
+            .with_unit_thresholds("2GB".parse().ok(), None, TriggerIfValue::Greater)
+            .is_err());
+    }
+}
@@ -0,0 +1,210 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ToPerfString;
+
+/// A threshold in the range format of the
+/// [Monitoring Plugins guidelines](https://www.monitoring-plugins.org/doc/guidelines.html#THRESHOLDFORMAT),
+/// as passed with `-w` and `-c` by existing command definitions:
+///
+/// | Range    | Alerts if the value is |
+/// |----------|------------------------|
+/// | `10`     | < 0 or > 10            |
+/// | `10:`    | < 10                   |
+/// | `~:10`   | > 10                   |
+/// | `10:20`  | < 10 or > 20           |
+/// | `@10:20` | ≥ 10 and ≤ 20          |
+///
+/// Use it with [crate::Metric::with_threshold_ranges]. With the `clap` feature, arguments can be
+/// parsed with `cli::threshold_parser`.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::ThresholdRange;
+///
+/// let range: ThresholdRange<f64> = "@10:20".parse().unwrap();
+/// assert!(range.is_alerting(&15.0));
+/// assert!(!range.is_alerting(&25.0));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThresholdRange<T> {
+    start: Option<T>,
+    end: Option<T>,
+    inside: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid threshold range '{input}': {reason}")]
+/// This error is returned by the [FromStr] implementation of [ThresholdRange].
+pub struct ThresholdRangeParseError {
+    input: String,
+    reason: String,
+}
+
+impl<T> ThresholdRange<T> {
+    /// Creates a range which alerts if the value is outside of the given bounds. `None` means
+    /// infinity.
+    pub fn outside(start: impl Into<Option<T>>, end: impl Into<Option<T>>) -> Self {
+        Self {
+            start: start.into(),
+            end: end.into(),
+            inside: false,
+        }
+    }
+
+    /// Creates a range which alerts if the value is within the given bounds, like `@10:20`.
+    pub fn inside(start: impl Into<Option<T>>, end: impl Into<Option<T>>) -> Self {
+        Self {
+            start: start.into(),
+            end: end.into(),
+            inside: true,
+        }
+    }
+
+    pub fn start(&self) -> Option<&T> {
+        self.start.as_ref()
+    }
+
+    pub fn end(&self) -> Option<&T> {
+        self.end.as_ref()
+    }
+
+    /// Returns whether the range alerts inside of the bounds, i.e. it starts with `@`.
+    pub fn is_inside(&self) -> bool {
+        self.inside
+    }
+}
+
+impl<T: PartialOrd> ThresholdRange<T> {
+    /// Returns whether the value triggers an alert. Values which can't be compared, like NaN, never
+    /// do.
+    pub fn is_alerting(&self, value: &T) -> bool {
+        if value.partial_cmp(value).is_none() {
+            return false;
+        }
+        let within = self.start.as_ref().is_none_or(|start| value >= start)
+            && self.end.as_ref().is_none_or(|end| value <= end);
+        within == self.inside
+    }
+}
+
+impl<T> FromStr for ThresholdRange<T>
+where
+    T: FromStr + Default + PartialOrd,
+    T::Err: fmt::Display,
+{
+    type Err = ThresholdRangeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |reason: String| ThresholdRangeParseError {
+            input: s.to_owned(),
+            reason,
+        };
+        let parse = |v: &str| v.parse::<T>().map_err(|e| error(e.to_string()));
+
+        let (inside, range) = match s.trim().strip_prefix('@') {
+            Some(range) => (true, range),
+            None => (false, s.trim()),
+        };
+        if range.is_empty() {
+            return Err(error("empty range".to_owned()));
+        }
+
+        let (start, end) = match range.split_once(':') {
+            None => (Some(T::default()), Some(parse(range)?)),
+            Some((start, end)) => {
+                let start = match start {
+                    "~" => None,
+                    "" => Some(T::default()),
+                    start => Some(parse(start)?),
+                };
+                let end = match end {
+                    "" => None,
+                    end => Some(parse(end)?),
+                };
+                (start, end)
+            }
+        };
+
+        if let (Some(start), Some(end)) = (&start, &end) {
+            if start > end {
+                return Err(error("start is greater than end".to_owned()));
+            }
+        }
+
+        Ok(Self { start, end, inside })
+    }
+}
+
+impl<T: ToPerfString> ToPerfString for ThresholdRange<T> {
+    /// Renders the range in the same format, which is also valid in performance data.
+    fn to_perf_string(&self) -> String {
+        let start = self.start.as_ref().map(ToPerfString::to_perf_string);
+        let end = self.end.as_ref().map(ToPerfString::to_perf_string);
+        let range = match (start.as_deref(), end) {
+            (Some("0"), Some(end)) => end,
+            (Some(start), end) => format!("{}:{}", start, end.unwrap_or_default()),
+            (None, end) => format!("~:{}", end.unwrap_or_default()),
+        };
+
+        if self.inside {
+            format!("@{}", range)
+        } else {
+            range
+        }
+    }
+}
+
+impl<T: ToPerfString> fmt::Display for ThresholdRange<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_perf_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let alerting = |range: &str, value: f64| {
+            let range: ThresholdRange<f64> = range.parse().unwrap();
+            range.is_alerting(&value)
+        };
+
+        assert!(alerting("10", -1.0));
+        assert!(!alerting("10", 10.0));
+        assert!(alerting("10", 10.5));
+        assert!(alerting("10:", 9.0));
+        assert!(!alerting("10:", 1e9));
+        assert!(alerting("~:10", 11.0));
+        assert!(!alerting("~:10", -1e9));
+        assert!(alerting("10:20", 21.0));
+        assert!(!alerting("10:20", 20.0));
+        assert!(alerting("@10:20", 10.0));
+        assert!(!alerting("@10:20", 9.9));
+        assert!(!alerting("10", f64::NAN));
+
+        for invalid in ["", "@", "a", "20:10", "1:b"] {
+            assert!(
+                invalid.parse::<ThresholdRange<f64>>().is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_to_perf_string() {
+        for range in ["10", "10:", "~:10", "10:20", "@10:20", "@~:0"] {
+            let parsed: ThresholdRange<i64> = range.parse().unwrap();
+            assert_eq!(parsed.to_perf_string(), range);
+        }
+        assert_eq!(
+            "5:".parse::<ThresholdRange<u8>>().unwrap(),
+            ThresholdRange::outside(5, None)
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use crate::pattern::Pattern;
+use crate::CheckResult;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Rule {
+    Rename(String, String),
+    Replace(Pattern, String),
+}
+
+/// Renames performance data labels before they are rendered, so the labels and with them the
+/// RRD files and graphs of the monitoring system stay the same when the names of the underlying
+/// items change, e.g. after a firmware update renamed the interfaces. Rules are applied in the
+/// order they were added, each to the result of the previous ones. Set it with
+/// [crate::Resource::with_relabel].
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{Metric, Relabel, Resource};
+///
+/// let relabel = Relabel::new()
+///     .with_rename("GigabitEthernet0/1_in", "uplink_in")
+///     .with_replace("^GigabitEthernet".parse().unwrap(), "Gi");
+/// let resource = Resource::new("switch")
+///     .with_relabel(relabel)
+///     .with_result(Metric::new("GigabitEthernet0/1_in", 10))
+///     .with_result(Metric::new("GigabitEthernet0/2_in", 20));
+///
+/// let (_, output) = resource.nagios_result();
+/// assert_eq!(output, "switch is OK|'uplink_in'=10;;;; 'Gi0/2_in'=20;;;;");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Relabel {
+    rules: Vec<Rule>,
+}
+
+impl Relabel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Renames the label if it equals `from`.
+    pub fn with_rename(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.rules.push(Rule::Rename(from.into(), to.into()));
+        self
+    }
+
+    /// Replaces the first match of the pattern in the label with the replacement.
+    pub fn with_replace(mut self, pattern: Pattern, replacement: impl Into<String>) -> Self {
+        self.rules.push(Rule::Replace(pattern, replacement.into()));
+        self
+    }
+
+    pub fn apply(&self, label: &str) -> String {
+        self.rules
+            .iter()
+            .fold(label.to_owned(), |label, rule| match rule {
+                Rule::Rename(from, to) if label == *from => to.clone(),
+                Rule::Rename(..) => label,
+                Rule::Replace(pattern, replacement) => match pattern.find(&label) {
+                    Some((start, end)) => {
+                        format!("{}{}{}", &label[..start], replacement, &label[end..])
+                    }
+                    None => label,
+                },
+            })
+    }
+
+    pub(crate) fn relabel(&self, result: &mut CheckResult) {
+        for perf_string in &mut result.perf_strings {
+            perf_string.label = self.apply(&perf_string.label);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let relabel = Relabel::new()
+            .with_replace("[0-9]+$".parse().unwrap(), "X")
+            .with_rename("eth_X", "uplink");
+        assert_eq!(relabel.apply("eth_12"), "uplink");
+        assert_eq!(relabel.apply("lo0"), "loX");
+        assert_eq!(relabel.apply("bond"), "bond");
+    }
+}
@@ -0,0 +1,877 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Once};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::sink::Sink;
+#[cfg(all(feature = "syslog", unix))]
+use crate::syslog::SyslogLogger;
+use crate::{args, CheckResult, ErrorPolicy, Resource, RunResult, ServiceState};
+
+/// Runs a check with a configurable behaviour on errors and timeouts. [safe_run] behaves like a
+/// runner which only has an error state set, but accepts errors which don't implement
+/// [fmt::Display].
+///
+/// With the `otel` feature, each run is recorded as a span with the final state and the
+/// performance data as attributes. Exporting the spans, e.g. via OTLP, is up to the plugin by
+/// installing a global tracer provider.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::error::Error;
+/// use std::time::Duration;
+///
+/// use nagiosplugin::{Resource, Runner, ServiceState};
+///
+/// fn main() {
+///     Runner::new()
+///         .with_error_state(ServiceState::Critical)
+///         .with_timeout(Duration::from_secs(10))
+///         .safe_run(do_check)
+///         .print_and_exit()
+/// }
+///
+/// fn do_check() -> Result<Resource, Box<dyn Error>> {
+///     Ok(Resource::new("foo"))
+/// }
+/// ```
+///
+/// [safe_run]: crate::safe_run
+#[derive(Debug, Clone)]
+pub struct Runner {
+    error_state: ServiceState,
+    error_policy: ErrorPolicy,
+    timeout: Option<Duration>,
+    sinks: Vec<Arc<dyn Sink + Send + Sync>>,
+    self_metrics: bool,
+    catch_panics: bool,
+    lock: Option<InstanceLock>,
+    #[cfg(all(feature = "syslog", unix))]
+    syslog: Option<SyslogLogger>,
+    #[cfg(all(feature = "signals", unix))]
+    signal_handler: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+/// This error is returned by [Runner::with_timeout_from_args].
+pub enum TimeoutArgError {
+    #[error("missing value for {0}")]
+    MissingValue(String),
+    #[error("invalid timeout '{0}', expected a positive number of seconds")]
+    InvalidValue(String),
+}
+
+impl Runner {
+    /// Creates a runner which reports [ServiceState::Unknown] on errors and has no timeout.
+    pub fn new() -> Self {
+        Self {
+            error_state: ServiceState::Unknown,
+            error_policy: Default::default(),
+            timeout: Default::default(),
+            sinks: Default::default(),
+            self_metrics: Default::default(),
+            catch_panics: Default::default(),
+            lock: Default::default(),
+            #[cfg(all(feature = "syslog", unix))]
+            syslog: Default::default(),
+            #[cfg(all(feature = "signals", unix))]
+            signal_handler: Default::default(),
+        }
+    }
+
+    /// Sets the state which is reported if the check returns an error.
+    pub fn with_error_state(mut self, state: ServiceState) -> Self {
+        self.error_state = state;
+        self
+    }
+
+    /// Reports errors matching a rule of the given policy with the state of the rule instead of
+    /// the error state, e.g. to soften known transient failures.
+    pub fn with_error_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.error_policy = policy;
+        self
+    }
+
+    /// If the check takes longer than the given timeout, the process prints an UNKNOWN message
+    /// and exits with the corresponding exit code, like the classic plugins do on `-t`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout from `-t` or `--timeout`, the usual option of plugins, in seconds. The
+    /// value is either the next argument or separated by `=`, the last one given wins. Arguments
+    /// after `--` and the values of the other standard plugin options like `-w` are skipped, as in
+    /// [crate::net::NetOptions::from_args]. Without the option, the timeout is left as is.
+    pub fn with_timeout_from_args<I, S>(mut self, args: I) -> Result<Self, TimeoutArgError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let options = args::Options {
+            values: &["-t", "--timeout"],
+            ..Default::default()
+        };
+        let found = args::scan(args, options).map_err(TimeoutArgError::MissingValue)?;
+
+        for (_, value) in found {
+            let value = value.unwrap_or_default();
+            let seconds = value
+                .parse::<f64>()
+                .ok()
+                .filter(|s| s.is_finite() && *s > 0.0)
+                .ok_or(TimeoutArgError::InvalidValue(value))?;
+            self.timeout = Some(Duration::from_secs_f64(seconds));
+        }
+
+        Ok(self)
+    }
+
+    /// Adds a sink which receives the [Resource] once the check has completed successfully.
+    pub fn with_sink(mut self, sink: impl Sink + Send + Sync + 'static) -> Self {
+        self.sinks.push(Arc::new(sink));
+        self
+    }
+
+    /// Appends metrics about the plugin process itself to the [Resource]: the runtime of the
+    /// check and, where `/proc` is available, the peak memory usage and the number of open file
+    /// descriptors. Helps to find checks which slowly degrade the monitoring host.
+    pub fn with_self_metrics(mut self) -> Self {
+        self.self_metrics = true;
+        self
+    }
+
+    /// Catches panics of the check in [Runner::safe_run] and [Runner::safe_run_async] and reports
+    /// them with the error state, e.g. `UNKNOWN: panicked at src/main.rs:10:5: index out of
+    /// bounds`, instead of exiting with code 101, which monitoring systems misinterpret.
+    pub fn catch_panics(mut self) -> Self {
+        self.catch_panics = true;
+        self
+    }
+
+    /// Takes an exclusive lock on the given file before running the check. If another instance
+    /// still holds the lock, the process prints the given state and message and exits right away
+    /// instead of piling up behind the slow instance.
+    pub fn with_single_instance_lock(
+        mut self,
+        path: impl Into<PathBuf>,
+        state: ServiceState,
+        message: impl Into<String>,
+    ) -> Self {
+        self.lock = Some(InstanceLock {
+            path: path.into(),
+            state,
+            message: message.into(),
+        });
+        self
+    }
+
+    /// Logs the start and the end of each run, including the duration, the resulting state and
+    /// errors, to syslog / journald with the given identifier.
+    #[cfg(all(feature = "syslog", unix))]
+    pub fn with_syslog(mut self, identifier: impl Into<String>) -> Self {
+        self.syslog = Some(SyslogLogger::new(identifier.into()));
+        self
+    }
+
+    /// Prints `UNKNOWN: check interrupted` and exits with code 3 if the process receives SIGTERM or
+    /// SIGINT while the check runs, e.g. when the monitoring system kills it after its timeout.
+    /// Performance data passed to [crate::signal::set_partial_result] is included.
+    #[cfg(all(feature = "signals", unix))]
+    pub fn with_signal_handler(mut self) -> Self {
+        self.signal_handler = true;
+        self
+    }
+
+    /// Runs the given check and returns a [RunResult] which can be used to print and exit.
+    pub fn safe_run<E: fmt::Display>(
+        &self,
+        f: impl FnOnce() -> Result<Resource, E>,
+    ) -> RunResult<E> {
+        self.guarded(|| self.execute(f))
+    }
+
+    /// Like [Runner::safe_run], but runs the check up to `count` more times, waiting `delay`
+    /// between the attempts, as long as it returns an error. Only the error of the last attempt is
+    /// reported. If a retry succeeds, the output gets a line with the number of attempts, e.g.
+    /// `succeeded after 2 of 4 attempts`. The timeout set with [Runner::with_timeout] covers all
+    /// attempts.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::error::Error;
+    /// use std::time::Duration;
+    ///
+    /// use nagiosplugin::{Resource, Runner};
+    ///
+    /// fn main() {
+    ///     Runner::new()
+    ///         .safe_run_with_retries(2, Duration::from_secs(1), do_check)
+    ///         .print_and_exit()
+    /// }
+    ///
+    /// fn do_check() -> Result<Resource, Box<dyn Error>> {
+    ///     Ok(Resource::new("foo"))
+    /// }
+    /// ```
+    pub fn safe_run_with_retries<E: fmt::Display>(
+        &self,
+        count: u32,
+        delay: Duration,
+        f: impl FnMut() -> Result<Resource, E>,
+    ) -> RunResult<E> {
+        self.guarded(|| self.execute(|| self.retry(count, delay, f)))
+    }
+
+    /// Like [Runner::safe_run], for checks which are futures. It doesn't depend on a specific
+    /// runtime, the returned future is awaited on the runtime the plugin already uses, e.g. in an
+    /// async `main`.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::error::Error;
+    ///
+    /// use nagiosplugin::{Resource, Runner};
+    ///
+    /// # async fn example() {
+    /// Runner::new()
+    ///     .safe_run_async(do_check)
+    ///     .await
+    ///     .print_and_exit()
+    /// # }
+    ///
+    /// async fn do_check() -> Result<Resource, Box<dyn Error>> {
+    ///     Ok(Resource::new("foo"))
+    /// }
+    /// ```
+    pub async fn safe_run_async<E: fmt::Display, F: Future<Output = Result<Resource, E>>>(
+        &self,
+        f: impl FnOnce() -> F,
+    ) -> RunResult<E> {
+        let _lock = self.lock.as_ref().map(InstanceLock::acquire_or_exit);
+        self.install_signal_handler();
+        let _watchdog = self.timeout.map(spawn_watchdog);
+
+        let start = self.begin();
+
+        #[cfg(feature = "otel")]
+        let future = crate::otel::in_span_async("check".to_owned(), self.error_state, f());
+        #[cfg(not(feature = "otel"))]
+        let future = f();
+
+        let mut future = std::pin::pin!(future);
+        let result = std::future::poll_fn(|cx| self.catch_panic(|| future.as_mut().poll(cx))).await;
+
+        self.finish(start, result)
+    }
+
+    /// Turns the plugin into a lightweight agent: runs the check every `interval` and passes each
+    /// result to the sinks instead of printing it. Failed runs are passed as a [Resource] with the
+    /// given name, the error state and the error as description, and the check is run again at
+    /// the next interval. The timeout set with [Runner::with_timeout] doesn't apply, as it would
+    /// end the process. Never returns.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// use std::error::Error;
+    /// use std::time::Duration;
+    ///
+    /// use nagiosplugin::sink::NdjsonFileSink;
+    /// use nagiosplugin::{Resource, Runner};
+    ///
+    /// fn do_check() -> Result<Resource, Box<dyn Error>> {
+    ///     Ok(Resource::new("foo"))
+    /// }
+    ///
+    /// fn main() {
+    ///     Runner::new()
+    ///         .with_sink(NdjsonFileSink::new("/var/spool/check_foo.ndjson"))
+    ///         .run_every("foo", Duration::from_secs(60), do_check)
+    /// }
+    /// ```
+    pub fn run_every<E: fmt::Display>(
+        &self,
+        name: &str,
+        interval: Duration,
+        mut f: impl FnMut() -> Result<Resource, E>,
+    ) -> ! {
+        loop {
+            let start = Instant::now();
+            self.run_passive(name, &mut f);
+            thread::sleep(interval.saturating_sub(start.elapsed()));
+        }
+    }
+
+    /// Runs the check once and passes the result to the sinks, even if the check failed.
+    fn run_passive<E: fmt::Display>(&self, name: &str, f: impl FnOnce() -> Result<Resource, E>) {
+        if let RunResult::Err(state, err) = self.execute(f) {
+            let resource = Resource::new(name)
+                .with_fixed_state(state)
+                .with_description(err.to_string());
+            self.submit(&resource);
+        }
+    }
+
+    /// Takes the lock, installs the signal handler and starts the watchdog for the duration of
+    /// `f`, catching panics if configured.
+    fn guarded<T>(&self, f: impl FnOnce() -> T) -> T {
+        // The lock is released when the file is closed at the end of this function.
+        let _lock = self.lock.as_ref().map(InstanceLock::acquire_or_exit);
+        self.install_signal_handler();
+
+        // The watchdog stops as soon as the sender is dropped at the end of this function.
+        let _watchdog = self.timeout.map(spawn_watchdog);
+
+        self.catch_panic(f)
+    }
+
+    fn execute<E: fmt::Display>(&self, f: impl FnOnce() -> Result<Resource, E>) -> RunResult<E> {
+        let start = self.begin();
+
+        #[cfg(feature = "otel")]
+        let result = crate::otel::in_span("check".to_owned(), self.error_state, f);
+        #[cfg(not(feature = "otel"))]
+        let result = f();
+
+        self.finish(start, result)
+    }
+
+    /// Runs the check until it succeeds or all retries failed.
+    fn retry<E: fmt::Display>(
+        &self,
+        retries: u32,
+        delay: Duration,
+        mut f: impl FnMut() -> Result<Resource, E>,
+    ) -> Result<Resource, E> {
+        let mut attempt = 1;
+        loop {
+            match f() {
+                Ok(mut resource) => {
+                    if attempt > 1 {
+                        resource.push_result(CheckResult::new().with_message(format!(
+                            "succeeded after {} of {} attempts",
+                            attempt,
+                            retries + 1
+                        )));
+                    }
+                    return Ok(resource);
+                }
+                Err(err) if attempt <= retries => {
+                    self.log(None, || format!("attempt {} failed error={}", attempt, err));
+                    thread::sleep(delay);
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn begin(&self) -> Instant {
+        self.log(None, || "check started".to_owned());
+        Instant::now()
+    }
+
+    fn finish<E: fmt::Display>(&self, start: Instant, result: Result<Resource, E>) -> RunResult<E> {
+        let result = match result {
+            Ok(mut resource) => {
+                if self.self_metrics {
+                    for result in crate::self_metrics::collect(start.elapsed()) {
+                        resource.push_result(result);
+                    }
+                }
+                RunResult::Ok(resource)
+            }
+            Err(err) => {
+                let state = self
+                    .error_policy
+                    .state_for(&err.to_string())
+                    .unwrap_or(self.error_state);
+                RunResult::Err(state, err)
+            }
+        };
+
+        let duration = start.elapsed().as_secs_f64();
+        match &result {
+            RunResult::Ok(resource) => {
+                let state = resource.state();
+                self.log(Some(state), || {
+                    format!("check finished state={} duration={}s", state, duration)
+                });
+
+                self.submit(resource);
+            }
+            RunResult::Err(state, err) => self.log(Some(*state), || {
+                format!(
+                    "check failed state={} duration={}s error={}",
+                    state, duration, err
+                )
+            }),
+        }
+
+        result
+    }
+
+    /// Runs `f` and, if panics are caught, prints the panic with the error state and exits if it
+    /// panicked.
+    fn catch_panic<T>(&self, f: impl FnOnce() -> T) -> T {
+        if !self.catch_panics {
+            return f();
+        }
+
+        match catch_panic_message(f) {
+            Ok(value) => value,
+            Err(message) => {
+                self.log(Some(self.error_state), || {
+                    format!("check failed state={} error={}", self.error_state, message)
+                });
+                println!("{}: {}", self.error_state, message);
+                std::process::exit(self.error_state.exit_code());
+            }
+        }
+    }
+
+    fn install_signal_handler(&self) {
+        #[cfg(all(feature = "signals", unix))]
+        if self.signal_handler {
+            if let Err(err) = crate::signal::install() {
+                eprintln!("failed to install signal handler: {}", err);
+            }
+        }
+    }
+
+    fn submit(&self, resource: &Resource) {
+        for sink in &self.sinks {
+            if let Err(err) = sink.submit(resource) {
+                eprintln!("failed to submit result to {}: {}", sink.name(), err);
+            }
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn log(&self, state: Option<ServiceState>, message: impl FnOnce() -> String) {
+        #[cfg(all(feature = "syslog", unix))]
+        if let Some(syslog) = &self.syslog {
+            syslog.log(state, &message());
+        }
+    }
+}
+
+impl Default for Runner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct InstanceLock {
+    path: PathBuf,
+    state: ServiceState,
+    message: String,
+}
+
+impl InstanceLock {
+    /// Returns the locked file, or `None` if another instance holds the lock.
+    fn acquire(&self) -> std::io::Result<Option<File>> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&self.path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(file)),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(err)) => Err(err),
+        }
+    }
+
+    fn acquire_or_exit(&self) -> File {
+        let (state, message) = match self.acquire() {
+            Ok(Some(file)) => return file,
+            Ok(None) => (self.state, self.message.clone()),
+            Err(err) => (
+                ServiceState::Unknown,
+                format!("failed to lock {}: {}", self.path.display(), err),
+            ),
+        };
+        println!("{}: {}", state, message);
+        std::process::exit(state.exit_code());
+    }
+}
+
+thread_local! {
+    /// The message of the last panic on this thread while it is caught by [catch_panic_message], or
+    /// `None` if panics aren't caught.
+    static PANIC_MESSAGE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` and returns the message of its panic, e.g. `panicked at src/main.rs:10:5: woops`.
+/// The message isn't printed to stderr, panics of other threads are passed to the previous hook.
+fn catch_panic_message<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let caught = PANIC_MESSAGE.with_borrow_mut(|message| match message {
+                Some(message) => {
+                    *message = panic_message(info);
+                    true
+                }
+                None => false,
+            });
+            if !caught {
+                previous(info);
+            }
+        }));
+    });
+
+    let outer = PANIC_MESSAGE.replace(Some(String::new()));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    let message = PANIC_MESSAGE.replace(outer).unwrap_or_default();
+    result.map_err(|_| message)
+}
+
+fn panic_message(info: &panic::PanicHookInfo) -> String {
+    let payload = info
+        .payload()
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| info.payload().downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+    match info.location() {
+        Some(location) => format!("panicked at {}: {}", location, payload),
+        None => format!("panicked: {}", payload),
+    }
+}
+
+fn spawn_watchdog(timeout: Duration) -> mpsc::Sender<()> {
+    let (tx, rx) = mpsc::channel::<()>();
+    thread::spawn(move || {
+        if let Err(mpsc::RecvTimeoutError::Timeout) = rx.recv_timeout(timeout) {
+            let state = ServiceState::Unknown;
+            println!(
+                "{}: check timed out after {} seconds",
+                state,
+                timeout.as_secs_f64()
+            );
+            std::process::exit(state.exit_code());
+        }
+    });
+    tx
+}
+
+/// Generates the `main` function of a plugin which runs the given check function with a [Runner]
+/// and prints the result. The settings are applied in the given order:
+///
+/// | Setting                 | Effect                                                          |
+/// |-------------------------|-----------------------------------------------------------------|
+/// | `error_state = <state>` | [Runner::with_error_state]                                      |
+/// | `timeout = <duration>`  | [Runner::with_timeout]                                          |
+/// | `timeout_flag`          | [Runner::with_timeout_from_args], invalid values exit UNKNOWN   |
+/// | `icinga_config = <cmd>` | prints the [CommandDescription] on `--generate-icinga-config`   |
+/// | `completions = <cmd>`   | with the `clap` feature, see `cli::write_requested_completions` |
+///
+/// The expressions of `icinga_config` and `completions` are only evaluated when needed.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::error::Error;
+/// use std::time::Duration;
+///
+/// use nagiosplugin::config_generator::{Argument, CommandDescription};
+/// use nagiosplugin::{plugin_main, Resource, ServiceState};
+///
+/// plugin_main!(
+///     do_check,
+///     error_state = ServiceState::Critical,
+///     timeout = Duration::from_secs(10),
+///     timeout_flag,
+///     icinga_config = CommandDescription::new("check_foo")
+///         .with_argument(Argument::new("--timeout").with_value("$foo_timeout$")),
+/// );
+///
+/// fn do_check() -> Result<Resource, Box<dyn Error>> {
+///     Ok(Resource::new("foo"))
+/// }
+/// ```
+///
+/// [CommandDescription]: crate::config_generator::CommandDescription
+#[macro_export]
+macro_rules! plugin_main {
+    (@setup $runner:ident; $(,)?) => {};
+    (@setup $runner:ident; error_state = $v:expr $(, $($rest:tt)*)?) => {
+        let $runner = $runner.with_error_state($v);
+        $crate::plugin_main!(@setup $runner; $($($rest)*)?);
+    };
+    (@setup $runner:ident; timeout = $v:expr $(, $($rest:tt)*)?) => {
+        let $runner = $runner.with_timeout($v);
+        $crate::plugin_main!(@setup $runner; $($($rest)*)?);
+    };
+    (@setup $runner:ident; timeout_flag $(, $($rest:tt)*)?) => {
+        let $runner = match $runner.with_timeout_from_args(::std::env::args().skip(1)) {
+            Ok(runner) => runner,
+            Err(err) => {
+                let state = $crate::ServiceState::Unknown;
+                println!("{}: {}", state, err);
+                ::std::process::exit(state.exit_code());
+            }
+        };
+        $crate::plugin_main!(@setup $runner; $($($rest)*)?);
+    };
+    (@setup $runner:ident; icinga_config = $v:expr $(, $($rest:tt)*)?) => {
+        if $crate::config_generator::is_requested(::std::env::args().skip(1)) {
+            print!("{}", $v);
+            return;
+        }
+        $crate::plugin_main!(@setup $runner; $($($rest)*)?);
+    };
+    (@setup $runner:ident; completions = $v:expr $(, $($rest:tt)*)?) => {
+        // Other arguments are left to the check, which reports invalid ones itself.
+        if $crate::cli::requested_shell(::std::env::args().skip(1)).is_some()
+            && $crate::cli::write_requested_completions(
+                &mut $crate::cli::with_completions($v),
+                ::std::env::args().skip(1),
+                &mut ::std::io::stdout(),
+            )
+        {
+            return;
+        }
+        $crate::plugin_main!(@setup $runner; $($($rest)*)?);
+    };
+
+    ($check:path $(, $($rest:tt)*)?) => {
+        fn main() {
+            let runner = $crate::Runner::new();
+            $crate::plugin_main!(@setup runner; $($($rest)*)?);
+            runner.safe_run($check).print_and_exit()
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct RecordingSink(Mutex<Vec<(String, ServiceState)>>);
+
+    impl Sink for Arc<RecordingSink> {
+        fn submit(&self, resource: &Resource) -> std::io::Result<()> {
+            let entry = (resource.name().to_owned(), resource.state());
+            self.0.lock().unwrap().push(entry);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_instance_lock() {
+        let path = std::env::temp_dir().join(format!("nagiosplugin-{}.lock", std::process::id()));
+        let lock = InstanceLock {
+            path: path.clone(),
+            state: ServiceState::Warning,
+            message: "still running".to_owned(),
+        };
+
+        let first = lock.acquire().unwrap();
+        assert!(first.is_some());
+        assert!(lock.acquire().unwrap().is_none());
+
+        drop(first);
+        assert!(lock.acquire().unwrap().is_some());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_runner_run_passive() {
+        let sink = Arc::new(RecordingSink::default());
+        let runner = Runner::new()
+            .with_error_state(ServiceState::Critical)
+            .with_sink(sink.clone());
+
+        runner.run_passive("foo", || Ok::<_, &str>(Resource::new("bar")));
+        runner.run_passive("foo", || Err("woops"));
+
+        assert_eq!(
+            *sink.0.lock().unwrap(),
+            vec![
+                ("bar".to_owned(), ServiceState::Ok),
+                ("foo".to_owned(), ServiceState::Critical)
+            ]
+        );
+    }
+
+    // Only checks that all settings of the macro expand to valid code.
+    #[cfg(feature = "clap")]
+    #[allow(dead_code)]
+    mod generated_main {
+        use crate::config_generator::CommandDescription;
+        use crate::{Resource, ServiceState};
+
+        fn do_check() -> Result<Resource, &'static str> {
+            Ok(Resource::new("foo"))
+        }
+
+        crate::plugin_main!(
+            do_check,
+            error_state = ServiceState::Critical,
+            timeout_flag,
+            icinga_config = CommandDescription::new("check_foo"),
+            completions = clap::Command::new("check_foo"),
+        );
+    }
+
+    #[test]
+    fn test_runner_timeout_from_args() {
+        let timeout = |args: &[&str]| {
+            Runner::new()
+                .with_timeout(Duration::from_secs(10))
+                .with_timeout_from_args(args)
+                .map(|runner| runner.timeout)
+        };
+
+        assert_eq!(timeout(&["-v"]).unwrap(), Some(Duration::from_secs(10)));
+        assert_eq!(timeout(&["-t", "5"]).unwrap(), Some(Duration::from_secs(5)));
+        assert_eq!(
+            timeout(&["--timeout=0.5"]).unwrap(),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            timeout(&["--", "-t", "5"]).unwrap(),
+            Some(Duration::from_secs(10))
+        );
+        assert!(matches!(
+            timeout(&["-t", "-1"]),
+            Err(TimeoutArgError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            timeout(&["--timeout"]),
+            Err(TimeoutArgError::MissingValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_runner_error_state() {
+        let result = Runner::new()
+            .with_error_state(ServiceState::Warning)
+            .with_timeout(Duration::from_secs(60))
+            .safe_run(|| Err::<Resource, _>("woops"));
+
+        assert!(matches!(
+            result,
+            RunResult::Err(ServiceState::Warning, "woops")
+        ));
+    }
+
+    #[test]
+    fn test_runner_retries() {
+        let runner = Runner::new();
+
+        let mut attempts = 0;
+        let result = runner.safe_run_with_retries(2, Duration::ZERO, || {
+            attempts += 1;
+            match attempts {
+                1 => Err("woops"),
+                _ => Ok(Resource::new("foo")),
+            }
+        });
+        let RunResult::Ok(resource) = result else {
+            panic!("expected a resource");
+        };
+        assert_eq!(
+            resource.nagios_result().1,
+            "foo is OK\n\nsucceeded after 2 of 3 attempts\n"
+        );
+
+        let mut attempts = 0;
+        let result = runner.safe_run_with_retries(2, Duration::ZERO, || {
+            attempts += 1;
+            Err::<Resource, _>(attempts)
+        });
+        assert!(matches!(result, RunResult::Err(ServiceState::Unknown, 3)));
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        use std::task::{Context, Poll, Wake, Waker};
+
+        struct ThreadWaker(thread::Thread);
+
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_runner_safe_run_async() {
+        let sink = Arc::new(RecordingSink::default());
+        let runner = Runner::new()
+            .with_error_state(ServiceState::Critical)
+            .with_sink(sink.clone());
+
+        let result =
+            block_on(runner.safe_run_async(|| async { Ok::<_, &str>(Resource::new("foo")) }));
+        assert!(matches!(result, RunResult::Ok(_)));
+        let result = block_on(runner.safe_run_async(|| async { Err::<Resource, _>("woops") }));
+        assert!(matches!(
+            result,
+            RunResult::Err(ServiceState::Critical, "woops")
+        ));
+
+        assert_eq!(
+            *sink.0.lock().unwrap(),
+            vec![("foo".to_owned(), ServiceState::Ok)]
+        );
+    }
+
+    #[test]
+    fn test_catch_panic() {
+        assert_eq!(catch_panic_message(|| 1), Ok(1));
+
+        let message = catch_panic_message(|| panic!("woops {}", 1)).unwrap_err();
+        assert!(
+            message.starts_with("panicked at src/runner.rs:"),
+            "{}",
+            message
+        );
+        assert!(message.ends_with(": woops 1"), "{}", message);
+    }
+
+    #[test]
+    fn test_runner_error_policy() {
+        let runner = Runner::new()
+            .with_error_state(ServiceState::Critical)
+            .with_error_policy("connection-refused=warning".parse().unwrap());
+
+        assert!(matches!(
+            runner.safe_run(|| Err::<Resource, _>("Connection refused (os error 111)")),
+            RunResult::Err(ServiceState::Warning, _)
+        ));
+        assert!(matches!(
+            runner.safe_run(|| Err::<Resource, _>("woops")),
+            RunResult::Err(ServiceState::Critical, _)
+        ));
+    }
+}
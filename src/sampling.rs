@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use crate::{Metric, Unit};
+
+/// Calls the measurement `n` times, waiting `interval` between the calls, and collects the
+/// values and errors. Failed samples don't abort the measurement, so a single lost packet or
+/// timed out request is reported as partial failure instead of a failed check.
+///
+/// ## Example
+///
+/// ```
+/// use std::time::Duration;
+/// use nagiosplugin::{sample_n, Resource, TriggerIfValue, Unit};
+///
+/// let mut rtts = [Ok(12.0), Err("timeout"), Ok(15.0)].into_iter();
+/// let samples = sample_n(3, Duration::ZERO, || rtts.next().unwrap());
+///
+/// let [min, avg, max] = samples.metrics("rta", Unit::Milliseconds);
+/// let resource = Resource::new("ping")
+///     .with_result(min)
+///     .with_result(avg.with_thresholds(100.0, 500.0, TriggerIfValue::Greater))
+///     .with_result(max);
+///
+/// assert_eq!(samples.errors(), &["timeout"]);
+/// assert_eq!(samples.failure_percentage(), 100.0 / 3.0);
+/// ```
+pub fn sample_n<E>(
+    n: usize,
+    interval: Duration,
+    mut f: impl FnMut() -> Result<f64, E>,
+) -> Samples<E> {
+    let mut samples = Samples {
+        values: Vec::with_capacity(n),
+        errors: Vec::new(),
+    };
+
+    for i in 0..n {
+        if i > 0 && !interval.is_zero() {
+            std::thread::sleep(interval);
+        }
+        match f() {
+            Ok(value) if !value.is_nan() => samples.values.push(value),
+            Ok(_) => {}
+            Err(err) => samples.errors.push(err),
+        }
+    }
+
+    samples
+}
+
+/// The values and errors collected by [sample_n].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Samples<E> {
+    values: Vec<f64>,
+    errors: Vec<E>,
+}
+
+impl<E> Samples<E> {
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    pub fn errors(&self) -> &[E] {
+        &self.errors
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.values.iter().copied().reduce(f64::min)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.values.iter().copied().reduce(f64::max)
+    }
+
+    pub fn avg(&self) -> Option<f64> {
+        match self.values.len() {
+            0 => None,
+            n => Some(self.values.iter().sum::<f64>() / n as f64),
+        }
+    }
+
+    /// Returns the percentage of failed samples, like the packet loss of a ping.
+    pub fn failure_percentage(&self) -> f64 {
+        match self.values.len() + self.errors.len() {
+            0 => 0.0,
+            n => self.errors.len() as f64 * 100.0 / n as f64,
+        }
+    }
+
+    /// Returns the metrics `<name>_min`, `<name>_avg` and `<name>_max`. If all samples failed,
+    /// their values are not a number.
+    pub fn metrics(&self, name: &str, unit: Unit) -> [Metric<f64>; 3] {
+        let metric = |suffix: &str, value: Option<f64>| {
+            Metric::new(format!("{}_{}", name, suffix), value.unwrap_or(f64::NAN))
+                .with_unit(unit.clone())
+        };
+        [
+            metric("min", self.min()),
+            metric("avg", self.avg()),
+            metric("max", self.max()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_n() {
+        let mut calls = 0;
+        let samples = sample_n(4, Duration::from_millis(1), || {
+            calls += 1;
+            match calls {
+                2 => Err("failed"),
+                n => Ok(n as f64),
+            }
+        });
+
+        assert_eq!(calls, 4);
+        assert_eq!(samples.values(), [1.0, 3.0, 4.0]);
+        assert_eq!((samples.min(), samples.max()), (Some(1.0), Some(4.0)));
+        assert_eq!(samples.avg(), Some(8.0 / 3.0));
+        assert_eq!(samples.failure_percentage(), 25.0);
+
+        let samples = sample_n(2, Duration::ZERO, || Err::<f64, _>("failed"));
+        let [min, _, _] = samples.metrics("rta", Unit::Milliseconds);
+        assert!(min.value.is_nan());
+        assert_eq!(min.name, "rta_min");
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{compiler_fence, Ordering};
+
+use crate::args;
+
+const REDACTED: &str = "********";
+
+/// A password or token of a plugin. The value is only accessible through [Secret::expose], all
+/// other ways of printing it, like [fmt::Debug] and [fmt::Display], show a placeholder instead, so
+/// it doesn't end up in the plugin output, generated config, debug dumps or error messages. The
+/// whole allocation is overwritten with zeros when the secret is dropped. There is no [PartialEq],
+/// as comparing secrets should be done in constant time; compare [Secret::expose] explicitly if
+/// needed.
+///
+/// ## Example
+///
+/// ```no_run
+/// use nagiosplugin::Secret;
+///
+/// let password = Secret::from_args(std::env::args(), Some("CHECK_FOO_PASSWORD"))
+///     .unwrap()
+///     .expect("no password given");
+/// assert_eq!(password.to_string(), "********");
+/// ```
+#[derive(Clone)]
+pub struct Secret(String);
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+/// This error is returned if a [Secret] can't be read. It never contains the secret itself.
+pub enum SecretError {
+    #[error("failed to read secret from '{0}': {1}")]
+    Io(PathBuf, #[source] std::io::Error),
+    #[error("missing value for {0}")]
+    MissingValue(String),
+}
+
+impl Secret {
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self(secret.into())
+    }
+
+    /// Reads the secret from the first line of the given file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, SecretError> {
+        let path = path.as_ref();
+        // Wrapped as well, so the rest of the file is zeroed too.
+        let contents = Secret(
+            std::fs::read_to_string(path).map_err(|err| SecretError::Io(path.to_owned(), err))?,
+        );
+        Ok(Self::new(contents.0.lines().next().unwrap_or_default()))
+    }
+
+    /// Reads the secret from the given environment variable.
+    pub fn from_env(name: &str) -> Option<Self> {
+        std::env::var(name).ok().map(Self)
+    }
+
+    /// Reads the secret from `--password` or `--password-file`, either followed by the value as
+    /// next argument or separated by `=`. The last one given wins. Without both, the given
+    /// environment variable is used, if any. Arguments after `--` and the values of the standard
+    /// plugin options like `-w` are skipped, as in [crate::net::NetOptions::from_args].
+    ///
+    /// Passwords on the command line are visible to other users of the host, so prefer the file
+    /// or the environment variable.
+    pub fn from_args<I, S>(args: I, env: Option<&str>) -> Result<Option<Self>, SecretError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let options = args::Options {
+            values: &["--password", "--password-file"],
+            ..Default::default()
+        };
+        let found = args::scan(args, options).map_err(SecretError::MissingValue)?;
+
+        let mut secret = None;
+        for (key, value) in found {
+            let value = value.unwrap_or_default();
+            secret = Some(match key.as_str() {
+                "--password" => Self(value),
+                _ => Self::from_file(value)?,
+            });
+        }
+
+        Ok(secret.or_else(|| env.and_then(Self::from_env)))
+    }
+
+    /// Returns the secret itself, e.g. to pass it to a client library.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({})", REDACTED)
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        let mut bytes = std::mem::take(&mut self.0).into_bytes();
+        bytes.fill(0);
+        // The spare capacity may still hold parts of the secret, e.g. from reading it. Growing
+        // within the capacity never reallocates.
+        bytes.resize(bytes.capacity(), 0);
+        // Keeps the writes from being optimized away, even though the memory is freed afterwards.
+        std::hint::black_box(&mut bytes);
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacted() {
+        let secret = Secret::new("hunter2");
+        assert_eq!(secret.expose(), "hunter2");
+        assert_eq!(secret.to_string(), "********");
+        assert_eq!(format!("{:?}", secret), "Secret(********)");
+    }
+
+    #[test]
+    fn test_from_args() {
+        let path = std::env::temp_dir().join(format!("nagiosplugin-secret-{}", std::process::id()));
+        std::fs::write(&path, "from-file\n").unwrap();
+
+        let secret = Secret::from_args(["check", "--password", "a"], None).unwrap();
+        assert_eq!(secret.unwrap().expose(), "a");
+
+        let file_arg = format!("--password-file={}", path.display());
+        let secret = Secret::from_args(["check", file_arg.as_str()], None).unwrap();
+        assert_eq!(secret.unwrap().expose(), "from-file");
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(Secret::from_args(["check"], None).unwrap().is_none());
+        assert!(matches!(
+            Secret::from_args(["check", "--password"], None),
+            Err(SecretError::MissingValue(_))
+        ));
+    }
+}
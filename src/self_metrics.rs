@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use crate::{CheckResult, Metric, Unit};
+
+/// Returns metrics about the plugin process itself. The peak memory usage and the number of open
+/// file descriptors are read from `/proc` and are left out where it isn't available.
+pub(crate) fn collect(runtime: Duration) -> Vec<CheckResult> {
+    let mut results = vec![Metric::new("plugin_runtime", runtime.as_secs_f64())
+        .with_unit(Unit::Seconds)
+        .into()];
+
+    if let Some(peak_rss) = peak_rss() {
+        results.push(
+            Metric::new("plugin_peak_rss", peak_rss)
+                .with_unit(Unit::Bytes)
+                .into(),
+        );
+    }
+
+    if let Some(open_fds) = open_fds() {
+        results.push(Metric::new("plugin_open_fds", open_fds).into());
+    }
+
+    results
+}
+
+/// Peak resident set size in bytes.
+fn peak_rss() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kilobytes = line
+        .trim_start_matches("VmHWM:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+fn open_fds() -> Option<usize> {
+    // The directory handle used for reading is counted as well.
+    std::fs::read_dir("/proc/self/fd")
+        .ok()
+        .map(|dir| dir.count().saturating_sub(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect() {
+        let results = collect(Duration::from_millis(1500));
+        let runtime = &results[0].perf_strings[0];
+        assert_eq!(runtime.to_string(), "'plugin_runtime'=1.5s;;;;");
+
+        if cfg!(target_os = "linux") {
+            assert_eq!(results.len(), 3);
+        }
+    }
+}
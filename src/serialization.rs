@@ -0,0 +1,98 @@
+//! `Serialize` and `Deserialize` for the types which can't derive them.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{CheckResult, Resource, ServiceState, Unit};
+
+/// Units are written as in the performance data, e.g. `"MiB"`.
+impl Serialize for Unit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Unit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let unit = String::deserialize(deserializer)?;
+        unit.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Serialize)]
+struct ResourceRef<'a> {
+    name: &'a str,
+    state: ServiceState,
+    description: Option<&'a str>,
+    fixed_state: Option<ServiceState>,
+    fixed_state_reason: Option<&'a str>,
+    results: &'a [CheckResult],
+}
+
+#[derive(Deserialize)]
+struct ResourceData {
+    name: String,
+    description: Option<String>,
+    fixed_state: Option<ServiceState>,
+    fixed_state_reason: Option<String>,
+    #[serde(default)]
+    results: Vec<CheckResult>,
+}
+
+/// Only the name, the description, the fixed state and the results are included, together with
+/// the resulting state. The output settings are left out.
+impl Serialize for Resource {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ResourceRef {
+            name: &self.name,
+            state: self.state(),
+            description: self.description.as_deref(),
+            fixed_state: self.fixed_state,
+            fixed_state_reason: self.fixed_state_reason.as_deref(),
+            results: &self.results,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// The state is determined from the results again, a serialized `state` is ignored.
+impl<'de> Deserialize<'de> for Resource {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = ResourceData::deserialize(deserializer)?;
+        let mut resource = Resource::new(data.name).with_results(data.results);
+        resource.description = data.description;
+        resource.fixed_state = data.fixed_state;
+        resource.fixed_state_reason = data.fixed_state_reason;
+        Ok(resource)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Metric, Resource, TriggerIfValue, Unit};
+
+    #[test]
+    fn test_round_trip() {
+        let metric: Metric<f64> = serde_json::from_str(
+            r#"{"name": "load", "value": 4.2, "unit": "s", "thresholds": [2, 4, "greater"]}"#,
+        )
+        .unwrap();
+        let resource = Resource::new("server")
+            .with_description("load of the server")
+            .with_result(metric)
+            .with_result(
+                Metric::new("disk", 10)
+                    .with_unit(Unit::Percentage)
+                    .with_thresholds(80, 90, TriggerIfValue::Greater),
+            );
+
+        let json = serde_json::to_string(&resource).unwrap();
+        assert!(
+            json.starts_with(r#"{"name":"server","state":"CRITICAL","#),
+            "{}",
+            json
+        );
+
+        let parsed: Resource = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.nagios_result(), resource.nagios_result());
+    }
+}
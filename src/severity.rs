@@ -0,0 +1,73 @@
+use std::cmp::Ordering;
+
+use crate::ServiceState;
+
+/// Defines which state wins if the results of a [crate::Resource] have different states, see
+/// [crate::Resource::with_severity_ordering]. [ServiceState::Ok] is always the best state.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{CheckResult, Resource, ServiceState, SeverityOrdering};
+///
+/// let resource = Resource::new("foo")
+///     .with_severity_ordering(SeverityOrdering::UnknownAboveCritical)
+///     .with_result(CheckResult::new().with_state(ServiceState::Critical))
+///     .with_result(CheckResult::new().with_state(ServiceState::Unknown));
+/// assert_eq!(resource.state(), ServiceState::Unknown);
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum SeverityOrdering {
+    /// Ok < Unknown < Warning < Critical, the order of [ServiceState] itself.
+    #[default]
+    UnknownBelowWarning,
+    /// Ok < Warning < Unknown < Critical.
+    UnknownAboveWarning,
+    /// Ok < Warning < Critical < Unknown, so a check which couldn't determine a state is never
+    /// hidden behind a problem of another result.
+    UnknownAboveCritical,
+}
+
+impl SeverityOrdering {
+    pub fn compare(self, a: ServiceState, b: ServiceState) -> Ordering {
+        self.rank(a).cmp(&self.rank(b))
+    }
+
+    /// Returns the worse of both states.
+    pub fn worst(self, a: ServiceState, b: ServiceState) -> ServiceState {
+        std::cmp::max_by(a, b, |a, b| self.compare(*a, *b))
+    }
+
+    fn rank(self, state: ServiceState) -> u8 {
+        match (self, state) {
+            (_, ServiceState::Ok) => 0,
+            (SeverityOrdering::UnknownBelowWarning, ServiceState::Unknown) => 1,
+            (_, ServiceState::Warning) => 2,
+            (SeverityOrdering::UnknownAboveWarning, ServiceState::Unknown) => 3,
+            (_, ServiceState::Critical) => 4,
+            (SeverityOrdering::UnknownAboveCritical, ServiceState::Unknown) => 5,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worst() {
+        use ServiceState::*;
+
+        let ordering = SeverityOrdering::default();
+        assert_eq!(ordering.worst(Unknown, Warning), Warning);
+        assert_eq!(ordering.worst(Ok, Unknown), Unknown);
+
+        let ordering = SeverityOrdering::UnknownAboveWarning;
+        assert_eq!(ordering.worst(Unknown, Warning), Unknown);
+        assert_eq!(ordering.worst(Unknown, Critical), Critical);
+
+        let ordering = SeverityOrdering::UnknownAboveCritical;
+        assert_eq!(ordering.worst(Critical, Unknown), Unknown);
+        assert_eq!(ordering.worst(Warning, Ok), Warning);
+    }
+}
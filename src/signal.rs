@@ -0,0 +1,136 @@
+//! Reports SIGTERM and SIGINT as UNKNOWN, see [crate::Runner::with_signal_handler].
+//!
+//! Nagios and Icinga send SIGTERM to plugins which exceed their timeout. Without a handler the
+//! process dies without any output, so the monitoring system shows a generic error instead of the
+//! plugin line. The handler prints `UNKNOWN: check interrupted` and exits with code 3 instead.
+//! Checks which collect metrics step by step can pass what they have so far to
+//! [set_partial_result], so it is included as performance data.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use std::error::Error;
+//!
+//! use nagiosplugin::{signal, Metric, Resource, Runner};
+//!
+//! fn do_check() -> Result<Resource, Box<dyn Error>> {
+//!     let mut resource = Resource::new("foo");
+//!     for i in 0..10 {
+//!         resource.push_result(Metric::new(format!("step{}", i), i));
+//!         signal::set_partial_result(&resource);
+//!     }
+//!     Ok(resource)
+//! }
+//!
+//! fn main() {
+//!     Runner::new()
+//!         .with_signal_handler()
+//!         .safe_run(do_check)
+//!         .print_and_exit()
+//! }
+//! ```
+use std::io;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+
+use crate::{join_perf_strings, Resource, ServiceState};
+
+/// The write end of the pipe the handler writes the signal number to.
+static PIPE: AtomicI32 = AtomicI32::new(-1);
+
+static INSTALLED: OnceLock<Result<(), i32>> = OnceLock::new();
+
+static PARTIAL_PERF_DATA: Mutex<String> = Mutex::new(String::new());
+
+/// Stores the performance data of the resource, which is printed if the check is interrupted.
+/// Replaces the previously stored performance data.
+pub fn set_partial_result(resource: &Resource) {
+    let perf_strings: Vec<_> = resource.perf_strings().cloned().collect();
+    let perf_data = join_perf_strings(&perf_strings);
+    *PARTIAL_PERF_DATA
+        .lock()
+        .unwrap_or_else(|err| err.into_inner()) = perf_data;
+}
+
+/// Installs the handler for SIGTERM and SIGINT. Only the first call installs it, later calls
+/// return the result of the first one.
+pub(crate) fn install() -> io::Result<()> {
+    INSTALLED
+        .get_or_init(|| install_handler().map_err(|err| err.raw_os_error().unwrap_or(0)))
+        .map_err(io::Error::from_raw_os_error)
+}
+
+fn install_handler() -> io::Result<()> {
+    let mut fds = [0; 2];
+    // SAFETY: fds has room for the two file descriptors.
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let [read_fd, write_fd] = fds;
+    PIPE.store(write_fd, Ordering::SeqCst);
+
+    thread::spawn(move || {
+        let mut signal = 0u8;
+        // SAFETY: the buffer is a single byte and the file descriptor stays open.
+        if unsafe { libc::read(read_fd, (&mut signal as *mut u8).cast(), 1) } == 1 {
+            let perf_data = PARTIAL_PERF_DATA
+                .lock()
+                .unwrap_or_else(|err| err.into_inner());
+            let state = ServiceState::Unknown;
+            println!("{}", interrupted_output(state, &perf_data));
+            std::process::exit(state.exit_code());
+        }
+    });
+
+    for signal in [libc::SIGTERM, libc::SIGINT] {
+        // SAFETY: the handler only calls async-signal-safe functions.
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handle as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            if libc::sigaction(signal, &action, std::ptr::null_mut()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+    }
+    Ok(())
+}
+
+extern "C" fn handle(signal: libc::c_int) {
+    let byte = signal as u8;
+    // SAFETY: write is async-signal-safe, the byte lives until it returns.
+    unsafe {
+        libc::write(PIPE.load(Ordering::SeqCst), (&byte as *const u8).cast(), 1);
+    }
+}
+
+fn interrupted_output(state: ServiceState, perf_data: &str) -> String {
+    if perf_data.is_empty() {
+        format!("{}: check interrupted", state)
+    } else {
+        format!("{}: check interrupted|{}", state, perf_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Metric;
+
+    #[test]
+    fn test_interrupted_output() {
+        assert_eq!(
+            interrupted_output(ServiceState::Unknown, ""),
+            "UNKNOWN: check interrupted"
+        );
+
+        set_partial_result(&Resource::new("foo").with_result(Metric::new("a", 1)));
+        let perf_data = PARTIAL_PERF_DATA.lock().unwrap().clone();
+        assert_eq!(
+            interrupted_output(ServiceState::Unknown, &perf_data),
+            "UNKNOWN: check interrupted|'a'=1;;;;"
+        );
+    }
+}
@@ -0,0 +1,294 @@
+//! Destinations for finished check results in addition to the regular plugin output. Sinks are
+//! registered with [Runner::with_sink] and are called once the check has completed.
+//!
+//! [Runner::with_sink]: crate::Runner::with_sink
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::net::NetOptions;
+use crate::{json, Resource};
+
+pub use self::buffered::BufferedSink;
+#[cfg(feature = "influxdb")]
+pub use self::influx::InfluxSink;
+#[cfg(feature = "zabbix")]
+pub use self::zabbix::ZabbixSink;
+
+mod buffered;
+#[cfg(feature = "influxdb")]
+mod influx;
+#[cfg(feature = "zabbix")]
+mod zabbix;
+
+/// A destination for finished check results. Errors returned by a sink are reported on stderr
+/// and never change the result of the check.
+pub trait Sink: fmt::Debug {
+    fn submit(&self, resource: &Resource) -> io::Result<()>;
+
+    /// Returns the name of the sink used in error messages, the type name by default. Unlike the
+    /// debug output, it must not contain addresses with credentials, tokens or the like.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// Sends each performance data value as a gauge to a statsd server via UDP. Metric names are
+/// formed from the optional prefix and the label, undetermined values (`U`) are skipped.
+///
+/// Nagios counters are absolute values while statsd counters are increments, so counters are sent
+/// as gauges as well. Turning them into increments would need the value of the previous run and is
+/// left to the statsd server (e.g. a derivative in the query).
+///
+/// ## Example
+///
+/// ```no_run
+/// use nagiosplugin::sink::StatsdSink;
+/// use nagiosplugin::{Runner, ServiceState};
+///
+/// let runner = Runner::new().with_sink(
+///     StatsdSink::new("127.0.0.1:8125")
+///         .with_prefix("nagios")
+///         .with_dogstatsd_tags()
+///         .with_tag("env", "prod"),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct StatsdSink {
+    address: String,
+    prefix: Option<String>,
+    dogstatsd_tags: bool,
+    tags: Vec<(String, String)>,
+    net: NetOptions,
+}
+
+impl StatsdSink {
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            prefix: Default::default(),
+            dogstatsd_tags: Default::default(),
+            tags: Default::default(),
+            net: Default::default(),
+        }
+    }
+
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Appends DogStatsD style tags (`|#resource:foo,state:ok,metric:load1`) with the resource
+    /// name, the state, the label of the metric and all additional tags to every metric.
+    pub fn with_dogstatsd_tags(mut self) -> Self {
+        self.dogstatsd_tags = true;
+        self
+    }
+
+    /// Adds a tag which is sent if DogStatsD tags are enabled.
+    pub fn with_tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.push((key.into(), value.into()));
+        self
+    }
+
+    /// Uses the address family and the source address of the options.
+    pub fn with_net_options(mut self, options: &NetOptions) -> Self {
+        self.net = options.clone();
+        self
+    }
+
+    fn lines(&self, resource: &Resource) -> Vec<String> {
+        let state = resource.state().to_string().to_lowercase();
+        let mut lines = Vec::new();
+
+        for p in resource
+            .perf_strings()
+            .filter(|p| p.value() != crate::UNDETERMINED_VALUE)
+        {
+            let name = match &self.prefix {
+                Some(prefix) => format!("{}.{}", prefix, sanitize(p.label())),
+                None => sanitize(p.label()),
+            };
+            let tags = if self.dogstatsd_tags {
+                let mut tags = vec![
+                    format!("resource:{}", sanitize(resource.name())),
+                    format!("state:{}", state),
+                    format!("metric:{}", sanitize(p.label())),
+                ];
+                tags.extend(
+                    self.tags
+                        .iter()
+                        .map(|(k, v)| format!("{}:{}", sanitize(k), sanitize(v))),
+                );
+                format!("|#{}", tags.join(","))
+            } else {
+                String::new()
+            };
+
+            // A signed gauge value changes the gauge relative to its current value, so a negative
+            // value has to be preceded by a reset to zero.
+            if p.value().starts_with('-') {
+                lines.push(format!("{}:0|g{}", name, tags));
+            }
+            lines.push(format!("{}:{}|g{}", name, p.value(), tags));
+        }
+        lines
+    }
+}
+
+impl Sink for StatsdSink {
+    fn submit(&self, resource: &Resource) -> io::Result<()> {
+        let address = self.net.resolve_address(&self.address, 8125)?[0];
+        let socket = self.net.bind_udp(&address)?;
+
+        for line in self.lines(resource) {
+            socket.send_to(line.as_bytes(), address)?;
+        }
+        Ok(())
+    }
+}
+
+/// Appends one JSON record per finished check to a file (newline delimited JSON), for consumption
+/// by log shippers. A record contains the timestamp as seconds since the Unix epoch, the resource
+/// name, the state, the exit code and all performance data entries.
+///
+/// ## Example
+///
+/// ```no_run
+/// use nagiosplugin::sink::NdjsonFileSink;
+/// use nagiosplugin::Runner;
+///
+/// // e.g. from an `--also-write-json PATH` option
+/// let path = "/var/log/nagios/check_foo.ndjson";
+/// let runner = Runner::new().with_sink(NdjsonFileSink::new(path));
+/// ```
+#[derive(Debug, Clone)]
+pub struct NdjsonFileSink {
+    path: PathBuf,
+}
+
+impl NdjsonFileSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    fn record(resource: &Resource, timestamp: u64) -> String {
+        let state = resource.state();
+        let metrics = resource
+            .perf_strings()
+            .map(json::perf_string)
+            .collect::<Vec<_>>()
+            .join(",");
+
+        format!(
+            "{{\"timestamp\":{},\"name\":{},\"state\":{},\"exit_code\":{},\"metrics\":[{}]}}",
+            timestamp,
+            json::quote(resource.name()),
+            json::quote(&state.to_string()),
+            state.exit_code(),
+            metrics
+        )
+    }
+}
+
+impl Sink for NdjsonFileSink {
+    fn submit(&self, resource: &Resource) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let mut line = Self::record(resource, timestamp);
+        line.push('\n');
+
+        // A single write keeps records of concurrent runs from interleaving.
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(line.as_bytes())
+    }
+}
+
+/// Replaces characters which have a meaning in the statsd protocol.
+fn sanitize(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            ':' | '|' | '@' | '#' | ',' => '_',
+            c if c.is_whitespace() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Metric, TriggerIfValue};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_statsd_lines() {
+        let resource = Resource::new("foo").with_result(Metric::new("a b", 42).with_thresholds(
+            40,
+            50,
+            TriggerIfValue::Greater,
+        ));
+
+        let sink = StatsdSink::new("localhost:8125").with_prefix("nagios");
+        assert_eq!(sink.lines(&resource), vec!["nagios.a_b:42|g"]);
+
+        let sink = sink.with_dogstatsd_tags().with_tag("env", "prod");
+        assert_eq!(
+            sink.lines(&resource),
+            vec!["nagios.a_b:42|g|#resource:foo,state:warning,metric:a_b,env:prod"]
+        );
+    }
+
+    #[test]
+    fn test_statsd_lines_special_values() {
+        let resource = Resource::new("foo")
+            .with_result(Metric::new("temp", -5))
+            .with_result(Metric::new("rate", f64::NAN));
+
+        assert_eq!(
+            StatsdSink::new("localhost:8125").lines(&resource),
+            vec!["temp:0|g", "temp:-5|g"]
+        );
+    }
+
+    #[test]
+    fn test_statsd_submit_from_source_address() {
+        let server = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let options = NetOptions::new().with_source_address("127.0.0.1".parse().unwrap());
+
+        StatsdSink::new(server.local_addr().unwrap().to_string())
+            .with_net_options(&options)
+            .submit(&Resource::new("foo").with_result(crate::Metric::new("a", 1)))
+            .unwrap();
+
+        let mut buf = [0; 64];
+        let (n, from) = server.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"a:1|g");
+        assert_eq!(from.ip(), options.source_address().unwrap());
+    }
+
+    #[test]
+    fn test_sink_name() {
+        let sink: Arc<dyn Sink> = Arc::new(NdjsonFileSink::new("/dev/null"));
+        assert_eq!(sink.name(), "nagiosplugin::sink::NdjsonFileSink");
+    }
+
+    #[test]
+    fn test_ndjson_record() {
+        let resource = Resource::new("foo").with_result(Metric::new("a", 1));
+
+        assert_eq!(
+            NdjsonFileSink::record(&resource, 1700000000),
+            "{\"timestamp\":1700000000,\"name\":\"foo\",\"state\":\"OK\",\"exit_code\":0,\"metrics\":[\
+             {\"label\":\"a\",\"value\":\"1\",\"unit\":\"\",\"warning\":\"\",\"critical\":\"\",\
+             \"min\":\"\",\"max\":\"\"}]}"
+        );
+    }
+}
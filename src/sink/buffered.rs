@@ -0,0 +1,306 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{Read, Seek, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use super::Sink;
+use crate::perf_parse::parse_perf_data;
+use crate::{CheckResult, PerfString, Resource, ServiceState};
+
+/// Wraps another sink to submit results in batches, retry failed submissions with an exponential
+/// backoff and keep results which still couldn't be submitted in a spool file, so they aren't
+/// lost while the backend is briefly unreachable. Meant for collectors using
+/// [Runner::run_every](crate::Runner::run_every).
+///
+/// Results are queued until the batch is full and then passed to the wrapped sink one by one, in
+/// the order they were submitted. Without a spool file the queue is kept in memory, so queued
+/// results are lost when the process exits. With a spool file every result is written to it
+/// before it is submitted, and the spool is shared by all processes using the same path.
+///
+/// Only the name, the state and the performance data of a result are kept, which is what the
+/// sinks of this crate submit. The wrapped sink receives a [Resource] rebuilt from them.
+///
+/// ## Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use nagiosplugin::sink::{BufferedSink, StatsdSink};
+/// use nagiosplugin::Runner;
+///
+/// let runner = Runner::new().with_sink(
+///     BufferedSink::new(StatsdSink::new("127.0.0.1:8125"))
+///         .with_batch_size(10)
+///         .with_retries(3, Duration::from_secs(1))
+///         .with_spool_file("/var/spool/nagios/check_foo.spool"),
+/// );
+/// ```
+#[derive(Debug)]
+pub struct BufferedSink<S> {
+    inner: S,
+    batch_size: usize,
+    retries: u32,
+    delay: Duration,
+    spool_file: Option<PathBuf>,
+    queue: Mutex<Vec<Snapshot>>,
+}
+
+impl<S: Sink> BufferedSink<S> {
+    /// Creates an instance which submits every result right away without retries.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            batch_size: 1,
+            retries: 0,
+            delay: Duration::ZERO,
+            spool_file: Default::default(),
+            queue: Default::default(),
+        }
+    }
+
+    /// Sets the number of results which are queued before they are submitted. Defaults to 1.
+    pub fn with_batch_size(mut self, size: usize) -> Self {
+        self.batch_size = size.max(1);
+        self
+    }
+
+    /// Retries a failed submission up to `count` times. The delay before the first retry is
+    /// `delay` and doubles with every further retry.
+    pub fn with_retries(mut self, count: u32, delay: Duration) -> Self {
+        self.retries = count;
+        self.delay = delay;
+        self
+    }
+
+    /// Keeps queued and undelivered results in the given file instead of in memory.
+    pub fn with_spool_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.spool_file = Some(path.into());
+        self
+    }
+
+    /// Submits all queued results, regardless of the batch size. Results which couldn't be
+    /// submitted stay queued for the next attempt and the last error is returned.
+    pub fn flush(&self) -> io::Result<()> {
+        self.update_queue(|_| true)
+    }
+
+    /// Runs `f` on the locked queue and submits the queue if `f` returns `true`.
+    fn update_queue(&self, f: impl FnOnce(&mut Vec<Snapshot>) -> bool) -> io::Result<()> {
+        let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+
+        let Some(path) = &self.spool_file else {
+            return if f(&mut queue) {
+                self.deliver(&mut queue)
+            } else {
+                Ok(())
+            };
+        };
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        file.lock()?;
+        let mut spooled = read_spool(&mut file)?;
+        let result = if f(&mut spooled) {
+            self.deliver(&mut spooled)
+        } else {
+            Ok(())
+        };
+        write_spool(&mut file, &spooled)?;
+        result
+    }
+
+    /// Submits the queued results in order and removes the submitted ones. Stops at the first
+    /// result which can't be submitted, so the order is kept.
+    fn deliver(&self, queue: &mut Vec<Snapshot>) -> io::Result<()> {
+        let mut delivered = 0;
+        let mut result = Ok(());
+        for snapshot in queue.iter() {
+            result = self.submit_with_retries(&snapshot.to_resource());
+            if result.is_err() {
+                break;
+            }
+            delivered += 1;
+        }
+        queue.drain(..delivered);
+        result
+    }
+
+    fn submit_with_retries(&self, resource: &Resource) -> io::Result<()> {
+        let mut delay = self.delay;
+        let mut attempt = 0;
+        loop {
+            match self.inner.submit(resource) {
+                Err(_) if attempt < self.retries => {
+                    thread::sleep(delay);
+                    delay = delay.saturating_mul(2);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<S: Sink> Sink for BufferedSink<S> {
+    fn submit(&self, resource: &Resource) -> io::Result<()> {
+        let snapshot = Snapshot::new(resource);
+        self.update_queue(|queue| {
+            queue.push(snapshot);
+            queue.len() >= self.batch_size
+        })
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
+
+/// The parts of a [Resource] which are kept in the queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Snapshot {
+    name: String,
+    state: ServiceState,
+    perf_strings: Vec<PerfString>,
+}
+
+impl Snapshot {
+    fn new(resource: &Resource) -> Self {
+        Self {
+            name: resource.name().to_owned(),
+            state: resource.state(),
+            perf_strings: resource.perf_strings().cloned().collect(),
+        }
+    }
+
+    fn to_resource(&self) -> Resource {
+        let result = self
+            .perf_strings
+            .iter()
+            .cloned()
+            .fold(CheckResult::new(), CheckResult::with_perf_data);
+        Resource::new(self.name.clone())
+            .with_fixed_state(self.state)
+            .with_result(result)
+    }
+
+    /// Renders the snapshot as a line of the spool file: the exit code, the name and the
+    /// performance data, separated by tabs.
+    fn to_line(&self) -> String {
+        let name = self.name.replace(['\t', '\n', '\r'], " ");
+        let perf_data = self
+            .perf_strings
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("{}\t{}\t{}\n", self.state.exit_code(), name, perf_data)
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut parts = line.splitn(3, '\t');
+        let state = ServiceState::from_exit_code(parts.next()?.parse().ok()?)?;
+        let name = parts.next()?.to_owned();
+        let perf_strings = parse_perf_data(parts.next()?).ok()?;
+        Some(Self {
+            name,
+            state,
+            perf_strings,
+        })
+    }
+}
+
+/// Reads the spool file. Lines which can't be parsed are dropped, so a damaged spool doesn't
+/// block the queue forever.
+fn read_spool(file: &mut File) -> io::Result<Vec<Snapshot>> {
+    let mut content = String::new();
+    file.rewind()?;
+    file.read_to_string(&mut content)?;
+    Ok(content.lines().filter_map(Snapshot::from_line).collect())
+}
+
+fn write_spool(file: &mut File, queue: &[Snapshot]) -> io::Result<()> {
+    let content: String = queue.iter().map(Snapshot::to_line).collect();
+    file.rewind()?;
+    file.set_len(0)?;
+    file.write_all(content.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Metric;
+
+    #[derive(Debug, Default)]
+    struct Flaky {
+        failures: Mutex<u32>,
+        submitted: Mutex<Vec<String>>,
+    }
+
+    impl Sink for &Flaky {
+        fn submit(&self, resource: &Resource) -> io::Result<()> {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(io::Error::other("unreachable"));
+            }
+            let perf_data = resource.perf_strings().map(ToString::to_string);
+            self.submitted.lock().unwrap().extend(perf_data);
+            Ok(())
+        }
+    }
+
+    fn resource(value: i64) -> Resource {
+        Resource::new("foo").with_result(Metric::new("a", value))
+    }
+
+    #[test]
+    fn test_buffered_sink_batches_and_retries() {
+        let inner = Flaky::default();
+        let sink = BufferedSink::new(&inner)
+            .with_batch_size(2)
+            .with_retries(1, Duration::ZERO);
+
+        sink.submit(&resource(1)).unwrap();
+        assert!(inner.submitted.lock().unwrap().is_empty());
+
+        *inner.failures.lock().unwrap() = 1;
+        sink.submit(&resource(2)).unwrap();
+        assert_eq!(
+            *inner.submitted.lock().unwrap(),
+            vec!["'a'=1;;;;", "'a'=2;;;;"]
+        );
+    }
+
+    #[test]
+    fn test_buffered_sink_spools_undelivered_results() {
+        let path = std::env::temp_dir().join(format!(
+            "nagiosplugin-test-{}-buffered.spool",
+            std::process::id()
+        ));
+        let inner = Flaky::default();
+        let sink = BufferedSink::new(&inner).with_spool_file(&path);
+
+        *inner.failures.lock().unwrap() = 1;
+        assert!(sink.submit(&resource(1)).is_err());
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "0\tfoo\t'a'=1;;;;\n"
+        );
+
+        sink.submit(&resource(2)).unwrap();
+        assert_eq!(
+            *inner.submitted.lock().unwrap(),
+            vec!["'a'=1;;;;", "'a'=2;;;;"]
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
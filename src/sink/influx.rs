@@ -0,0 +1,335 @@
+use std::io;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::Sink;
+use crate::net::NetOptions;
+use crate::{Resource, Secret};
+
+/// The default port of the UDP listener.
+const DEFAULT_UDP_PORT: u16 = 8089;
+
+#[derive(Debug, Clone)]
+enum Transport {
+    Udp(String),
+    Http { address: String, path: String },
+}
+
+/// Converts the performance data of a [Resource] into the InfluxDB line protocol and sends it via
+/// UDP or HTTP. Each resource becomes a single point: the measurement is the name of the resource,
+/// the state is a tag, the exit code and each performance data value are fields. Undetermined
+/// values are skipped.
+///
+/// ## Example
+///
+/// ```no_run
+/// use nagiosplugin::sink::InfluxSink;
+/// use nagiosplugin::Runner;
+///
+/// let runner = Runner::new().with_sink(
+///     InfluxSink::http("http://influxdb.example.com:8086/api/v2/write?org=ops&bucket=nagios")
+///         .unwrap()
+///         .with_token("secret")
+///         .with_tag("host", "db1"),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct InfluxSink {
+    transport: Transport,
+    measurement: Option<String>,
+    tags: Vec<(String, String)>,
+    token: Option<Secret>,
+    timeout: Duration,
+    net: NetOptions,
+}
+
+impl InfluxSink {
+    /// Creates a sink sending to the UDP listener of InfluxDB at the given address.
+    pub fn udp(address: impl Into<String>) -> Self {
+        Self::new(Transport::Udp(address.into()))
+    }
+
+    /// Creates a sink posting to the given write URL, e.g. `http://localhost:8086/write?db=nagios`
+    /// for InfluxDB 1.x or `http://localhost:8086/api/v2/write?org=ops&bucket=nagios` for 2.x.
+    /// Only plain HTTP is supported, use a local proxy for HTTPS, e.g. with
+    /// [InfluxSink::with_net_options].
+    pub fn http(url: &str) -> io::Result<Self> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("expected an URL like http://host:port/write, got '{}'", url),
+            )
+        };
+        let rest = url.strip_prefix("http://").ok_or_else(invalid)?;
+        let (address, path) = match rest.find('/') {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => return Err(invalid()),
+        };
+        if address.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self::new(Transport::Http {
+            address: address.to_owned(),
+            path: path.to_owned(),
+        }))
+    }
+
+    fn new(transport: Transport) -> Self {
+        Self {
+            transport,
+            measurement: Default::default(),
+            tags: Default::default(),
+            token: Default::default(),
+            timeout: Duration::from_secs(5),
+            net: Default::default(),
+        }
+    }
+
+    /// Uses the given measurement instead of the name of the resource.
+    pub fn with_measurement(mut self, measurement: impl Into<String>) -> Self {
+        self.measurement = Some(measurement.into());
+        self
+    }
+
+    /// Adds a tag to every point, e.g. the host the check ran on.
+    pub fn with_tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.push((key.into(), value.into()));
+        self
+    }
+
+    /// Sends the token in the `Authorization` header of HTTP requests, as InfluxDB 2.x expects. It
+    /// is kept as a [Secret], so it doesn't show up in the debug output of the sink.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(Secret::new(token));
+        self
+    }
+
+    /// Sets the timeout for connecting, sending and receiving. Defaults to 5 seconds.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Uses the address family and the source address of the options and sends HTTP requests
+    /// through the proxy, which has to be an `http://` URL. Over TCP a source address makes
+    /// connecting fail, see [NetOptions::connect].
+    pub fn with_net_options(mut self, options: &NetOptions) -> Self {
+        self.net = options.clone();
+        self
+    }
+
+    /// Returns the point for the resource with the current time, without a trailing line break.
+    pub fn to_line_protocol(&self, resource: &Resource) -> String {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos());
+        self.line(resource, timestamp)
+    }
+
+    fn line(&self, resource: &Resource, timestamp: u128) -> String {
+        let state = resource.state();
+        let measurement = self.measurement.as_deref().unwrap_or(resource.name());
+
+        let mut line = escape(measurement, &[',', ' ']);
+        let state_tag = ("state".to_owned(), state.to_string().to_lowercase());
+        for (key, value) in std::iter::once(&state_tag).chain(&self.tags) {
+            line.push_str(&format!(
+                ",{}={}",
+                escape(key, &[',', '=', ' ']),
+                escape(value, &[',', '=', ' '])
+            ));
+        }
+
+        line.push_str(&format!(" exit_code={}i", state.exit_code()));
+        for perf_string in resource
+            .perf_strings()
+            .filter(|p| p.value() != crate::UNDETERMINED_VALUE)
+        {
+            line.push_str(&format!(
+                ",{}={}",
+                escape(perf_string.label(), &[',', '=', ' ']),
+                perf_string.value()
+            ));
+        }
+
+        line.push_str(&format!(" {}", timestamp));
+        line
+    }
+
+    /// Connects to the server or the proxy and returns the request target to use.
+    fn connect(&self, address: &str, path: &str) -> io::Result<(TcpStream, String)> {
+        match self.net.proxy() {
+            Some(proxy) => {
+                let proxy_address = proxy
+                    .strip_prefix("http://")
+                    .map(|rest| rest.trim_end_matches('/'))
+                    .filter(|rest| !rest.is_empty() && !rest.contains('/'))
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::Unsupported,
+                            format!(
+                                "expected a proxy URL like http://host:port, got '{}'",
+                                proxy
+                            ),
+                        )
+                    })?;
+                let stream = self.net.connect(proxy_address, 80, self.timeout)?;
+                Ok((stream, format!("http://{}{}", address, path)))
+            }
+            None => Ok((
+                self.net.connect(address, 80, self.timeout)?,
+                path.to_owned(),
+            )),
+        }
+    }
+
+    fn post(&self, address: &str, path: &str, body: &str) -> io::Result<()> {
+        let (mut stream, target) = self.connect(address, path)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        let mut request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: {}\r\nConnection: close\r\n",
+            target,
+            address,
+            body.len()
+        );
+        if let Some(token) = &self.token {
+            request.push_str(&format!("Authorization: Token {}\r\n", token.expose()));
+        }
+        request.push_str("\r\n");
+        request.push_str(body);
+        stream.write_all(request.as_bytes())?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+        check_response(&response)
+    }
+}
+
+impl Sink for InfluxSink {
+    fn submit(&self, resource: &Resource) -> io::Result<()> {
+        let line = self.to_line_protocol(resource);
+        match &self.transport {
+            Transport::Udp(address) => {
+                let address = self.net.resolve_address(address, DEFAULT_UDP_PORT)?[0];
+                self.net
+                    .bind_udp(&address)?
+                    .send_to(line.as_bytes(), address)?;
+                Ok(())
+            }
+            Transport::Http { address, path } => self.post(address, path, &line),
+        }
+    }
+}
+
+/// Accepts all 2xx responses, InfluxDB answers writes with `204 No Content`.
+fn check_response(response: &[u8]) -> io::Result<()> {
+    let response = String::from_utf8_lossy(response);
+    let status_line = response.lines().next().unwrap_or_default();
+    match status_line.split(' ').nth(1) {
+        Some(status) if status.starts_with('2') => Ok(()),
+        _ => Err(io::Error::other(format!(
+            "InfluxDB rejected the data: {}",
+            status_line
+        ))),
+    }
+}
+
+/// Escapes the given characters with a backslash.
+fn escape(s: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if special.contains(&c) || c == '\\' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Metric, TriggerIfValue};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_line() {
+        let resource = Resource::new("disk usage")
+            .with_result(Metric::new("/var,log", 93).with_thresholds(
+                80,
+                90,
+                TriggerIfValue::Greater,
+            ))
+            .with_result(Metric::new("b", f64::NAN));
+        let sink = InfluxSink::udp("localhost:8089").with_tag("host", "db 1");
+
+        assert_eq!(
+            sink.line(&resource, 1700000000000000000),
+            "disk\\ usage,state=critical,host=db\\ 1 exit_code=2i,/var\\,log=93 1700000000000000000"
+        );
+        assert!(InfluxSink::http("https://localhost/write").is_err());
+
+        let sink = InfluxSink::udp("localhost:8089").with_token("hunter2");
+        assert!(!format!("{:?}", sink).contains("hunter2"));
+    }
+
+    #[test]
+    fn test_submit_http() {
+        submit_http(None);
+    }
+
+    #[test]
+    fn test_submit_http_via_proxy() {
+        submit_http(Some("influxdb.example.com:8086"));
+    }
+
+    /// Submits to a local server, which acts as the proxy if a target address is given.
+    fn submit_http(target: Option<&'static str>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let local = format!("http://{}", listener.local_addr().unwrap());
+        let (url, net) = match target {
+            Some(target) => (
+                format!("http://{}/write?db=nagios", target),
+                NetOptions::new().with_proxy(local),
+            ),
+            None => (format!("{}/write?db=nagios", local), NetOptions::new()),
+        };
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !String::from_utf8_lossy(&request).contains(" exit_code=0i,a=1 ") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let request = String::from_utf8(request).unwrap();
+            let expected = match target {
+                Some(target) => format!("POST http://{}/write?db=nagios HTTP/1.1\r\n", target),
+                None => "POST /write?db=nagios HTTP/1.1\r\n".to_owned(),
+            };
+            assert!(request.starts_with(&expected), "{}", request);
+            assert!(request.contains("Authorization: Token secret\r\n"));
+
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                .unwrap();
+        });
+
+        let resource = Resource::new("foo").with_result(Metric::new("a", 1));
+        InfluxSink::http(&url)
+            .unwrap()
+            .with_token("secret")
+            .with_net_options(&net)
+            .submit(&resource)
+            .unwrap();
+        server.join().unwrap();
+
+        assert!(check_response(b"HTTP/1.1 400 Bad Request\r\n\r\n").is_err());
+    }
+}
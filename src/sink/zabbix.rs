@@ -0,0 +1,194 @@
+use std::io;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use super::Sink;
+use crate::net::NetOptions;
+use crate::{json, Resource};
+
+/// The port of the Zabbix trapper.
+const DEFAULT_PORT: u16 = 10051;
+const HEADER: &[u8; 5] = b"ZBXD\x01";
+
+/// Sends each performance data value to a Zabbix server or proxy using the trapper protocol of
+/// `zabbix_sender`. The values end up in trapper items of the given host with the keys
+/// `<prefix>.<label>`, or just the label without a prefix. Undetermined values are skipped.
+///
+/// ## Example
+///
+/// ```no_run
+/// use nagiosplugin::sink::ZabbixSink;
+/// use nagiosplugin::Runner;
+///
+/// let runner = Runner::new().with_sink(
+///     ZabbixSink::new("zabbix-proxy.example.com", "db1.example.com").with_key_prefix("nagios.disk"),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct ZabbixSink {
+    address: String,
+    host: String,
+    key_prefix: Option<String>,
+    timeout: Duration,
+    net: NetOptions,
+}
+
+impl ZabbixSink {
+    /// Creates a sink sending to the given server for the given host as it is named in Zabbix.
+    /// The port defaults to 10051, IPv6 addresses have to be enclosed in brackets.
+    pub fn new(address: impl Into<String>, host: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            host: host.into(),
+            key_prefix: Default::default(),
+            timeout: Duration::from_secs(5),
+            net: Default::default(),
+        }
+    }
+
+    pub fn with_key_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.key_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Sets the timeout for connecting, sending and receiving. Defaults to 5 seconds.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Uses the address family of the options. A source address makes connecting fail, see
+    /// [NetOptions::connect].
+    pub fn with_net_options(mut self, options: &NetOptions) -> Self {
+        self.net = options.clone();
+        self
+    }
+
+    fn request(&self, resource: &Resource) -> String {
+        let data = resource
+            .perf_strings()
+            .filter(|p| p.value() != crate::UNDETERMINED_VALUE)
+            .map(|p| {
+                let key = match &self.key_prefix {
+                    Some(prefix) => format!("{}.{}", prefix, sanitize(p.label())),
+                    None => sanitize(p.label()),
+                };
+                format!(
+                    "{{\"host\":{},\"key\":{},\"value\":{}}}",
+                    json::quote(&self.host),
+                    json::quote(&key),
+                    json::quote(p.value())
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        format!("{{\"request\":\"sender data\",\"data\":[{}]}}", data)
+    }
+
+    fn connect(&self) -> io::Result<TcpStream> {
+        self.net.connect(&self.address, DEFAULT_PORT, self.timeout)
+    }
+}
+
+impl Sink for ZabbixSink {
+    fn submit(&self, resource: &Resource) -> io::Result<()> {
+        let mut stream = self.connect()?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        stream.write_all(&encode(&self.request(resource)))?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+
+        check_response(&response)
+    }
+}
+
+/// Prepends the protocol header and the length of the data.
+fn encode(data: &str) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(HEADER.len() + 8 + data.len());
+    packet.extend_from_slice(HEADER);
+    packet.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    packet.extend_from_slice(data.as_bytes());
+    packet
+}
+
+/// Checks the response of the server, e.g.
+/// `{"response":"success","info":"processed: 1; failed: 0; total: 1; seconds spent: 0.000055"}`.
+/// Values which were rejected by the server, e.g. for unknown items, count as failed.
+fn check_response(response: &[u8]) -> io::Result<()> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_owned());
+
+    let body = response
+        .strip_prefix(HEADER.as_slice())
+        .and_then(|r| r.get(8..))
+        .ok_or_else(|| invalid("invalid response header from Zabbix"))?;
+    let body = String::from_utf8_lossy(body);
+
+    if !body.contains("\"response\":\"success\"") {
+        return Err(invalid(&format!("Zabbix rejected the data: {}", body)));
+    }
+    if !body.contains("failed: 0;") {
+        return Err(invalid(&format!(
+            "Zabbix failed to process values: {}",
+            body
+        )));
+    }
+    Ok(())
+}
+
+/// Replaces characters which aren't allowed in Zabbix item keys.
+fn sanitize(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Metric;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_request() {
+        let resource = Resource::new("foo")
+            .with_result(Metric::new("used space", 93))
+            .with_result(Metric::new("b", f64::NAN));
+        let sink = ZabbixSink::new("localhost", "db1").with_key_prefix("nagios");
+
+        assert_eq!(
+            sink.request(&resource),
+            r#"{"request":"sender data","data":[{"host":"db1","key":"nagios.used_space","value":"93"}]}"#
+        );
+        assert_eq!(&encode("{}")[..], b"ZBXD\x01\x02\0\0\0\0\0\0\0{}");
+    }
+
+    #[test]
+    fn test_submit() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut header = [0; 13];
+            stream.read_exact(&mut header).unwrap();
+            let mut data = vec![0; u64::from_le_bytes(header[5..].try_into().unwrap()) as usize];
+            stream.read_exact(&mut data).unwrap();
+
+            assert!(String::from_utf8(data).unwrap().contains("\"key\":\"a\""));
+
+            let body = r#"{"response":"success","info":"processed: 1; failed: 0; total: 1"}"#;
+            stream.write_all(&encode(body)).unwrap();
+        });
+
+        let resource = Resource::new("foo").with_result(Metric::new("a", 1));
+        ZabbixSink::new(address, "db1").submit(&resource).unwrap();
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,181 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The state directory of the Monitoring Plugins, if not overridden.
+pub const DEFAULT_STATE_PATH: &str = "/var/lib/monitoring-plugins";
+
+/// The environment variables which override the state directory, in order of precedence.
+const STATE_PATH_VARS: [&str; 2] = ["MP_STATE_PATH", "NAGIOS_PLUGIN_STATE_DIRECTORY"];
+
+/// A directory which was tried as state directory and why it was rejected.
+#[derive(Debug)]
+pub struct RejectedStateDir {
+    pub path: PathBuf,
+    pub error: io::Error,
+}
+
+impl fmt::Display for RejectedStateDir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.path.display(), self.error)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "no usable state directory, tried: {}; set MP_STATE_PATH to a directory writable by the plugin user",
+    .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+)]
+/// This error is returned if none of the candidates of [state_dir] can be used. The message lists
+/// every candidate with the reason it was rejected.
+pub struct StateDirError(pub Vec<RejectedStateDir>);
+
+/// Returns the directory for state files of the given plugin, e.g. counters which are needed to
+/// calculate rates, following the convention of the Monitoring Plugins:
+///
+/// 1. `$MP_STATE_PATH/<plugin>/<uid>`, or the legacy `$NAGIOS_PLUGIN_STATE_DIRECTORY` instead
+/// 2. `/var/lib/monitoring-plugins/<plugin>/<uid>`
+/// 3. `$XDG_STATE_HOME/monitoring-plugins/<plugin>` or `~/.local/state/monitoring-plugins/<plugin>`
+///    as per-user fallback
+///
+/// The first candidate which exists or can be created, is writable and is not writable by others
+/// is returned. Missing directories are created. When returned from a check run by
+/// [Runner](crate::Runner), the error is reported as UNKNOWN with the rejected candidates.
+pub fn state_dir(plugin: &str) -> Result<PathBuf, StateDirError> {
+    let mut rejected = Vec::new();
+
+    for path in candidates(plugin, |name| std::env::var_os(name).map(PathBuf::from)) {
+        match prepare(&path) {
+            Ok(()) => return Ok(path),
+            Err(error) => rejected.push(RejectedStateDir { path, error }),
+        }
+    }
+
+    Err(StateDirError(rejected))
+}
+
+fn candidates(plugin: &str, env: impl Fn(&str) -> Option<PathBuf>) -> Vec<PathBuf> {
+    let per_uid = |base: PathBuf| {
+        let dir = base.join(plugin);
+        match current_uid() {
+            Some(uid) => dir.join(uid.to_string()),
+            None => dir,
+        }
+    };
+
+    let mut candidates = vec![per_uid(
+        STATE_PATH_VARS
+            .iter()
+            .find_map(|name| env(name))
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_STATE_PATH)),
+    )];
+
+    let user_state =
+        env("XDG_STATE_HOME").or_else(|| env("HOME").map(|home| home.join(".local").join("state")));
+    if let Some(user_state) = user_state {
+        candidates.push(user_state.join("monitoring-plugins").join(plugin));
+    }
+
+    candidates
+}
+
+/// Creates the directory if needed and checks that it can be used.
+fn prepare(path: &Path) -> io::Result<()> {
+    create_dir(path)?;
+
+    let metadata = fs::metadata(path)?;
+    if !metadata.is_dir() {
+        return Err(io::Error::other("not a directory"));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o002 != 0 {
+            return Err(io::Error::other("writable by others"));
+        }
+    }
+
+    // Checking the permission bits isn't enough, e.g. for read-only file systems.
+    let probe = path.join(format!(".probe-{}", std::process::id()));
+    fs::write(&probe, b"")?;
+    fs::remove_file(&probe)
+}
+
+#[cfg(unix)]
+fn create_dir(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(path)
+}
+
+#[cfg(not(unix))]
+fn create_dir(path: &Path) -> io::Result<()> {
+    fs::create_dir_all(path)
+}
+
+/// Returns the user id of the process, which owns everything below `/proc/self`.
+#[cfg(target_os = "linux")]
+fn current_uid() -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata("/proc/self").ok().map(|m| m.uid())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn current_uid() -> Option<u32> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates() {
+        let env = |name: &str| match name {
+            "NAGIOS_PLUGIN_STATE_DIRECTORY" => Some(PathBuf::from("/srv/state")),
+            "HOME" => Some(PathBuf::from("/home/nagios")),
+            _ => None,
+        };
+        let candidates = candidates("check_foo", env);
+
+        assert_eq!(candidates.len(), 2);
+        assert!(candidates[0].starts_with("/srv/state/check_foo"));
+        assert_eq!(
+            candidates[1],
+            Path::new("/home/nagios/.local/state/monitoring-plugins/check_foo")
+        );
+    }
+
+    #[test]
+    fn test_prepare() {
+        let base = std::env::temp_dir().join(format!("nagiosplugin-state-{}", std::process::id()));
+        let dir = base.join("check_foo");
+        prepare(&dir).unwrap();
+        assert!(dir.is_dir());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&dir, fs::Permissions::from_mode(0o777)).unwrap();
+            assert!(prepare(&dir).is_err());
+        }
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn test_error_message() {
+        let error = StateDirError(vec![RejectedStateDir {
+            path: PathBuf::from("/var/lib/monitoring-plugins/check_foo"),
+            error: io::Error::from(io::ErrorKind::PermissionDenied),
+        }]);
+        assert_eq!(
+            error.to_string(),
+            "no usable state directory, tried: /var/lib/monitoring-plugins/check_foo \
+             (permission denied); set MP_STATE_PATH to a directory writable by the plugin user"
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Values which are kept between check runs, e.g. for escalations, baselines or trends, stored as
+/// `key=value` lines. Usually placed in the [state_dir](crate::state_dir) of the plugin.
+///
+/// Changes are only written by [StateFile::save], which replaces the file atomically, so a
+/// killed check never leaves a truncated file behind.
+///
+/// ## Example
+///
+/// ```no_run
+/// use nagiosplugin::{state_dir, StateFile};
+///
+/// let dir = state_dir("check_foo").unwrap();
+/// let mut state = StateFile::open(dir.join("db1.state")).unwrap();
+/// let runs: u64 = state.get("runs").and_then(|v| v.parse().ok()).unwrap_or(0);
+/// state.set("runs", (runs + 1).to_string());
+/// state.save().unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateFile {
+    path: PathBuf,
+    values: BTreeMap<String, String>,
+}
+
+impl StateFile {
+    /// Reads the file at the given path. A missing file is treated as empty, lines without `=`
+    /// are ignored.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+
+        Ok(Self {
+            values: parse(&contents),
+            path,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Sets the value of the key. Line breaks in the value are replaced by spaces.
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        let value = value.into().replace(['\n', '\r'], " ");
+        self.values.insert(key.into(), value);
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.values.remove(key)
+    }
+
+    /// Writes all values to the file.
+    pub fn save(&self) -> io::Result<()> {
+        let mut contents = String::new();
+        for (key, value) in &self.values {
+            contents.push_str(key);
+            contents.push('=');
+            contents.push_str(value);
+            contents.push('\n');
+        }
+
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(format!(".{}.tmp", std::process::id()));
+        fs::write(&temp, contents)?;
+        fs::rename(&temp, &self.path)
+    }
+}
+
+fn parse(contents: &str) -> BTreeMap<String, String> {
+    contents
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_open() {
+        let path = std::env::temp_dir().join(format!("nagiosplugin-{}.state", std::process::id()));
+
+        let mut state = StateFile::open(&path).unwrap();
+        assert_eq!(state.get("a"), None);
+        state.set("a", "1");
+        state.set("b", "x=y\nz");
+        state.save().unwrap();
+
+        let mut state = StateFile::open(&path).unwrap();
+        assert_eq!(state.get("a"), Some("1"));
+        assert_eq!(state.get("b"), Some("x=y z"));
+        assert_eq!(state.remove("a"), Some("1".to_owned()));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,365 @@
+use std::fmt;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{CheckResult, Resource, ServiceState};
+
+type LocalCheckFn<'a, E> = Box<dyn FnOnce() -> Result<Resource, E> + 'a>;
+/// Spawns the check and waits for it with the given budget. Takes the error state for tracing.
+type DetachedCheckFn<'a, E> = Box<dyn FnOnce(Option<Duration>, ServiceState) -> Outcome<E> + 'a>;
+
+enum SubCheckFn<'a, E> {
+    /// Runs on the calling thread and can't be interrupted.
+    Local(LocalCheckFn<'a, E>),
+    /// Runs on a thread of its own, which is abandoned if the check exceeds its budget.
+    Detached(DetachedCheckFn<'a, E>, Option<Duration>),
+}
+
+/// Runs multiple named sub-checks and aggregates them into a single [Resource]. Messages of the
+/// sub-checks are prefixed with `<name>: ` and performance data labels with `<name>_`, so the
+/// output of composite checks stays consistent.
+///
+/// With the `otel` feature, each sub-check is recorded as a span of its own.
+///
+/// ## Example
+///
+/// ```
+/// use std::error::Error;
+///
+/// use nagiosplugin::{Metric, Resource, SubChecks, TriggerIfValue};
+///
+/// fn check_disk() -> Result<Resource, Box<dyn Error>> {
+///     Ok(Resource::new("disk")
+///         .with_result(Metric::new("usage", 93).with_thresholds(80, 90, TriggerIfValue::Greater)))
+/// }
+///
+/// let resource = SubChecks::new("server")
+///     .with_check("disk", check_disk)
+///     .with_check("load", || Ok(Resource::new("load").with_result(Metric::new("load1", 2))))
+///     .run();
+///
+/// let (_, output) = resource.nagios_result();
+/// assert!(output.contains("disk: metric 'usage' is CRITICAL"));
+/// assert!(output.contains("'disk_usage'=93"));
+/// ```
+///
+/// ## Timeouts
+///
+/// An overall deadline can be set with [SubChecks::with_deadline]. Sub-checks added with
+/// [SubChecks::with_detached_check] run on a thread of their own and get an equal share of the
+/// remaining time, optionally limited by a budget of their own. A sub-check exceeding its time is
+/// reported as [ServiceState::Unknown] and the remaining sub-checks still run. Sub-checks added
+/// with [SubChecks::with_check] can't be interrupted; they are only skipped once the deadline has
+/// passed.
+pub struct SubChecks<'a, E> {
+    name: String,
+    error_state: ServiceState,
+    deadline: Option<Duration>,
+    checks: Vec<(String, SubCheckFn<'a, E>)>,
+}
+
+impl<'a, E: fmt::Display> SubChecks<'a, E> {
+    /// Creates an empty instance. The given name is used for the aggregated [Resource].
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            error_state: ServiceState::Unknown,
+            deadline: Default::default(),
+            checks: Default::default(),
+        }
+    }
+
+    /// Sets the state which is reported for a sub-check which returned an error. Defaults to
+    /// [ServiceState::Unknown].
+    pub fn with_error_state(mut self, state: ServiceState) -> Self {
+        self.error_state = state;
+        self
+    }
+
+    /// Sets the time all sub-checks together may take, starting with [SubChecks::run].
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn with_check(
+        mut self,
+        name: impl Into<String>,
+        check: impl FnOnce() -> Result<Resource, E> + 'a,
+    ) -> Self {
+        self.push_check(name, check);
+        self
+    }
+
+    pub fn push_check(
+        &mut self,
+        name: impl Into<String>,
+        check: impl FnOnce() -> Result<Resource, E> + 'a,
+    ) {
+        self.checks
+            .push((name.into(), SubCheckFn::Local(Box::new(check))));
+    }
+
+    /// Adds a sub-check which runs on a thread of its own, so it can be abandoned once it exceeds
+    /// the given timeout or its share of the deadline.
+    pub fn with_detached_check(
+        mut self,
+        name: impl Into<String>,
+        timeout: impl Into<Option<Duration>>,
+        check: impl FnOnce() -> Result<Resource, E> + Send + 'static,
+    ) -> Self
+    where
+        E: Send + 'static,
+    {
+        self.push_detached_check(name, timeout, check);
+        self
+    }
+
+    pub fn push_detached_check(
+        &mut self,
+        name: impl Into<String>,
+        timeout: impl Into<Option<Duration>>,
+        check: impl FnOnce() -> Result<Resource, E> + Send + 'static,
+    ) where
+        E: Send + 'static,
+    {
+        let name = name.into();
+
+        #[cfg(not(feature = "otel"))]
+        let detached = move |budget, _| run_detached(check, budget);
+
+        #[cfg(feature = "otel")]
+        let detached = {
+            let name = name.clone();
+            move |budget, error_state| {
+                let cx = opentelemetry::Context::current();
+                let check = move || {
+                    let _guard = cx.attach();
+                    crate::otel::in_span(name, error_state, check)
+                };
+                run_detached(check, budget)
+            }
+        };
+
+        self.checks.push((
+            name,
+            SubCheckFn::Detached(Box::new(detached), timeout.into()),
+        ));
+    }
+
+    /// Runs all sub-checks in the order they were added and returns the aggregated [Resource].
+    /// A sub-check returning an error does not stop the other sub-checks.
+    pub fn run(self) -> Resource {
+        let mut resource = Resource::new(self.name);
+        let deadline = self.deadline.map(|d| Instant::now() + d);
+        let count = self.checks.len();
+
+        for (i, (name, check)) in self.checks.into_iter().enumerate() {
+            let remaining = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+            if remaining == Some(Duration::ZERO) {
+                resource.push_result(
+                    CheckResult::new()
+                        .with_state(ServiceState::Unknown)
+                        .with_message(format!("{}: skipped, deadline exceeded", name)),
+                );
+                continue;
+            }
+
+            let outcome = match check {
+                SubCheckFn::Local(check) => {
+                    #[cfg(feature = "otel")]
+                    let check = || crate::otel::in_span(name.clone(), self.error_state, check);
+
+                    Outcome::Done(check())
+                }
+                SubCheckFn::Detached(check, timeout) => {
+                    let share = remaining.map(|r| r / (count - i) as u32);
+                    let budget = match (share, timeout) {
+                        (Some(share), Some(timeout)) => Some(share.min(timeout)),
+                        (share, timeout) => share.or(timeout),
+                    };
+
+                    check(budget, self.error_state)
+                }
+            };
+
+            resource = resource.with_results(outcome_results(&name, outcome, self.error_state));
+        }
+
+        resource
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum Outcome<E> {
+    Done(Result<Resource, E>),
+    TimedOut(Duration),
+    Panicked,
+}
+
+/// Turns the outcome of the sub-check with the given name into results.
+pub(crate) fn outcome_results<E: fmt::Display>(
+    name: &str,
+    outcome: Outcome<E>,
+    error_state: ServiceState,
+) -> Vec<CheckResult> {
+    let failure = |state, message| vec![CheckResult::new().with_state(state).with_message(message)];
+
+    match outcome {
+        Outcome::Done(Ok(sub)) => prefixed_results(name, sub),
+        Outcome::Done(Err(err)) => failure(error_state, format!("{}: {}", name, err)),
+        Outcome::TimedOut(budget) => failure(
+            ServiceState::Unknown,
+            format!(
+                "{}: timed out after {:.2} seconds",
+                name,
+                budget.as_secs_f64()
+            ),
+        ),
+        Outcome::Panicked => failure(error_state, format!("{}: panicked", name)),
+    }
+}
+
+pub(crate) fn run_detached<E: Send + 'static>(
+    check: impl FnOnce() -> Result<Resource, E> + Send + 'static,
+    budget: Option<Duration>,
+) -> Outcome<E> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        // The receiver is gone if the check timed out, so the result is dropped.
+        let _ = tx.send(check());
+    });
+
+    let received = match budget {
+        Some(budget) => rx.recv_timeout(budget).map_err(|err| match err {
+            mpsc::RecvTimeoutError::Timeout => Outcome::TimedOut(budget),
+            mpsc::RecvTimeoutError::Disconnected => Outcome::Panicked,
+        }),
+        None => rx.recv().map_err(|_| Outcome::Panicked),
+    };
+
+    match received {
+        Ok(result) => Outcome::Done(result),
+        Err(outcome) => outcome,
+    }
+}
+
+/// Turns a sub-check [Resource] into results with prefixed messages and labels. The description
+/// and the fixed state of the sub-check are kept as results of their own, as is the empty state if
+/// the sub-check has no results. The states of the results are capped at the state of the
+/// sub-check, so e.g. a sub-check with a fixed [ServiceState::Ok] never raises the aggregated
+/// state and the [crate::SeverityOrdering] of the sub-check decides which state propagates.
+fn prefixed_results(name: &str, sub: Resource) -> Vec<CheckResult> {
+    let mut results = Vec::with_capacity(sub.results.len() + 1);
+
+    let sub_state = sub.state();
+    let state = match sub.fixed_state {
+        Some(_) => Some(sub_state),
+        None if sub.results.is_empty() => Some(sub_state),
+        None => None,
+    };
+    if sub.description.is_some() || state.is_some() {
+        let message = match (sub.description.as_deref(), sub.forced_state_note()) {
+            (Some(description), Some(note)) => Some(format!("{} ({})", description.trim(), note)),
+            (description, note) => note.or(description.map(str::to_owned)),
+        };
+        results.push(CheckResult {
+            state,
+            message,
+            details: Vec::new(),
+            perf_strings: Vec::new(),
+            evaluation: None,
+        });
+    }
+    results.extend(sub.results);
+
+    for result in &mut results {
+        result.state = result.state.map(|state| state.min(sub_state));
+        if let Some(message) = &mut result.message {
+            *message = format!("{}: {}", name, message.trim());
+        }
+        for perf_string in &mut result.perf_strings {
+            perf_string.label = format!("{}_{}", name, perf_string.label);
+        }
+    }
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Metric, SeverityOrdering, TriggerIfValue};
+
+    #[test]
+    fn test_sub_checks_run() {
+        let (state, output) = SubChecks::new("foo")
+            .with_check("a", || {
+                Ok(Resource::new("a")
+                    .with_description("all good")
+                    .with_result(Metric::new("value", 1)))
+            })
+            .with_check("b", || Err("woops"))
+            .run()
+            .nagios_result();
+
+        assert_eq!(state, ServiceState::Unknown);
+        assert!(output.contains("a: all good"));
+        assert!(output.contains("b: woops"));
+        assert!(output.contains("'a_value'=1"));
+    }
+
+    #[test]
+    fn test_sub_checks_empty_state() {
+        let resource = SubChecks::<&str>::new("foo")
+            .with_check("a", || {
+                Ok(Resource::new("a").with_empty_state(ServiceState::Unknown))
+            })
+            .run();
+
+        assert_eq!(resource.state(), ServiceState::Unknown);
+    }
+
+    #[test]
+    fn test_sub_checks_sub_state() {
+        let critical = || Metric::new("value", 3).with_thresholds(1, 2, TriggerIfValue::Greater);
+        let resource = SubChecks::<&str>::new("foo")
+            .with_check("a", || {
+                Ok(Resource::new("a")
+                    .with_result(critical())
+                    .with_fixed_state(ServiceState::Ok))
+            })
+            .run();
+        assert_eq!(resource.state(), ServiceState::Ok);
+
+        let resource = SubChecks::<&str>::new("foo")
+            .with_check("a", || {
+                Ok(Resource::new("a")
+                    .with_severity_ordering(SeverityOrdering::UnknownAboveCritical)
+                    .with_result(critical())
+                    .with_result(CheckResult::new().with_state(ServiceState::Unknown)))
+            })
+            .run();
+        assert_eq!(resource.state(), ServiceState::Unknown);
+    }
+
+    #[test]
+    fn test_sub_checks_deadline() {
+        let (state, output) = SubChecks::<&str>::new("foo")
+            .with_deadline(Duration::from_millis(200))
+            .with_detached_check("slow", None, || {
+                thread::sleep(Duration::from_secs(5));
+                Ok(Resource::new("slow"))
+            })
+            .with_detached_check("fast", Duration::from_secs(1), || {
+                Ok(Resource::new("fast").with_description("done"))
+            })
+            .run()
+            .nagios_result();
+
+        assert_eq!(state, ServiceState::Unknown);
+        assert!(output.contains("slow: timed out"));
+        assert!(output.contains("fast: done"));
+    }
+}
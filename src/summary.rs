@@ -0,0 +1,120 @@
+use crate::ServiceState;
+
+/// Defines how the header of the summary line of a [Resource] looks like. The state evaluation
+/// and the performance data are the same for all headers.
+///
+/// [Resource]: crate::Resource
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SummaryHeader {
+    /// `<name> is <STATE>`, e.g. `disk is WARNING: 93% used`.
+    #[default]
+    NameAndState,
+    /// Only the state, e.g. `WARNING: 93% used`.
+    State,
+    /// No header at all, the summary starts with the description, e.g. `93% used`. Without a
+    /// description the state is used.
+    None,
+    /// The given text, e.g. `Disk check: 93% used`.
+    Custom(String),
+    /// A template for the whole summary line including the description, e.g.
+    /// `"{name} [{state}] {description}"` for `disk [WARNING] 93% used`. Supported placeholders are
+    /// `{name}`, `{state}`, `{state_lowercase}`, `{exit_code}` and `{description}`, which is empty
+    /// if no description is set.
+    Template(String),
+}
+
+impl SummaryHeader {
+    /// Returns the summary line made of the header and the description.
+    pub(crate) fn render(
+        &self,
+        name: &str,
+        state: ServiceState,
+        description: Option<&str>,
+    ) -> String {
+        let header = match self {
+            SummaryHeader::NameAndState => format!("{} is {}", name, state),
+            SummaryHeader::State => state.to_string(),
+            SummaryHeader::None => {
+                return description.map_or_else(|| state.to_string(), str::to_owned)
+            }
+            SummaryHeader::Custom(header) => header.clone(),
+            SummaryHeader::Template(template) => {
+                return render_template(template, name, state, description)
+            }
+        };
+
+        match description {
+            Some(description) => format!("{}: {}", header, description),
+            None => header,
+        }
+    }
+}
+
+/// Replaces the placeholders of a [SummaryHeader::Template]. Unknown placeholders are kept.
+fn render_template(
+    template: &str,
+    name: &str,
+    state: ServiceState,
+    description: Option<&str>,
+) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        match &rest[1..end] {
+            "name" => output.push_str(name),
+            "state" => output.push_str(&state.to_string()),
+            "state_lowercase" => output.push_str(&state.to_string().to_lowercase()),
+            "exit_code" => output.push_str(&state.exit_code().to_string()),
+            "description" => output.push_str(description.unwrap_or_default()),
+            _ => output.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    output.push_str(rest);
+
+    // An empty description must not leave dangling whitespace behind.
+    output.trim().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let render = |h: SummaryHeader, d| h.render("disk", ServiceState::Warning, d);
+
+        assert_eq!(
+            render(SummaryHeader::NameAndState, Some("93% used")),
+            "disk is WARNING: 93% used"
+        );
+        assert_eq!(render(SummaryHeader::State, None), "WARNING");
+        assert_eq!(render(SummaryHeader::None, Some("93% used")), "93% used");
+        assert_eq!(render(SummaryHeader::None, None), "WARNING");
+        assert_eq!(
+            render(SummaryHeader::Custom("Disk check".to_owned()), None),
+            "Disk check"
+        );
+    }
+
+    #[test]
+    fn test_render_template() {
+        let template =
+            SummaryHeader::Template("{name} [{state_lowercase}] {description} {foo}".to_owned());
+
+        assert_eq!(
+            template.render("disk", ServiceState::Warning, Some("93% used")),
+            "disk [warning] 93% used {foo}"
+        );
+
+        let template = SummaryHeader::Template("{exit_code}: {name} {description}".to_owned());
+        assert_eq!(template.render("disk", ServiceState::Ok, None), "0: disk");
+    }
+}
@@ -0,0 +1,68 @@
+use std::os::unix::net::UnixDatagram;
+
+use crate::ServiceState;
+
+const SOCKET_PATH: &str = "/dev/log";
+/// The `user` facility.
+const FACILITY: u8 = 1;
+
+/// Writes messages to the local syslog socket, which is also read by journald. Logging is best
+/// effort, failures are ignored so they never influence the check result.
+#[derive(Debug, Clone)]
+pub(crate) struct SyslogLogger {
+    identifier: String,
+}
+
+impl SyslogLogger {
+    pub(crate) fn new(identifier: String) -> Self {
+        Self { identifier }
+    }
+
+    pub(crate) fn log(&self, state: Option<ServiceState>, message: &str) {
+        let line = format_message(&self.identifier, std::process::id(), state, message);
+        if let Ok(socket) = UnixDatagram::unbound() {
+            let _ = socket.send_to(line.as_bytes(), SOCKET_PATH);
+        }
+    }
+}
+
+/// Formats a message in the BSD syslog format (RFC 3164) without timestamp and hostname, which
+/// are added by the syslog daemon.
+fn format_message(
+    identifier: &str,
+    pid: u32,
+    state: Option<ServiceState>,
+    message: &str,
+) -> String {
+    let severity = match state {
+        None | Some(ServiceState::Ok) => 6,
+        Some(ServiceState::Unknown) => 5,
+        Some(ServiceState::Warning) => 4,
+        Some(ServiceState::Critical) => 3,
+    };
+    format!(
+        "<{}>{}[{}]: {}",
+        FACILITY * 8 + severity,
+        identifier,
+        pid,
+        message
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_message() {
+        assert_eq!(
+            format_message(
+                "check_foo",
+                42,
+                Some(ServiceState::Critical),
+                "state=CRITICAL"
+            ),
+            "<11>check_foo[42]: state=CRITICAL"
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use crate::{Resource, ServiceState};
+
+/// Renders a human friendly, colored version of the resource for terminals. This is not compliant
+/// plugin output and must only be used if stdout is a terminal.
+pub(crate) fn preview(resource: Resource) -> (ServiceState, String) {
+    let mut rows = Vec::new();
+    for r in resource
+        .results
+        .iter()
+        .filter(|r| r.message.is_some() || !r.details.is_empty() || !r.perf_strings.is_empty())
+    {
+        let state = r.state.map(|s| s.to_string()).unwrap_or_default();
+        let message = r.message.as_deref().unwrap_or_default().trim().to_owned();
+        let mut perf_strings = r
+            .perf_strings
+            .iter()
+            .map(|p| (p.label.clone(), format!("{}{}", p.value, p.unit.as_str())));
+
+        // Additional performance data and the details of the same result get rows of their own
+        // without state.
+        let (label, value) = perf_strings.next().unwrap_or_default();
+        rows.push((r.state, state, label, value, message));
+        for (label, value) in perf_strings {
+            rows.push((None, String::new(), label, value, String::new()));
+        }
+        for detail in &r.details {
+            let detail = format!("  {}", detail.trim());
+            rows.push((None, String::new(), String::new(), String::new(), detail));
+        }
+    }
+
+    let evaluation = resource.evaluate();
+
+    let mut output = format!(
+        "{}\n",
+        colorize(Some(evaluation.state), &evaluation.summary)
+    );
+
+    if !rows.is_empty() {
+        let state_width = rows.iter().map(|r| r.1.len()).max().unwrap_or_default();
+        let label_width = rows.iter().map(|r| r.2.len()).max().unwrap_or_default();
+        let value_width = rows.iter().map(|r| r.3.len()).max().unwrap_or_default();
+
+        output.push('\n');
+        for (state, state_text, label, value, message) in rows {
+            let state_text = format!("{:<width$}", state_text, width = state_width);
+            let line = format!(
+                "  {}  {:<label_width$}  {:>value_width$}  {}",
+                colorize(state, &state_text),
+                label,
+                value,
+                message,
+                label_width = label_width,
+                value_width = value_width,
+            );
+            output.push_str(line.trim_end());
+            output.push('\n');
+        }
+    }
+
+    (evaluation.state, output.trim_end().to_owned())
+}
+
+fn colorize(state: Option<ServiceState>, s: &str) -> String {
+    let code = match state {
+        Some(ServiceState::Ok) => "32",
+        Some(ServiceState::Warning) => "33",
+        Some(ServiceState::Critical) => "31",
+        Some(ServiceState::Unknown) => "35",
+        None => return s.to_owned(),
+    };
+    format!("\x1b[{}m{}\x1b[0m", code, s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Metric, TriggerIfValue};
+
+    #[test]
+    fn test_preview() {
+        let resource = Resource::new("foo")
+            .with_result(Metric::new("a", 1).with_fixed_state(ServiceState::Ok))
+            .with_result(Metric::new("long", 100).with_thresholds(
+                50,
+                200,
+                TriggerIfValue::Greater,
+            ));
+
+        let (state, s) = preview(resource);
+        assert_eq!(state, ServiceState::Warning);
+        assert_eq!(
+            s,
+            "\x1b[33mfoo is WARNING\x1b[0m\n\n  \
+             \x1b[32mOK     \x1b[0m  a       1\n  \
+             \x1b[33mWARNING\x1b[0m  long  100  metric 'long' is WARNING: value '100' has exceeded threshold of '50'"
+        );
+    }
+}
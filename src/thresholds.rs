@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Metric, ThresholdRange};
+
+/// The thresholds of a single metric given on the command line as `name=warning,critical`, e.g.
+/// `--threshold load1=4,8`. Either threshold can be left empty, like `load1=,8`, and the comma
+/// can be omitted if there is only a warning threshold. Collect them into [NamedThresholds].
+///
+/// The thresholds are Nagios ranges, see [ThresholdRange]: `load1=4,8` alerts outside of `0:4`
+/// and `0:8`, `free=10:,5:` below 10 and 5, and `temp=@10:20` inside of `10:20`.
+///
+/// With the `clap` feature it can be used with `value_parser!(NamedThreshold<f64>)` and
+/// `ArgAction::Append` for repeated occurrences.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedThreshold<T> {
+    pub name: String,
+    pub warning: Option<ThresholdRange<T>>,
+    pub critical: Option<ThresholdRange<T>>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid threshold '{input}', expected 'name=warning,critical': {reason}")]
+/// This error is returned by the [FromStr] implementation of [NamedThreshold].
+pub struct NamedThresholdParseError {
+    input: String,
+    reason: String,
+}
+
+impl<T> FromStr for NamedThreshold<T>
+where
+    T: FromStr + Default + PartialOrd,
+    T::Err: fmt::Display,
+{
+    type Err = NamedThresholdParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |reason: String| NamedThresholdParseError {
+            input: s.to_owned(),
+            reason,
+        };
+
+        let (name, values) = s
+            .split_once('=')
+            .ok_or_else(|| error("missing '='".to_owned()))?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(error("missing metric name".to_owned()));
+        }
+
+        let (warning, critical) = values.split_once(',').unwrap_or((values, ""));
+        let parse = |v: &str| match v.trim() {
+            "" => Ok(None),
+            v => v
+                .parse()
+                .map(Some)
+                .map_err(|e: crate::ThresholdRangeParseError| error(e.to_string())),
+        };
+        let threshold = NamedThreshold {
+            name: name.to_owned(),
+            warning: parse(warning)?,
+            critical: parse(critical)?,
+        };
+        if threshold.warning.is_none() && threshold.critical.is_none() {
+            return Err(error("no threshold given".to_owned()));
+        }
+
+        Ok(threshold)
+    }
+}
+
+/// The thresholds of several metrics by metric name, usually collected from a repeated
+/// command line option. See [NamedThreshold].
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{Metric, NamedThreshold, NamedThresholds};
+///
+/// let thresholds: NamedThresholds<f64> = ["load1=4,8", "load5=,6", "free=10:,5:"]
+///     .iter()
+///     .map(|s| s.parse::<NamedThreshold<f64>>().unwrap())
+///     .collect();
+///
+/// let metric = thresholds.apply(Metric::new("load1", 0.5));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedThresholds<T> {
+    thresholds: BTreeMap<String, NamedThreshold<T>>,
+}
+
+impl<T> Default for NamedThresholds<T> {
+    fn default() -> Self {
+        Self {
+            thresholds: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> NamedThresholds<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the thresholds. Thresholds given earlier for the same metric are replaced.
+    pub fn push(&mut self, threshold: NamedThreshold<T>) {
+        self.thresholds.insert(threshold.name.clone(), threshold);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&NamedThreshold<T>> {
+        self.thresholds.get(name)
+    }
+
+    /// Returns the names of all metrics with thresholds, e.g. to reject unknown names.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.thresholds.keys().map(String::as_str)
+    }
+}
+
+impl<T: Clone> NamedThresholds<T> {
+    /// Sets the threshold ranges for the metric with the same name, if any. Otherwise the metric
+    /// is returned unchanged.
+    pub fn apply(&self, metric: Metric<T>) -> Metric<T> {
+        match self.thresholds.get(&metric.name) {
+            Some(t) => metric.with_threshold_ranges(t.warning.clone(), t.critical.clone()),
+            None => metric,
+        }
+    }
+}
+
+impl<T> FromIterator<NamedThreshold<T>> for NamedThresholds<T> {
+    fn from_iter<I: IntoIterator<Item = NamedThreshold<T>>>(iter: I) -> Self {
+        let mut thresholds = Self::new();
+        for threshold in iter {
+            thresholds.push(threshold);
+        }
+        thresholds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckResult, ServiceState};
+
+    fn range<T>(s: &str) -> Option<ThresholdRange<T>>
+    where
+        T: FromStr + Default + PartialOrd,
+        T::Err: fmt::Display,
+    {
+        Some(s.parse().unwrap())
+    }
+
+    #[test]
+    fn test_parse() {
+        let threshold: NamedThreshold<f64> = "load1=4,8".parse().unwrap();
+        assert_eq!(threshold.name, "load1");
+        assert_eq!(threshold.warning, range("4"));
+        assert_eq!(threshold.critical, range("8"));
+
+        let threshold: NamedThreshold<u64> = "conns=,100".parse().unwrap();
+        assert_eq!(threshold.warning, None);
+        assert_eq!(threshold.critical, range("100"));
+
+        let threshold: NamedThreshold<u64> = "conns=50".parse().unwrap();
+        assert_eq!(threshold.warning, range("50"));
+
+        let threshold: NamedThreshold<f64> = "temp=10:20,@~:0".parse().unwrap();
+        assert_eq!(threshold.warning, Some(ThresholdRange::outside(10.0, 20.0)));
+        assert_eq!(threshold.critical, range("@~:0"));
+
+        assert!("load1".parse::<NamedThreshold<f64>>().is_err());
+        assert!("=1,2".parse::<NamedThreshold<f64>>().is_err());
+        assert!("load1=,".parse::<NamedThreshold<f64>>().is_err());
+        assert!("load1=a,2".parse::<NamedThreshold<f64>>().is_err());
+        assert!("load1=20:10".parse::<NamedThreshold<f64>>().is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let thresholds: NamedThresholds<i64> = ["a=1,2", "b=5,10", "a=3,4"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        assert_eq!(thresholds.names().collect::<Vec<_>>(), ["a", "b"]);
+
+        let result: CheckResult = thresholds.apply(Metric::new("a", 4)).into();
+        assert_eq!(result.state, Some(ServiceState::Warning));
+
+        let thresholds: NamedThresholds<i64> =
+            ["free=10:,5:".parse().unwrap()].into_iter().collect();
+        let result: CheckResult = thresholds.apply(Metric::new("free", 4)).into();
+        assert_eq!(result.state, Some(ServiceState::Critical));
+
+        let result: CheckResult = thresholds.apply(Metric::new("c", 100)).into();
+        assert_eq!(result.state, None);
+    }
+}
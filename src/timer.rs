@@ -0,0 +1,156 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{Metric, TriggerIfValue, Unit};
+
+type Thresholds = (Option<f64>, Option<f64>, TriggerIfValue);
+
+/// Measures the time of a step of a check and turns it into a metric in seconds. Call
+/// [MetricTimer::stop] to get the metric, or start the timer with [Timings::start] to have it
+/// recorded when the timer is dropped.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{MetricTimer, Resource};
+///
+/// let timer = MetricTimer::start("query_time");
+/// // run the query
+/// let resource = Resource::new("db").with_result(timer.stop());
+/// ```
+#[derive(Debug)]
+pub struct MetricTimer {
+    name: String,
+    start: Instant,
+    thresholds: Option<Thresholds>,
+    timings: Option<Timings>,
+}
+
+impl MetricTimer {
+    pub fn start(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            start: Instant::now(),
+            thresholds: Default::default(),
+            timings: Default::default(),
+        }
+    }
+
+    /// Sets the thresholds of the metric in seconds.
+    pub fn with_thresholds(
+        mut self,
+        warning: impl Into<Option<f64>>,
+        critical: impl Into<Option<f64>>,
+        trigger_if_value: TriggerIfValue,
+    ) -> Self {
+        self.thresholds = Some((warning.into(), critical.into(), trigger_if_value));
+        self
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Stops the timer and returns the metric. It is not recorded in the [Timings], if any.
+    pub fn stop(mut self) -> Metric<f64> {
+        self.timings = None;
+        self.metric()
+    }
+
+    fn metric(&self) -> Metric<f64> {
+        let metric =
+            Metric::new(self.name.clone(), self.elapsed().as_secs_f64()).with_unit(Unit::Seconds);
+        match self.thresholds {
+            Some((warning, critical, trigger)) => {
+                metric.with_thresholds(warning, critical, trigger)
+            }
+            None => metric,
+        }
+    }
+}
+
+impl Drop for MetricTimer {
+    fn drop(&mut self) {
+        if let Some(timings) = self.timings.take() {
+            let metric = self.metric();
+            timings.push(metric);
+        }
+    }
+}
+
+/// Collects the metrics of [MetricTimer]s which are dropped, e.g. at the end of a scope or when a
+/// step returns early with `?`. Clones share the collected metrics, so they can be passed to
+/// other threads.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{Resource, Timings};
+///
+/// let timings = Timings::new();
+/// {
+///     let _timer = timings.start("connect_time");
+///     // connect
+/// }
+/// let timer = timings.start("query_time");
+/// // run the query
+/// drop(timer);
+///
+/// let resource = Resource::from_metrics("db", timings.take());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Timings {
+    metrics: Arc<Mutex<Vec<Metric<f64>>>>,
+}
+
+impl Timings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a timer which is recorded in these timings when it is dropped.
+    pub fn start(&self, name: impl Into<String>) -> MetricTimer {
+        let mut timer = MetricTimer::start(name);
+        timer.timings = Some(self.clone());
+        timer
+    }
+
+    pub fn push(&self, metric: Metric<f64>) {
+        self.lock().push(metric);
+    }
+
+    /// Returns the collected metrics in the order the timers were dropped and clears them.
+    pub fn take(&self) -> Vec<Metric<f64>> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Metric<f64>>> {
+        // A panic while pushing can't leave the vector in an inconsistent state.
+        self.metrics.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timings() {
+        let timings = Timings::new();
+        {
+            let _timer = timings
+                .start("a")
+                .with_thresholds(0.0, None, TriggerIfValue::Greater);
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let metric = timings.start("b").stop();
+        assert_eq!(metric.name, "b");
+
+        let metrics = timings.take();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].name, "a");
+        assert!(metrics[0].value >= 0.005);
+        assert_eq!(metrics[0].unit, Unit::Seconds);
+        assert!(timings.take().is_empty());
+    }
+}
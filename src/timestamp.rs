@@ -0,0 +1,129 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Defines how an [OutputTimestamp] is formatted.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// UTC in RFC 3339 format with second precision, e.g. `2024-05-17T08:30:00Z`.
+    #[default]
+    Rfc3339,
+    /// Seconds since the Unix epoch.
+    Epoch,
+}
+
+/// The time the data of a [Resource] was collected, which is included in the output. Useful for
+/// cached or passive results, where the time of the measurement matters. See
+/// [Resource::with_timestamp].
+///
+/// [Resource]: crate::Resource
+/// [Resource::with_timestamp]: crate::Resource::with_timestamp
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputTimestamp {
+    time: SystemTime,
+    format: TimestampFormat,
+    in_long_output: bool,
+}
+
+impl OutputTimestamp {
+    pub fn new(time: SystemTime) -> Self {
+        Self {
+            time,
+            format: Default::default(),
+            in_long_output: false,
+        }
+    }
+
+    pub fn now() -> Self {
+        Self::new(SystemTime::now())
+    }
+
+    pub fn with_format(mut self, format: TimestampFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Puts the timestamp on a line of its own at the end of the long output instead of appending
+    /// it to the summary.
+    pub fn in_long_output(mut self) -> Self {
+        self.in_long_output = true;
+        self
+    }
+
+    pub(crate) fn is_in_long_output(&self) -> bool {
+        self.in_long_output
+    }
+
+    pub(crate) fn render(&self) -> String {
+        let formatted = match self.format {
+            TimestampFormat::Rfc3339 => rfc3339(self.time),
+            TimestampFormat::Epoch => epoch_seconds(self.time).to_string(),
+        };
+        format!("measured at {}", formatted)
+    }
+}
+
+/// Returns the seconds since the Unix epoch, negative for earlier times.
+pub(crate) fn epoch_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(err) => -(err.duration().as_secs() as i64),
+    }
+}
+
+/// Formats the time as UTC in RFC 3339 format with second precision.
+pub(crate) fn rfc3339(time: SystemTime) -> String {
+    let secs = epoch_seconds(time);
+    let (days, secs_of_day) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )
+}
+
+/// Converts days since the Unix epoch into a date of the proleptic Gregorian calendar, see
+/// <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_render() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_715_934_600);
+
+        assert_eq!(
+            OutputTimestamp::new(time).render(),
+            "measured at 2024-05-17T08:30:00Z"
+        );
+        assert_eq!(
+            OutputTimestamp::new(time)
+                .with_format(TimestampFormat::Epoch)
+                .render(),
+            "measured at 1715934600"
+        );
+        assert_eq!(rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            rfc3339(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+            "2000-02-29T00:00:00Z"
+        );
+    }
+}
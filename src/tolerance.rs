@@ -0,0 +1,111 @@
+use std::fmt;
+
+use crate::{CheckResult, ServiceState};
+
+/// Collects the results of many items, e.g. the volumes of a storage system, where a single item
+/// failing to be read shouldn't abort the whole run. Failed items become
+/// [ServiceState::Unknown] results with the error message. Only if the ratio of failed items
+/// exceeds the maximum, the check goes [ServiceState::Critical].
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{FailureTolerance, Metric, Resource, ServiceState};
+///
+/// fn read_usage(volume: &str) -> Result<Metric<u64>, String> {
+///     match volume {
+///         "vol3" => Err(format!("{}: permission denied", volume)),
+///         _ => Ok(Metric::new(volume, 42)),
+///     }
+/// }
+///
+/// let volumes = ["vol1", "vol2", "vol3", "vol4"];
+/// let results = FailureTolerance::new(0.5).collect(volumes, |v| read_usage(v));
+/// let resource = Resource::new("storage").with_results(results);
+///
+/// let (state, output) = resource.nagios_result();
+/// assert_eq!(state, ServiceState::Unknown);
+/// assert!(output.contains("vol3: permission denied"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FailureTolerance {
+    max_failure_ratio: f64,
+}
+
+impl FailureTolerance {
+    /// Creates an instance which tolerates the given ratio of failed items between 0 and 1.
+    pub fn new(max_failure_ratio: f64) -> Self {
+        Self {
+            max_failure_ratio: max_failure_ratio.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Calls the function for each item and returns the results. A
+    /// [ServiceState::Critical] result is added if too many items failed.
+    pub fn collect<T, R, E>(
+        &self,
+        items: impl IntoIterator<Item = T>,
+        mut f: impl FnMut(T) -> Result<R, E>,
+    ) -> Vec<CheckResult>
+    where
+        R: Into<CheckResult>,
+        E: fmt::Display,
+    {
+        let mut results = Vec::new();
+        let (mut total, mut failed) = (0, 0);
+
+        for item in items {
+            total += 1;
+            match f(item) {
+                Ok(result) => results.push(result.into()),
+                Err(err) => {
+                    failed += 1;
+                    results.push(
+                        CheckResult::new()
+                            .with_state(ServiceState::Unknown)
+                            .with_message(err.to_string()),
+                    );
+                }
+            }
+        }
+
+        if total > 0 && failed as f64 / total as f64 > self.max_failure_ratio {
+            results.push(
+                CheckResult::new()
+                    .with_state(ServiceState::Critical)
+                    .with_message(format!(
+                        "{} of {} items failed, more than the tolerated {}%",
+                        failed,
+                        total,
+                        self.max_failure_ratio * 100.0
+                    )),
+            );
+        }
+
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Metric, Resource};
+
+    #[test]
+    fn test_collect() {
+        let tolerance = FailureTolerance::new(0.25);
+        let read = |n: u32| match n % 2 {
+            0 => Ok(Metric::new(format!("item{}", n), n)),
+            _ => Err(format!("item{}: unreadable", n)),
+        };
+
+        let results = tolerance.collect([2, 4, 6, 7], read);
+        let (state, _) = Resource::new("items").with_results(results).nagios_result();
+        assert_eq!(state, ServiceState::Unknown);
+
+        let results = tolerance.collect([2, 3, 5], read);
+        let (state, output) = Resource::new("items").with_results(results).nagios_result();
+        assert_eq!(state, ServiceState::Critical);
+        assert!(output.contains("2 of 3 items failed, more than the tolerated 25%"));
+    }
+}
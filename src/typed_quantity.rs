@@ -0,0 +1,106 @@
+use uom::si::f64::{Information, Ratio, Time};
+use uom::si::{information, ratio, time};
+
+use crate::{Metric, TriggerIfValue, Unit};
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// A `uom` quantity which can be used as value of a [Metric], see [Metric::from_uom]. The value
+/// is converted into the base unit of the Nagios plugin guidelines for the quantity.
+///
+/// | Quantity      | Unit           |
+/// |---------------|----------------|
+/// | `Information` | [Unit::Bytes]  |
+/// | `Time`        | [Unit::Seconds] |
+/// | `Ratio`       | [Unit::Percentage] |
+pub trait UomQuantity: private::Sealed {
+    /// The unit of the values returned by [UomQuantity::base_value].
+    fn unit() -> Unit;
+
+    fn base_value(&self) -> f64;
+}
+
+macro_rules! impl_uom_quantity {
+    ($quantity:ty, $unit:expr, $base:ty) => {
+        impl private::Sealed for $quantity {}
+
+        impl UomQuantity for $quantity {
+            fn unit() -> Unit {
+                $unit
+            }
+
+            fn base_value(&self) -> f64 {
+                self.get::<$base>()
+            }
+        }
+    };
+}
+
+impl_uom_quantity!(Information, Unit::Bytes, information::byte);
+impl_uom_quantity!(Time, Unit::Seconds, time::second);
+impl_uom_quantity!(Ratio, Unit::Percentage, ratio::percent);
+
+impl Metric<f64> {
+    /// Creates a metric from a `uom` quantity. The unit is inferred from the quantity.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use nagiosplugin::{Metric, TriggerIfValue};
+    /// use uom::si::f64::Information;
+    /// use uom::si::information::{gigabyte, megabyte};
+    ///
+    /// let metric = Metric::from_uom("used", Information::new::<megabyte>(1500.0))
+    ///     .with_uom_thresholds(
+    ///         Information::new::<gigabyte>(1.0),
+    ///         Information::new::<gigabyte>(2.0),
+    ///         TriggerIfValue::Greater,
+    ///     );
+    /// ```
+    pub fn from_uom<Q: UomQuantity>(name: impl Into<String>, quantity: Q) -> Self {
+        Metric::new(name, quantity.base_value()).with_unit(Q::unit())
+    }
+
+    /// Like [Metric::with_thresholds], but with thresholds of the same quantity as used in
+    /// [Metric::from_uom].
+    pub fn with_uom_thresholds<Q: UomQuantity>(
+        self,
+        warning: impl Into<Option<Q>>,
+        critical: impl Into<Option<Q>>,
+        trigger_if_value: TriggerIfValue,
+    ) -> Self {
+        let warning = warning.into().map(|q| q.base_value());
+        let critical = critical.into().map(|q| q.base_value());
+        self.with_thresholds(warning, critical, trigger_if_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckResult, ServiceState};
+    use uom::si::information::kibibyte;
+    use uom::si::time::millisecond;
+
+    #[test]
+    fn test_from_uom() {
+        let result: CheckResult = Metric::from_uom("response", Time::new::<millisecond>(1500.0))
+            .with_uom_thresholds(
+                Time::new::<time::second>(1.0),
+                None,
+                TriggerIfValue::Greater,
+            )
+            .into();
+        assert_eq!(result.state, Some(ServiceState::Warning));
+        assert_eq!(result.perf_strings[0].to_string(), "'response'=1.5s;1;;;");
+
+        let metric = Metric::from_uom("size", Information::new::<kibibyte>(2.0));
+        assert_eq!(metric.value, 2048.0);
+        assert_eq!(metric.unit, Unit::Bytes);
+
+        let metric = Metric::from_uom("ratio", Ratio::new::<ratio::ratio>(0.25));
+        assert_eq!(metric.value, 25.0);
+    }
+}
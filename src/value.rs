@@ -0,0 +1,106 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::ToPerfString;
+
+/// A numeric value of one of several types, so metrics of different types can be handled alike,
+/// e.g. collected in one `Vec` for [Resource::from_metrics], without converting large counters to
+/// `f64` and losing precision.
+///
+/// Values of different types are compared by their numeric value. Integers are compared exactly,
+/// comparisons between integers and floats are done as `f64`.
+///
+/// ## Example
+///
+/// ```
+/// use nagiosplugin::{Metric, Resource, TriggerIfValue, Value};
+///
+/// let resource = Resource::from_metrics(
+///     "interface",
+///     vec![
+///         Metric::new("octets", Value::from(u64::MAX)),
+///         Metric::new("errors", Value::from(-1)),
+///         Metric::new("load", Value::from(0.25))
+///             .with_thresholds(Value::from(0.8), Value::from(1), TriggerIfValue::Greater),
+///     ],
+/// );
+/// ```
+///
+/// [Resource::from_metrics]: crate::Resource::from_metrics
+#[derive(Debug, Copy, Clone)]
+pub enum Value {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+}
+
+impl ToPerfString for Value {
+    fn to_perf_string(&self) -> String {
+        match self {
+            Value::Int(v) => v.to_perf_string(),
+            Value::UInt(v) => v.to_perf_string(),
+            Value::Float(v) => v.to_perf_string(),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_perf_string())
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (*self, *other) {
+            (Value::Int(a), Value::Int(b)) => a.partial_cmp(&b),
+            (Value::UInt(a), Value::UInt(b)) => a.partial_cmp(&b),
+            (Value::Float(a), Value::Float(b)) => a.partial_cmp(&b),
+            (Value::Int(a), Value::UInt(b)) => i128::from(a).partial_cmp(&i128::from(b)),
+            (Value::UInt(a), Value::Int(b)) => i128::from(a).partial_cmp(&i128::from(b)),
+            (Value::Int(a), Value::Float(b)) => (a as f64).partial_cmp(&b),
+            (Value::Float(a), Value::Int(b)) => a.partial_cmp(&(b as f64)),
+            (Value::UInt(a), Value::Float(b)) => (a as f64).partial_cmp(&b),
+            (Value::Float(a), Value::UInt(b)) => a.partial_cmp(&(b as f64)),
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+macro_rules! impl_from {
+    ($variant:ident, $target:ty, $($t:ty),*) => {
+        $(
+            impl From<$t> for Value {
+                fn from(v: $t) -> Self {
+                    Value::$variant(v as $target)
+                }
+            }
+        )*
+    };
+}
+
+impl_from!(Int, i64, i8, i16, i32, i64, isize);
+impl_from!(UInt, u64, u8, u16, u32, u64, usize);
+impl_from!(Float, f64, f32, f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_ordering() {
+        assert!(Value::from(-1) < Value::from(0u64));
+        assert!(Value::from(u64::MAX) > Value::from(i64::MAX));
+        assert!(Value::from(0.5) < Value::from(1));
+        assert_eq!(Value::from(2), Value::from(2.0));
+        assert_eq!(
+            Value::from(u64::MAX).to_perf_string(),
+            "18446744073709551615"
+        );
+    }
+}